@@ -1,5 +1,5 @@
 use torut::utils::{run_tor, AutoKillChild};
-use torut::control::{UnauthenticatedConn};
+use torut::control::AuthenticatedConn;
 use tokio::net::TcpStream;
 
 #[tokio::main]
@@ -15,12 +15,7 @@ async fn main() {
     let _child = AutoKillChild::new(child);
 
     let s = TcpStream::connect(&format!("127.0.0.1:{}", 47835)).await.unwrap();
-    let mut utc = UnauthenticatedConn::new(s);
-    let proto_info = utc.load_protocol_info().await.unwrap();
-    let ad = proto_info.make_auth_data().unwrap().unwrap();
-
-    utc.authenticate(&ad).await.unwrap();
-    let mut ac = utc.into_authenticated().await;
+    let mut ac = AuthenticatedConn::connect(s, None).await.unwrap();
     ac.set_async_event_handler(Some(|_| {
         async move { Ok(()) }
     }));
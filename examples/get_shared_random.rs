@@ -1,5 +1,5 @@
 use torut::utils::{run_tor, AutoKillChild};
-use torut::control::{UnauthenticatedConn, TorAuthMethod, TorAuthData};
+use torut::control::{AuthenticatedConn, TorAuthData};
 use tokio::net::TcpStream;
 
 use std::thread::sleep;
@@ -18,12 +18,7 @@ async fn main() {
     let _child = AutoKillChild::new(child);
 
     let s = TcpStream::connect(&format!("127.0.0.1:{}", 47835)).await.unwrap();
-    let mut utc = UnauthenticatedConn::new(s);
-    let proto_info = utc.load_protocol_info().await.unwrap();
-
-    assert!(proto_info.auth_methods.contains(&TorAuthMethod::Null), "Null authentication is not allowed");
-    utc.authenticate(&TorAuthData::Null).await.unwrap();
-    let mut ac = utc.into_authenticated().await;
+    let mut ac = AuthenticatedConn::connect(s, Some(TorAuthData::Null)).await.unwrap();
     ac.set_async_event_handler(Some(|_| {
         async move { Ok(()) }
     }));
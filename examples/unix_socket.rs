@@ -0,0 +1,28 @@
+// tor's ControlSocket is unix socket, so this example works on unix-like systems only
+
+#[cfg(unix)]
+#[tokio::main]
+async fn main() {
+    use tokio::net::UnixStream;
+    use torut::control::AuthenticatedConn;
+
+    // on most linux distributions tor(when ControlSocket is enabled) listens on /run/tor/control
+    // make sure that user running this example is allowed to access it(usually it means being in tor's group)
+    let path = std::env::var("TORUT_CONTROL_SOCKET").unwrap_or_else(|_| "/run/tor/control".to_string());
+
+    let s = UnixStream::connect(&path).await.unwrap();
+
+    // protocol info tells which auth method should be used(usually it's cookie auth for control socket)
+    let mut ac = AuthenticatedConn::connect(s, None).await.unwrap();
+    ac.set_async_event_handler(Some(|_| {
+        async move { Ok(()) }
+    }));
+
+    let version = ac.get_info("version").await.unwrap();
+    println!("Connected to tor {} via {}", version, path);
+}
+
+#[cfg(not(unix))]
+fn main() {
+    println!("Unix sockets are not supported on this platform");
+}
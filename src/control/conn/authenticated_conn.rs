@@ -1,12 +1,14 @@
 use std::borrow::Cow;
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
 use std::future::Future;
-use std::net::{Ipv4Addr, SocketAddr};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 use tokio::io::{AsyncRead, AsyncWrite};
 
-use crate::control::conn::{AuthenticatedConnError, Conn, ConnError};
-use crate::control::primitives::AsyncEvent;
-use crate::utils::{is_valid_event, is_valid_hostname, is_valid_keyword, is_valid_option, parse_single_key_value, quote_string, unquote_string};
+use crate::control::conn::{AuthenticatedConnError, Conn, ConnError, UnauthenticatedConn, UnauthenticatedConnError};
+#[cfg(feature = "v3")]
+use crate::control::primitives::AddOnionOptions;
+use crate::control::primitives::{AsyncEvent, AsyncEventKind, BootstrapPhase, DescSelector, CircuitStatusEvent, ConfValue, parse_conf_line, ConnState, ListenerAddr, OnionListener, OnionTarget, ResolveTarget, RouterStatus, StreamId, TorAuthData, TorSignal, VersionStatus};
+use crate::utils::{encode_multiline_data, is_valid_event, is_valid_feature, is_valid_fingerprint, is_valid_hostname, is_valid_keyword, is_valid_nickname, is_valid_option, parse_single_key_value, quote_string_cow, unquote_string};
 
 /// AuthenticatedConn represents connection to TorCP after it has been authenticated so one may
 /// perform various operations on it.
@@ -22,6 +24,9 @@ use crate::utils::{is_valid_event, is_valid_hostname, is_valid_keyword, is_valid
 /// Asynchronous handler will be awaited in current thread(no calls to `tokio::spawn` or stuff like that).
 /// Make sure that async handlers won't take long time to execute as this may cause latencies in handling other functions.
 ///
+/// Handler is `FnMut`, so it may mutate state it captured(like counter or vec of events) without `RefCell`.
+/// Note that future returned from handler can't borrow handler's state, so move everything it needs into it.
+///
 /// Please also note that this connection won't do anything in background to handle events.
 /// In order to trigger event handling(if any) use `noop` function.
 ///
@@ -32,13 +37,32 @@ use crate::utils::{is_valid_event, is_valid_hostname, is_valid_keyword, is_valid
 pub struct AuthenticatedConn<S, H> {
     async_event_handler: Option<H>,
     conn: Conn<S>,
+    max_get_info_line_length: usize,
+    // events which tor was successfully told to report and whether extended flag was used
+    events_extended: bool,
+    events: Vec<String>,
+    #[cfg(feature = "v3")]
+    track_onion_services: bool,
+    #[cfg(feature = "v3")]
+    onion_services: Vec<crate::control::primitives::OnionServiceState>,
 }
 
+/// DEFAULT_MAX_GET_INFO_LINE_LENGTH is default limit of length of single `GETINFO` line sent by `get_info_multiple`.
+/// Keys which do not fit are requested with next `GETINFO` command.
+pub const DEFAULT_MAX_GET_INFO_LINE_LENGTH: usize = 4096;
+
 impl<S, H> From<Conn<S>> for AuthenticatedConn<S, H> {
     fn from(conn: Conn<S>) -> Self {
         Self {
             async_event_handler: None,
             conn,
+            max_get_info_line_length: DEFAULT_MAX_GET_INFO_LINE_LENGTH,
+            events_extended: false,
+            events: Vec::new(),
+            #[cfg(feature = "v3")]
+            track_onion_services: false,
+            #[cfg(feature = "v3")]
+            onion_services: Vec::new(),
         }
     }
 }
@@ -48,6 +72,157 @@ impl<S, H> AuthenticatedConn<S, H> {
     pub fn set_async_event_handler(&mut self, handler: Option<H>) {
         self.async_event_handler = handler;
     }
+
+    /// set_max_get_info_line_length sets limit of length(including trailing CRLF) of single `GETINFO` line
+    /// sent by `get_info_multiple`. Default one is `DEFAULT_MAX_GET_INFO_LINE_LENGTH`.
+    ///
+    /// Key which does not fit into limit on its own is still sent, alone in its line.
+    pub fn set_max_get_info_line_length(&mut self, max_length: usize) {
+        self.max_get_info_line_length = max_length;
+    }
+
+    #[cfg(feature = "v3")]
+    /// set_track_onion_services enables or disables tracking of onion services added with `add_onion_v3_opts`
+    /// and adopted with `adopt_detached`, so they are included in state returned by `to_state`. It's disabled by default.
+    ///
+    /// Tracked services contain copies of their secret keys, which are kept in memory as long as connection lives.
+    /// Disabling tracking drops services tracked so far.
+    pub fn set_track_onion_services(&mut self, enabled: bool) {
+        self.track_onion_services = enabled;
+        if !enabled {
+            self.onion_services.clear();
+        }
+    }
+
+    /// into_inner returns underlying stream, for instance in order to reuse or close it explicitly
+    ///
+    /// Async event handler is dropped.
+    pub fn into_inner(self) -> S {
+        self.conn.into_inner()
+    }
+
+    /// get_ref returns reference to underlying stream
+    pub fn get_ref(&self) -> &S {
+        self.conn.get_ref()
+    }
+
+    /// subscribed_events returns names of events which tor reports to this connection,
+    /// as set with `set_events`, `subscribe_event` and `unsubscribe_event`.
+    pub fn subscribed_events(&self) -> &[String] {
+        &self.events
+    }
+
+    /// to_state returns snapshot of events this connection is subscribed to and onion services it has added,
+    /// so they can be restored later with `restore_state`.
+    ///
+    /// Onion services are included only if they were added while tracking was enabled with `set_track_onion_services`.
+    pub fn to_state(&self) -> ConnState {
+        ConnState {
+            events_extended: self.events_extended,
+            events: self.events.clone(),
+            #[cfg(feature = "v3")]
+            onion_services: self.onion_services.clone(),
+        }
+    }
+}
+
+impl<S, H> AuthenticatedConn<S, H>
+    where S: AsyncRead + AsyncWrite + Unpin
+{
+    /// connect creates `AuthenticatedConn` from raw stream connected to tor's control port.
+    /// It loads protocol info, authenticates and returns authenticated connection without async event handler set.
+    ///
+    /// If `auth` is `None`, authentication data is created with `TorPreAuthInfo::make_auth_data`,
+    /// which may read cookie file.
+    ///
+    /// # Error
+    /// It returns `UnauthenticatedConnError::NoAuthDataAvailable` when `auth` is `None` and `make_auth_data` is not able
+    /// to create authentication data. For instance password authentication requires passing `auth` explicitly.
+    /// It returns error when tor refuses authentication.
+    pub async fn connect(stream: S, auth: Option<TorAuthData<'_>>) -> Result<Self, ConnError> {
+        let mut utc = UnauthenticatedConn::new(stream);
+        let proto_info = utc.load_protocol_info().await?;
+        let auth = match auth {
+            Some(auth) => auth,
+            None => proto_info.make_auth_data()?
+                .ok_or(ConnError::UnauthenticatedConnError(UnauthenticatedConnError::NoAuthDataAvailable))?,
+        };
+        utc.authenticate(&auth).await?;
+        Ok(utc.into_authenticated().await)
+    }
+}
+
+/// connect opens tcp connection to tor's control port at given address and authenticates it.
+///
+/// It's shortcut for `TcpStream::connect` followed by `AuthenticatedConn::connect(stream, None)`, so protocol info is
+/// loaded and authentication data is created with `TorPreAuthInfo::make_auth_data`, which reads cookie file
+/// if needed. Returned connection has no async event handler set.
+///
+/// # Note
+/// Password can't be guessed, so tor which requires `HashedPassword` authentication has to be connected to with
+/// `AuthenticatedConn::connect` and explicit `TorAuthData::HashedPassword`.
+///
+/// # Error
+/// It returns `ConnError::IOError` if connection could not be opened.
+/// It returns `UnauthenticatedConnError::NoAuthDataAvailable` when no supported auth method is available.
+/// It returns error when tor refuses authentication.
+///
+/// # Example
+/// ```no_run
+/// # async fn doctest() -> Result<(), torut::control::ConnError> {
+/// use torut::control::{AsyncEvent, ConnError};
+///
+/// let mut conn = torut::control::connect("127.0.0.1:9051").await?;
+/// conn.set_async_event_handler(Some(|_: AsyncEvent<'static>| async move { Ok::<(), ConnError>(()) }));
+/// println!("Tor version: {}", conn.get_info("version").await?);
+/// # Ok(())
+/// # }
+/// ```
+pub async fn connect<H>(addr: impl tokio::net::ToSocketAddrs) -> Result<AuthenticatedConn<tokio::net::TcpStream, H>, ConnError> {
+    let stream = tokio::net::TcpStream::connect(addr).await?;
+    AuthenticatedConn::connect(stream, None).await
+}
+
+/// is_success_code returns true if code of reply to command which does not return any data means success.
+///
+/// According to torCP docs besides `250 OK` tor may reply with `251 Operation was unnecessary`,
+/// when it has declined to perform the operation but no harm was done. Both are treated as success.
+/// Commands which return data(like `GETINFO`) accept `250` only, since `251` carries no data.
+///
+/// # TorCP docs
+/// Ctrl+F `4. Replies`
+fn is_success_code(code: u16) -> bool {
+    code == 250 || code == 251
+}
+
+/// make_set_conf_call creates `SETCONF` line(including trailing CRLF) from given pairs.
+/// It returns `None` if there are no pairs, since there is nothing to send then.
+///
+/// Keys are validated with `is_valid_keyword` and values are quoted, so the only CRLF in result is the terminating one.
+/// It's separate from `set_conf_multiple` for testing and fuzzing purposes.
+pub(crate) fn make_set_conf_call<'a, 'b>(options: &mut impl Iterator<Item=(&'a str, Option<&'b str>)>) -> Result<Option<String>, ConnError> {
+    let mut call = String::new();
+    call.push_str("SETCONF");
+    let mut has_any_option = false;
+    for (k, value) in options {
+        has_any_option = true;
+        if !is_valid_keyword(k) {
+            return Err(ConnError::AuthenticatedConnError(AuthenticatedConnError::InvalidKeywordValue));
+        }
+        call.push(' ');
+        call.push_str(k);
+        if let Some(value) = value {
+            // string quoting makes value safe to use in context of connection
+            call.push_str("=\"");
+            call.push_str(&quote_string_cow(value.as_bytes()));
+            call.push('"');
+        }
+    }
+    if !has_any_option {
+        return Ok(None);
+    }
+    call.push_str("\r\n");
+    Ok(Some(call))
 }
 
 // parsing stuff here(read only for test + fuzzing purposes)
@@ -55,16 +230,50 @@ impl<S, H, F> AuthenticatedConn<S, H>
     where
         S: AsyncRead + Unpin,
     // there fns make use of event handler so it's needed
-        H: Fn(AsyncEvent<'static>) -> F,
+        H: FnMut(AsyncEvent<'static>) -> F,
         F: Future<Output=Result<(), ConnError>>,
 {
-    async fn handle_async_event(&self, event: AsyncEvent<'static>) -> Result<(), ConnError> {
-        if let Some(handler) = &self.async_event_handler {
+    async fn handle_async_event(&mut self, event: AsyncEvent<'static>) -> Result<(), ConnError> {
+        if let Some(handler) = &mut self.async_event_handler {
             (handler)(event).await?;
         }
         Ok(())
     }
 
+    /// is_alive checks if connection was not closed by tor without sending any command.
+    ///
+    /// Unlike `noop` it does not send `GETINFO version`, so it's cheap enough to be called in health check loop.
+    /// Asynchronous events are not handled by this function.
+    /// Take a look at `Conn::is_alive` for details and limitations.
+    pub async fn is_alive(&mut self) -> bool {
+        self.conn.is_alive().await
+    }
+
+    /// next_event waits for next asynchronous event and returns it instead of passing it to handler.
+    /// It's pull-based alternative to event handler, so events may be processed with loop like:
+    /// `while let Ok(event) = conn.next_event().await { ... }`.
+    ///
+    /// Commands may be sent between calls. Events received while waiting for command's response are still
+    /// passed to handler, since this connection can't queue them.
+    ///
+    /// # Error
+    /// `ConnError::InvalidResponseCode` is returned when tor sends something other than asynchronous event,
+    /// which means that connection got out of sync.
+    ///
+    /// # Note
+    /// It does not return `Stream`, since this crate does not depend on any crate which defines it.
+    /// It can be wrapped into one with something like `futures::stream::unfold` though.
+    pub async fn next_event(&mut self) -> Result<AsyncEvent<'static>, ConnError> {
+        let (code, lines) = self.conn.receive_data().await?;
+        if code != 650 {
+            return Err(ConnError::InvalidResponseCode(code));
+        }
+        Ok(AsyncEvent {
+            code,
+            lines: lines.into_iter().map(Cow::Owned).collect(),
+        })
+    }
+
     // recv response + handle async event until there are some
     async fn recv_response(&mut self) -> Result<(u16, Vec<String>), ConnError> {
         loop {
@@ -91,7 +300,7 @@ impl<S, H, F> AuthenticatedConn<S, H>
         let res_len = res.len();
 
         if code != 250 {
-            return Err(ConnError::InvalidResponseCode(code));
+            return Err(ConnError::from_response_code(code, res));
         }
         // ... followed by a final 250 OK
         if &res[res.len() - 1] != "OK" {
@@ -111,55 +320,46 @@ impl<S, H, F> AuthenticatedConn<S, H>
         Ok(result)
     }
 
-    async fn read_get_conf_response(&mut self) -> Result<HashMap<String, Vec<Option<String>>>, ConnError> {
+    #[cfg(feature = "v3")]
+    async fn read_onion_client_auth_view_response(&mut self) -> Result<Vec<crate::control::primitives::ClientAuthEntry>, ConnError> {
+        let (code, lines) = self.recv_response().await?;
+        if code != 250 {
+            return Err(ConnError::from_response_code(code, lines));
+        }
+        if lines.len() < 2 || !lines[0].starts_with("ONION_CLIENT_AUTH_VIEW") || lines[lines.len() - 1] != "OK" {
+            return Err(ConnError::InvalidFormat);
+        }
+        let mut res = Vec::new();
+        for line in &lines[1..lines.len() - 1] {
+            let entry = line.parse()
+                .map_err(|_| ConnError::InvalidFormat)?;
+            res.push(entry);
+        }
+        Ok(res)
+    }
+
+    // reads GETCONF response preserving order and casing of keys sent by tor
+    async fn read_get_conf_response_ordered(&mut self) -> Result<Vec<(String, Option<String>)>, ConnError> {
         let (code, res) = self.recv_response().await?;
         if code != 250 {
-            return Err(ConnError::InvalidResponseCode(code));
+            return Err(ConnError::from_response_code(code, res));
         }
+        let mut result = Vec::with_capacity(res.len());
+        for line in &res {
+            let (k, v) = parse_conf_line(line)
+                .ok_or(ConnError::InvalidFormat)?;
+            result.push((k.to_string(), v));
+        }
+        Ok(result)
+    }
+
+    async fn read_get_conf_response(&mut self) -> Result<HashMap<String, Vec<Option<String>>>, ConnError> {
         let mut result: HashMap<String, Vec<Option<String>>> = HashMap::new();
-        for line in res {
-            let mut is_default = true;
-            for c in line.as_bytes() {
-                if *c == b'=' {
-                    is_default = false;
-                }
-            }
-            if is_default {
-                if let Some(v) = result.get_mut(&line) {
-                    v.push(None);
-                } else {
-                    result.insert(line, vec![None]);
-                }
+        for (k, v) in self.read_get_conf_response_ordered().await? {
+            if let Some(result_list) = result.get_mut(&k) {
+                result_list.push(v);
             } else {
-                let (k, v) = parse_single_key_value(&line)
-                    .map_err(|_| ConnError::InvalidFormat)?;
-                // TODO(teawithsand): Apply some restrictions on what is key?
-                //  ensure unique keys?
-                /*
-                    According to torCP docs:
-                    ```
-                    Value may be a raw value or a quoted string.  Tor will try to use unquoted
-                    values except when the value could be misinterpreted through not being
-                    quoted. (Right now, Tor supports no such misinterpretable values for
-                    configuration options.)
-                    ```
-                */
-                let v = match unquote_string(v) {
-                    (Some(offset), Ok(unquoted)) if offset == v.len() - 1 => {
-                        unquoted.into_owned()
-                    }
-                    (None, Ok(unquoted)) => {
-                        unquoted.into_owned()
-                    }
-                    _ => {
-                        return Err(ConnError::InvalidFormat);
-                    }
-                };
-                if let Some(result_list) = result.get_mut(k) {
-                    result_list.push(Some(v));
-                } else {
-                    result.insert(k.to_string(), vec![Some(v)]);
-                }
+                result.insert(k, vec![v]);
             }
         }
         Ok(result)
@@ -169,7 +369,7 @@ impl<S, H, F> AuthenticatedConn<S, H>
 impl<S, F, H> AuthenticatedConn<S, H>
     where
         S: AsyncRead + AsyncWrite + Unpin,
-        H: Fn(AsyncEvent<'static>) -> F,
+        H: FnMut(AsyncEvent<'static>) -> F,
         F: Future<Output=Result<(), ConnError>>,
 {
     /// set_conf_multiple sends `SETCONF` command to remote tor instance
@@ -179,39 +379,29 @@ impl<S, F, H> AuthenticatedConn<S, H>
     /// `new_value` should not be a quoted string as it will be quoted during this function call before send.
     /// If `new_value` is `None` default value will be set for given configuration option.
     ///
+    /// Same key may be passed many times in order to set multi-valued option(like `SocksPort`).
+    /// Pairs are sent in given order and no deduplication is performed.
+    ///
     /// # Error
     /// It returns error when `config_option` variable is not valid tor keyword.
     /// It returns error when tor instance returns an error.
     pub async fn set_conf_multiple(&mut self, options: &mut impl Iterator<Item=(&str, Option<&str>)>) -> Result<(), ConnError>
     {
-        let mut call = String::new();
-        call.push_str("SETCONF");
-        let mut has_any_option = false;
-        for (k, value) in options {
-            has_any_option = true;
-            if !is_valid_keyword(k) {
-                return Err(ConnError::AuthenticatedConnError(AuthenticatedConnError::InvalidKeywordValue));
-            }
-            call.push(' ');
-            call.push_str(k);
-            if let Some(value) = value {
-                // string quoting makes value safe to use in context of connection
-                let value = quote_string(value.as_bytes());
-                call.push('=');
-                call.push_str(&value);
-            }
-        }
-        if !has_any_option {
-            return Ok(());
-        }
-        call.push_str("\r\n");
+        let call = make_set_conf_call(options)?;
+        self.send_set_conf_call(call).await
+    }
+
+    // sends line created with make_set_conf_call, nothing is sent if there is no line
+    async fn send_set_conf_call(&mut self, call: Option<String>) -> Result<(), ConnError> {
+        let call = match call {
+            Some(call) => call,
+            None => return Ok(()),
+        };
         self.conn.write_data(call.as_bytes()).await?;
 
-        // response parsing is simple
-        // no need for separate fn
-        let (code, _lines) = self.conn.receive_data().await?;
-        if code != 250 {
-            return Err(ConnError::InvalidResponseCode(code));
+        let (code, lines) = self.recv_response().await?;
+        if !is_success_code(code) {
+            return Err(ConnError::from_response_code(code, lines));
         }
         Ok(())
     }
@@ -221,7 +411,92 @@ impl<S, F, H> AuthenticatedConn<S, H>
         self.set_conf_multiple(&mut std::iter::once((option, value))).await
     }
 
+    /// set_conf_value is just like `set_conf` but accepts typed value, so bools and numbers are formatted
+    /// the way tor expects them.
+    ///
+    /// # Example
+    /// `conn.set_conf_value("DisableNetwork", true.into())` sends `SETCONF DisableNetwork="1"`
+    pub async fn set_conf_value(&mut self, option: &str, value: ConfValue<'_>) -> Result<(), ConnError> {
+        let value = value.to_conf_string();
+        self.set_conf(option, value.as_deref()).await
+    }
+
+    /// set_learn_circuit_build_timeout sets `LearnCircuitBuildTimeout` option, which makes tor adapt circuit build timeout
+    /// to observed network performance. Disabling it makes circuit build timeouts predictable, which is
+    /// useful in deterministic tests against tor.
+    pub async fn set_learn_circuit_build_timeout(&mut self, enabled: bool) -> Result<(), ConnError> {
+        self.set_conf_value("LearnCircuitBuildTimeout", enabled.into()).await
+    }
+
+    /// get_learn_circuit_build_timeout returns value of `LearnCircuitBuildTimeout` option.
+    /// If option has default value, tor's default(`true`) is returned.
+    ///
+    /// # Error
+    /// `ConnError::InvalidFormat` is returned if value returned by tor is not `0` nor `1`.
+    pub async fn get_learn_circuit_build_timeout(&mut self) -> Result<bool, ConnError> {
+        self.get_conf_bool("LearnCircuitBuildTimeout", true).await
+    }
+
+    /// set_leave_streams_unattached sets `__LeaveStreamsUnattached` option.
+    /// When it's enabled tor does not attach new streams to circuits itself, so controller has to do it with `ATTACHSTREAM`.
+    ///
+    /// Don't forget to disable it once manual attaching is done, otherwise tor does not connect any stream.
+    /// Note: there is no RAII guard which would do that automatically, since drop can't send command to tor.
+    pub async fn set_leave_streams_unattached(&mut self, enabled: bool) -> Result<(), ConnError> {
+        self.set_conf_value("__LeaveStreamsUnattached", enabled.into()).await
+    }
+
+    /// get_leave_streams_unattached returns value of `__LeaveStreamsUnattached` option.
+    /// If option has default value, tor's default(`false`) is returned.
+    ///
+    /// # Error
+    /// `ConnError::InvalidFormat` is returned if value returned by tor is not `0` nor `1`.
+    pub async fn get_leave_streams_unattached(&mut self) -> Result<bool, ConnError> {
+        self.get_conf_bool("__LeaveStreamsUnattached", false).await
+    }
+
+    async fn get_conf_bool(&mut self, config_option: &str, default: bool) -> Result<bool, ConnError> {
+        let mut res = self.get_conf(config_option).await?;
+        if res.len() != 1 {
+            return Err(ConnError::InvalidFormat);
+        }
+        match res.pop().unwrap().as_deref() {
+            None => Ok(default),
+            Some("1") => Ok(true),
+            Some("0") => Ok(false),
+            Some(_) => Err(ConnError::InvalidFormat),
+        }
+    }
+
     // TODO(teawithsand): multiple versions of get_conf for specific stuff
+    /// load_conf sends `LOADCONF` command which(according to torCP docs):
+    /// ```text
+    /// This command allows a controller to upload the text of a config file
+    /// to Tor over the control port.  This config file is then loaded as if
+    /// it had been read from disk.
+    /// ```
+    /// So whole configuration is replaced at once. Options which are not present in given text are reset to defaults,
+    /// so make sure that `ControlPort`(or `ControlSocket`) is set there as well.
+    ///
+    /// Text is dot-stuffed before sending so no line in it can terminate data block.
+    ///
+    /// # Error
+    /// `ConnError::TorError` is returned when tor rejects given config, for instance because it's invalid.
+    ///
+    /// # TorCP docs
+    /// Ctrl+F `3.19. LOADCONF`
+    pub async fn load_conf(&mut self, config_text: &str) -> Result<(), ConnError> {
+        let mut call = String::from("+LOADCONF\r\n");
+        call.push_str(&encode_multiline_data(config_text));
+        self.conn.write_data(call.as_bytes()).await?;
+
+        let (code, lines) = self.recv_response().await?;
+        if !is_success_code(code) {
+            return Err(ConnError::from_response_code(code, lines));
+        }
+        Ok(())
+    }
+
     /// get_conf sends `GETCONF` command to remote tor instance
     /// which gets one(or more but it's not implemented, use sequence of calls to this function)
     /// configuration value from tor.
@@ -274,6 +549,41 @@ impl<S, F, H> AuthenticatedConn<S, H>
         return Err(ConnError::InvalidFormat);
     }
 
+    /// get_conf_ordered sends `GETCONF` command with all given options at once.
+    ///
+    /// # Return value
+    /// Unlike `get_conf` it returns pairs of key and value in order tor has sent them and keys are left as tor returned them,
+    /// so casing is tor's canonical one(for instance `SOCKSPORT` is returned as `SocksPort`).
+    /// Options with multiple values occur multiple times. Value is `None` if option is set to default value.
+    ///
+    /// It's useful for reading configuration, tweaking it and writing it back.
+    ///
+    /// # Error
+    /// `AuthenticatedConnError::InvalidKeywordValue` is returned if one of provided options is invalid keyword.
+    ///
+    /// # TorCP docs
+    /// Ctrl+F in torCP docs: `3.3. GETCONF`
+    pub async fn get_conf_ordered(&mut self, options: &mut impl Iterator<Item=&str>) -> Result<Vec<(String, Option<String>)>, ConnError> {
+        let mut call = String::new();
+        call.push_str("GETCONF");
+        let mut is_empty = true;
+        for option in options {
+            if !is_valid_keyword(option) {
+                return Err(ConnError::AuthenticatedConnError(AuthenticatedConnError::InvalidKeywordValue));
+            }
+            is_empty = false;
+            call.push(' ');
+            call.push_str(option);
+        }
+        if is_empty {
+            return Ok(Vec::new());
+        }
+        call.push_str("\r\n");
+        self.conn.write_data(call.as_bytes()).await?;
+
+        self.read_get_conf_response_ordered().await
+    }
+
     /// get_info_multiple sends `GETINFO` command to remote tor controller.
     /// Unlike `GETCONF` it may get values which are not part of tor's configuration.
     ///
@@ -284,12 +594,17 @@ impl<S, F, H> AuthenticatedConn<S, H>
     /// If same key was provided two or more times it's value will occur in result these amount of times.
     /// Values are fetched directly from tor so they probably are same but take a look at torCP docs to be sure about that.
     ///
+    /// # Batching
+    /// If keys do not fit into single line(see `set_max_get_info_line_length`) they are requested with many `GETINFO`
+    /// commands and results are merged.
+    ///
     /// # Error
     /// `AuthenticatedConnError::InvalidKeywordValue` is returned if one of provided options is invalid option value and may
     /// break control flow integrity of transmission.
     pub async fn get_info_multiple(&mut self, options: &mut impl Iterator<Item=&str>) -> Result<HashMap<String, Vec<String>>, ConnError> {
+        // long lists of keys are split into many GETINFO calls, so no line is longer than limit
+        let mut calls = Vec::new();
         let mut call = String::new();
-        call.push_str("GETINFO");
         let mut keys = HashMap::new();
         for option in options {
             if !is_valid_option(option) {
@@ -300,16 +615,30 @@ impl<S, F, H> AuthenticatedConn<S, H>
             } else {
                 keys.insert(option, 1usize);
             }
+            // +3 for space before key and trailing CRLF
+            if !call.is_empty() && call.len() + option.len() + 3 > self.max_get_info_line_length {
+                call.push_str("\r\n");
+                calls.push(std::mem::take(&mut call));
+            }
+            if call.is_empty() {
+                call.push_str("GETINFO");
+            }
             call.push(' ');
             call.push_str(option);
         }
-        call.push_str("\r\n");
         if keys.len() == 0 {
             return Ok(HashMap::new());
         }
-        self.conn.write_data(call.as_bytes()).await?;
+        call.push_str("\r\n");
+        calls.push(call);
 
-        let res = self.read_get_info_response().await?;
+        let mut res: HashMap<String, Vec<String>> = HashMap::new();
+        for call in calls {
+            self.conn.write_data(call.as_bytes()).await?;
+            for (key, values) in self.read_get_info_response().await? {
+                res.entry(key).or_default().extend(values);
+            }
+        }
         if res.len() != keys.len() {
             return Err(ConnError::InvalidFormat);
         }
@@ -361,6 +690,58 @@ impl<S, F, H> AuthenticatedConn<S, H>
     }
 
 
+    /// get_info_streaming works like `get_info` but it does not load whole value into memory.
+    /// It's designed for huge values like `dir/status-vote/current/consensus`.
+    ///
+    /// Each line of value is passed to `sink` as soon as it's received.
+    /// If tor returns single line value(not multiline block), it's passed to `sink` as single line.
+    ///
+    /// # Error
+    /// It returns error when `option` is not valid `GETINFO` keyword.
+    /// It returns error when tor instance returns an error, for instance when there is no such info key.
+    /// Some lines may have been passed to `sink` already in such case.
+    pub async fn get_info_streaming(&mut self, option: &str, mut sink: impl FnMut(&str)) -> Result<(), ConnError> {
+        if !is_valid_option(option) {
+            return Err(ConnError::AuthenticatedConnError(AuthenticatedConnError::InvalidKeywordValue));
+        }
+        self.conn.write_data(format!("GETINFO {}\r\n", option).as_bytes()).await?;
+
+        loop {
+            let (code, lines) = self.conn.receive_data_streaming(&mut |code, line| {
+                // async event has to be handled as whole
+                if code == 650 {
+                    return false;
+                }
+                sink(line);
+                true
+            }).await?;
+            if code == 650 {
+                self.handle_async_event(AsyncEvent {
+                    code,
+                    lines: lines.into_iter().map(Cow::Owned).collect(),
+                }).await?;
+                continue;
+            }
+            if code != 250 {
+                return Err(ConnError::from_response_code(code, lines));
+            }
+            // ... followed by a final 250 OK
+            if lines.len() != 2 || lines[1] != "OK" {
+                return Err(ConnError::InvalidFormat);
+            }
+            let (k, v) = parse_single_key_value(&lines[0])
+                .map_err(|_| ConnError::InvalidFormat)?;
+            if k != option {
+                return Err(ConnError::InvalidFormat);
+            }
+            // multiline block was already streamed and its reply line contains only `key=`
+            if !v.is_empty() {
+                sink(v);
+            }
+            return Ok(());
+        }
+    }
+
     /// get_info_unquote is just like `get_info` but rather than using `self.get_info_multiple` under the hood id uses `self.get_info_multiple_unquote`
     pub async fn get_info_unquote(&mut self, option: &str) -> Result<String, ConnError> {
         let res = self.get_info_multiple_unquote(&mut std::iter::once(option)).await?;
@@ -376,83 +757,434 @@ impl<S, F, H> AuthenticatedConn<S, H>
     }
 
 
-    /// drop_guards invokes `DROPGUARDS` which(according to torCP docs):
+    /// signal sends `SIGNAL` command which makes tor behave like it received given signal.
     ///
-    /// ```text
-    /// Tells the server to drop all guard nodes. Do not invoke this command
-    /// lightly; it can increase vulnerability to tracking attacks over time.
-    /// ```
-    pub async fn drop_guards(&mut self) -> Result<(), ConnError> {
-        self.conn.write_data(b"DROPGUARDS\r\n").await?;
-        let (code, _) = self.recv_response().await?;
-        if code != 250 {
-            return Err(ConnError::InvalidResponseCode(code));
+    /// # Return value
+    /// Tor replies with `250 OK` even if signal had no effect. For instance when `NEWNYM` is sent too often
+    /// tor rate-limits it and only logs notice about that. So all reply lines are returned and caller may inspect
+    /// them, but there is no guarantee that new identity was taken.
+    /// Use `is_circuit_established` to check if tor is able to build circuits at all.
+    ///
+    /// # TorCP docs
+    /// Ctrl+F `3.7. SIGNAL`
+    pub async fn signal(&mut self, signal: TorSignal) -> Result<Vec<String>, ConnError> {
+        // signal's display is its keyword so it's safe to send
+        self.conn.write_data(format!("SIGNAL {}\r\n", signal).as_bytes()).await?;
+        let (code, lines) = self.recv_response().await?;
+        if !is_success_code(code) {
+            return Err(ConnError::from_response_code(code, lines));
         }
-        Ok(())
+        Ok(lines)
     }
 
-    /// take_ownership invokes `TAKEOWNERSHIP` which(according to torCP docs):
-    ///
-    /// ```text
-    /// This command instructs Tor to shut down when this control
-    /// connection is closed. This command affects each control connection
-    /// that sends it independently; if multiple control connections send
-    /// the TAKEOWNERSHIP command to a Tor instance, Tor will shut down when
-    /// any of those connections closes.
-    /// ```
-    pub async fn take_ownership(&mut self) -> Result<(), ConnError> {
-        self.conn.write_data(b"TAKEOWNERSHIP\r\n").await?;
-        let (code, _) = self.recv_response().await?;
-        if code != 250 {
-            return Err(ConnError::InvalidResponseCode(code));
+    /// is_circuit_established returns value of `status/circuit-established` info key, which tells whether
+    /// tor has built circuit successfully and it's ready to be used.
+    pub async fn is_circuit_established(&mut self) -> Result<bool, ConnError> {
+        match &self.get_info("status/circuit-established").await? as &str {
+            "1" => Ok(true),
+            "0" => Ok(false),
+            _ => Err(ConnError::InvalidFormat),
         }
-        Ok(())
     }
 
-    /// drop_ownership invokes `DROPOWNERSHIP` which(according to torCP docs):
+    /// is_dormant returns value of `dormant` info key, which tells whether tor is in dormant mode.
+    /// Dormant mode is entered and left with `TorSignal::Dormant` and `TorSignal::Active`.
     ///
-    /// ```text
-    /// This command instructs Tor to relinquish ownership of its control
-    /// connection. As such tor will not shut down when this control
-    /// connection is closed.
-    /// ```
-    pub async fn drop_ownership(&mut self) -> Result<(), ConnError> {
-        self.conn.write_data(b"DROPOWNERSHIP\r\n").await?;
-        let (code, _) = self.recv_response().await?;
-        if code != 250 {
-            return Err(ConnError::InvalidResponseCode(code));
+    /// # Error
+    /// Tor older than 0.4.0 does not know this key and replies with `552` code
+    /// (returned as `ConnError::TorError(TorErrorKind::UnrecognizedEntity, _)`).
+    /// `ConnError::InvalidFormat` is returned if value returned by tor is not `0` nor `1`.
+    pub async fn is_dormant(&mut self) -> Result<bool, ConnError> {
+        match &self.get_info("dormant").await? as &str {
+            "1" => Ok(true),
+            "0" => Ok(false),
+            _ => Err(ConnError::InvalidFormat),
         }
-        Ok(())
     }
 
-    // TODO(teawithsand): make async resolve with custom future with tokio which parses async event
-    //  and then notifies caller using waker
-    //  same for reverse_resolve
+    /// bootstrap_phase returns parsed value of `status/bootstrap-phase` info key, which tells how far tor is
+    /// with bootstrapping.
+    ///
+    /// It's pull-based alternative to listening for `BOOTSTRAP` action of `STATUS_CLIENT` event(see `StatusEvent`).
+    ///
+    /// # Error
+    /// `ConnError::InvalidFormat` is returned if value returned by tor could not be parsed.
+    pub async fn bootstrap_phase(&mut self) -> Result<BootstrapPhase, ConnError> {
+        self.get_info("status/bootstrap-phase").await?
+            .parse()
+            .map_err(|_| ConnError::InvalidFormat)
+    }
 
-    /// resolve performs dns lookup over tor. It invokes `RESOLVE` command which(according to torCP docs):
-    /// ```text
-    /// This command launches a remote hostname lookup request for every specified
-    /// request (or reverse lookup if "mode=reverse" is specified).
-    /// ```
-    /// Note: there is separate function for reverse requests.
+    /// version_status returns what tor thinks about its own version(`status/version/current` info key),
+    /// so controller may warn user that tor in use is outdated.
     ///
-    /// # Result
-    /// Result is passed as `ADDRMAP` event so one should setup event listener to use it.
-    /// It's `NewAddressMapping` event.
-    pub async fn resolve(&mut self, hostname: &str) -> Result<(), ConnError> {
-        if is_valid_hostname(hostname) {
-            return Err(ConnError::AuthenticatedConnError(AuthenticatedConnError::InvalidHostnameValue));
-        }
+    /// Values not known to torut are returned as `VersionStatus::Unknown`.
+    pub async fn version_status(&mut self) -> Result<VersionStatus, ConnError> {
+        Ok(VersionStatus::from(self.get_info("status/version/current").await?.as_str()))
+    }
 
-        self.conn.write_data(&format!("RESOLVE {}\r\n", hostname).as_bytes()).await?;
-        let (code, _) = self.recv_response().await?;
-        if code != 250 {
-            return Err(ConnError::InvalidResponseCode(code));
+    /// get_listeners returns addresses tor is listening on for given kind of listener(`net/listeners/<kind>` info key).
+    /// Kind is something like `socks`, `control`, `dir` or `or`.
+    ///
+    /// Empty vector is returned when there are no listeners of given kind, for instance when `SocksPort` is `0`.
+    ///
+    /// # Error
+    /// It returns error when `kind` is not valid keyword.
+    /// `ConnError::InvalidFormat` is returned if any listener returned by tor could not be parsed.
+    pub async fn get_listeners(&mut self, kind: &str) -> Result<Vec<ListenerAddr>, ConnError> {
+        if !is_valid_keyword(kind) {
+            return Err(ConnError::AuthenticatedConnError(AuthenticatedConnError::InvalidKeywordValue));
         }
-        Ok(())
+        let value = self.get_info(&format!("net/listeners/{}", kind)).await?;
+        crate::control::primitives::parse_listeners(&value).ok_or(ConnError::InvalidFormat)
     }
 
-    /// resolve performs reverse dns lookup over tor. It invokes `RESOLVE` command which(according to torCP docs):
+    /// get_circuits returns all circuits tor knows about, parsed from `circuit-status` info key.
+    ///
+    /// # Error
+    /// `ConnError::InvalidFormat` is returned if any circuit returned by tor could not be parsed.
+    pub async fn get_circuits(&mut self) -> Result<Vec<CircuitStatusEvent>, ConnError> {
+        let text = self.get_info("circuit-status").await?;
+        crate::control::primitives::parse_circuit_status(&text).ok_or(ConnError::InvalidFormat)
+    }
+
+    /// get_circuits_by_purpose works like `get_circuits` but returns only circuits with given `PURPOSE`,
+    /// like `GENERAL` or `HS_SERVICE_REND`.
+    ///
+    /// Tor has no way of filtering circuits on its side, so all of them are fetched and filtered here.
+    /// Circuits without `PURPOSE` argument never match.
+    pub async fn get_circuits_by_purpose(&mut self, purpose: &str) -> Result<Vec<CircuitStatusEvent>, ConnError> {
+        let mut circuits = self.get_circuits().await?;
+        circuits.retain(|c| c.purpose.as_deref() == Some(purpose));
+        Ok(circuits)
+    }
+
+    #[cfg(feature = "v3")]
+    /// get_current_onions returns onion services owned by this control connection(`onions/current` info key).
+    ///
+    /// Services created with `detach` flag are not included here, see `get_detached_onions`.
+    ///
+    /// # Error
+    /// `ConnError::InvalidFormat` is returned if any service id returned by tor is not valid v3 onion address.
+    pub async fn get_current_onions(&mut self) -> Result<Vec<crate::onion::OnionAddressV3>, ConnError> {
+        self.get_onions("onions/current").await
+    }
+
+    #[cfg(feature = "v3")]
+    /// get_detached_onions returns detached onion services(`onions/detached` info key).
+    /// These are not owned by any control connection and live until they are removed with `del_onion` or tor exits.
+    ///
+    /// # Error
+    /// `ConnError::InvalidFormat` is returned if any service id returned by tor is not valid v3 onion address.
+    pub async fn get_detached_onions(&mut self) -> Result<Vec<crate::onion::OnionAddressV3>, ConnError> {
+        self.get_onions("onions/detached").await
+    }
+
+    #[cfg(feature = "v3")]
+    /// adopt_detached lists detached onion services(just like `get_detached_onions`) and takes over tracking of
+    /// the ones which match any of given services, so they are included in state returned by `to_state`.
+    ///
+    /// Services are matched locally by onion address derived from their keys, so keys are never sent to tor.
+    /// It's meant to be used after controller restart with services from state saved before, like:
+    /// `conn.adopt_detached(&mut state.onion_services.iter())`.
+    ///
+    /// Services which are already tracked by this connection and services which are not detached anymore are skipped.
+    /// Pass `std::iter::empty()` in order to just list detached services.
+    /// Nothing is taken over unless tracking is enabled with `set_track_onion_services`.
+    ///
+    /// # Return value
+    /// All detached onion services, including ones without matching service.
+    pub async fn adopt_detached<'a>(
+        &mut self,
+        services: &mut impl Iterator<Item=&'a crate::control::primitives::OnionServiceState>,
+    ) -> Result<Vec<crate::onion::OnionAddressV3>, ConnError> {
+        let detached = self.get_detached_onions().await?;
+        if !self.track_onion_services {
+            return Ok(detached);
+        }
+        for service in services {
+            let address = service.key.public().get_onion_address();
+            if !detached.contains(&address) {
+                continue;
+            }
+            if self.onion_services.iter().any(|s| s.key.public().get_onion_address() == address) {
+                continue;
+            }
+            self.onion_services.push(service.clone());
+        }
+        Ok(detached)
+    }
+
+    #[cfg(feature = "v3")]
+    async fn get_onions(&mut self, key: &str) -> Result<Vec<crate::onion::OnionAddressV3>, ConnError> {
+        let value = match self.get_info(key).await {
+            Ok(v) => v,
+            // tor replies with `551 No onion services of the specified type.` rather than empty value
+            // other `551` replies are real errors, so only this exact message means that there are no services
+            Err(ConnError::TorError(crate::control::TorErrorKind::InternalError, ref lines))
+            if lines.len() == 1 && lines[0] == "No onion services of the specified type." => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        value.lines()
+            .map(|l| l.trim())
+            .filter(|l| !l.is_empty())
+            .map(|l| l.parse().map_err(|_| ConnError::InvalidFormat))
+            .collect()
+    }
+
+    /// get_info_names returns all info keys supported by tor(`info/names` info key) with their descriptions.
+    /// It may be used to check if tor supports given key(like `md/all`) before requesting it.
+    ///
+    /// Keys which take an argument end with `/*`, like `config/*`. They are returned the way tor sends them.
+    ///
+    /// # Error
+    /// `ConnError::InvalidFormat` is returned if any line is not in `key -- description` format.
+    pub async fn get_info_names(&mut self) -> Result<Vec<(String, String)>, ConnError> {
+        self.get_info("info/names").await?
+            .lines()
+            .filter(|l| !l.trim().is_empty())
+            .map(|l| {
+                let mut parts = l.splitn(2, " -- ");
+                match (parts.next().map(|k| k.trim()), parts.next()) {
+                    (Some(key), Some(description)) if !key.is_empty() => {
+                        Ok((key.to_string(), description.trim().to_string()))
+                    }
+                    _ => Err(ConnError::InvalidFormat),
+                }
+            })
+            .collect()
+    }
+
+    /// get_config_text returns effective configuration of tor in torrc format(`config-text` info key).
+    ///
+    /// Text is returned as sent by tor, so lines are separated with `\r\n`.
+    pub async fn get_config_text(&mut self) -> Result<String, ConnError> {
+        let text = self.get_info("config-text").await?;
+        // multiline value starts right after `config-text=` so there is line break before first line
+        match text.strip_prefix("\r\n") {
+            Some(stripped) => Ok(stripped.to_string()),
+            None => Ok(text),
+        }
+    }
+
+    /// get_config_lines works like `get_config_text` but splits each line into option name and its value.
+    ///
+    /// # Return value
+    /// Pairs of option name and value in order sent by tor. Option without value has empty one.
+    /// Empty lines and comments are skipped.
+    pub async fn get_config_lines(&mut self) -> Result<Vec<(String, String)>, ConnError> {
+        Ok(self.get_config_text().await?
+            .lines()
+            .map(|l| l.trim())
+            .filter(|l| !l.is_empty() && !l.starts_with('#'))
+            .map(|l| {
+                let mut parts = l.splitn(2, char::is_whitespace);
+                let key = parts.next().unwrap_or("");
+                let value = parts.next().unwrap_or("").trim();
+                (key.to_string(), value.to_string())
+            })
+            .collect())
+    }
+
+    /// get_router_status returns router status entry of relay with given fingerprint(`ns/id/<fingerprint>` info key).
+    /// Fingerprint may be prefixed with `$`.
+    ///
+    /// # Error
+    /// `AuthenticatedConnError::InvalidFingerprint` is returned if fingerprint is not valid.
+    /// `ConnError::InvalidFormat` is returned if entry returned by tor could not be parsed.
+    /// Tor returns an error when it does not know such relay.
+    pub async fn get_router_status(&mut self, fingerprint: &str) -> Result<RouterStatus, ConnError> {
+        if !is_valid_fingerprint(fingerprint) {
+            return Err(ConnError::AuthenticatedConnError(AuthenticatedConnError::InvalidFingerprint));
+        }
+        self.get_info(&format!("ns/id/{}", fingerprint)).await?
+            .parse()
+            .map_err(|_| ConnError::InvalidFormat)
+    }
+
+    /// get_descriptor returns raw server descriptor of selected relay(`desc/id/<fingerprint>` or `desc/name/<nickname>` info key).
+    ///
+    /// Tor has server descriptors only when it does not use microdescriptors, for instance when it runs as relay or
+    /// `UseMicrodescriptors 0` or `FetchUselessDescriptors 1` is set.
+    ///
+    /// # Error
+    /// `AuthenticatedConnError::InvalidFingerprint` or `AuthenticatedConnError::InvalidNickname` is returned
+    /// if relay selector is not valid.
+    /// When tor does not know such relay it replies with `552` code, which is returned as
+    /// `ConnError::TorError(TorErrorKind::UnrecognizedEntity, _)`.
+    ///
+    /// # TorCP docs
+    /// Ctrl+F `desc/id/<OR identity>`
+    pub async fn get_descriptor(&mut self, selector: DescSelector) -> Result<String, ConnError> {
+        let key = match selector {
+            DescSelector::ByFingerprint(fingerprint) => {
+                if !is_valid_fingerprint(&fingerprint) {
+                    return Err(ConnError::AuthenticatedConnError(AuthenticatedConnError::InvalidFingerprint));
+                }
+                format!("desc/id/{}", fingerprint)
+            }
+            DescSelector::ByNickname(nickname) => {
+                if !is_valid_nickname(&nickname) {
+                    return Err(ConnError::AuthenticatedConnError(AuthenticatedConnError::InvalidNickname));
+                }
+                format!("desc/name/{}", nickname)
+            }
+        };
+        let res = self.get_info(&key).await?;
+        // multiline value starts with CRLF which separates it from reply line
+        Ok(res.strip_prefix("\r\n").map(|v| v.to_string()).unwrap_or(res))
+    }
+
+    /// external_address returns tor's best guess of its external ip address(`address` info key).
+    ///
+    /// # Error
+    /// Tor replies with `551` code(returned as `ConnError::TorError(TorErrorKind::InternalError, _)`) when it hasn't learned its address yet.
+    /// `ConnError::InvalidFormat` is returned if value returned by tor is not valid ip address.
+    pub async fn external_address(&mut self) -> Result<IpAddr, ConnError> {
+        self.get_info("address").await?
+            .parse()
+            .map_err(|_| ConnError::InvalidFormat)
+    }
+
+    /// external_address_v6 works like `external_address` but returns tor's ipv6 address(`address/v6` info key).
+    /// This key is supported by tor 0.4.5 and newer.
+    ///
+    /// # Error
+    /// Same as for `external_address`. `ConnError::InvalidFormat` is returned if address is not ipv6 one.
+    pub async fn external_address_v6(&mut self) -> Result<IpAddr, ConnError> {
+        match self.get_info("address/v6").await?.parse() {
+            Ok(addr @ IpAddr::V6(_)) => Ok(addr),
+            _ => Err(ConnError::InvalidFormat),
+        }
+    }
+
+    /// traffic_read returns total number of bytes read by tor since it was started(`traffic/read` info key)
+    pub async fn traffic_read(&mut self) -> Result<u64, ConnError> {
+        Ok(self.get_info("traffic/read").await?.parse()?)
+    }
+
+    /// traffic_written returns total number of bytes written by tor since it was started(`traffic/written` info key)
+    pub async fn traffic_written(&mut self) -> Result<u64, ConnError> {
+        Ok(self.get_info("traffic/written").await?.parse()?)
+    }
+
+    /// drop_guards invokes `DROPGUARDS` which(according to torCP docs):
+    ///
+    /// ```text
+    /// Tells the server to drop all guard nodes. Do not invoke this command
+    /// lightly; it can increase vulnerability to tracking attacks over time.
+    /// ```
+    pub async fn drop_guards(&mut self) -> Result<(), ConnError> {
+        self.conn.write_data(b"DROPGUARDS\r\n").await?;
+        let (code, _) = self.recv_response().await?;
+        if !is_success_code(code) {
+            return Err(ConnError::InvalidResponseCode(code));
+        }
+        Ok(())
+    }
+
+    /// drop_timeouts invokes `DROPTIMEOUTS` which(according to torCP docs):
+    ///
+    /// ```text
+    /// Tells the server to drop all circuit build times. Do not invoke this command
+    /// lightly; it can increase vulnerability to tracking attacks over time.
+    /// ```
+    ///
+    /// # Error
+    /// Tor versions which do not support this command reply with 510 code. In that case
+    /// `ConnError::TorError` with `TorErrorKind::UnrecognizedCmd` is returned.
+    pub async fn drop_timeouts(&mut self) -> Result<(), ConnError> {
+        self.conn.write_data(b"DROPTIMEOUTS\r\n").await?;
+        let (code, lines) = self.recv_response().await?;
+        if !is_success_code(code) {
+            return Err(ConnError::from_response_code(code, lines));
+        }
+        Ok(())
+    }
+
+    /// take_ownership invokes `TAKEOWNERSHIP` which(according to torCP docs):
+    ///
+    /// ```text
+    /// This command instructs Tor to shut down when this control
+    /// connection is closed. This command affects each control connection
+    /// that sends it independently; if multiple control connections send
+    /// the TAKEOWNERSHIP command to a Tor instance, Tor will shut down when
+    /// any of those connections closes.
+    /// ```
+    pub async fn take_ownership(&mut self) -> Result<(), ConnError> {
+        self.conn.write_data(b"TAKEOWNERSHIP\r\n").await?;
+        let (code, _) = self.recv_response().await?;
+        if !is_success_code(code) {
+            return Err(ConnError::InvalidResponseCode(code));
+        }
+        Ok(())
+    }
+
+    /// take_ownership_with_pid sets `__OwningControllerProcess` to given pid and then invokes `TAKEOWNERSHIP`.
+    ///
+    /// This way tor shuts down when control connection is closed and it also exits when process with given pid
+    /// dies, even if connection was not closed cleanly(for instance because controller was killed).
+    pub async fn take_ownership_with_pid(&mut self, pid: u32) -> Result<(), ConnError> {
+        self.set_conf("__OwningControllerProcess", Some(&pid.to_string())).await?;
+        self.take_ownership().await
+    }
+
+    /// take_ownership_with_current_pid is just like `take_ownership_with_pid` but it uses pid of current process
+    pub async fn take_ownership_with_current_pid(&mut self) -> Result<(), ConnError> {
+        self.take_ownership_with_pid(std::process::id()).await
+    }
+
+    /// drop_ownership invokes `DROPOWNERSHIP` which(according to torCP docs):
+    ///
+    /// ```text
+    /// This command instructs Tor to relinquish ownership of its control
+    /// connection. As such tor will not shut down when this control
+    /// connection is closed.
+    /// ```
+    pub async fn drop_ownership(&mut self) -> Result<(), ConnError> {
+        self.conn.write_data(b"DROPOWNERSHIP\r\n").await?;
+        let (code, _) = self.recv_response().await?;
+        if !is_success_code(code) {
+            return Err(ConnError::InvalidResponseCode(code));
+        }
+        Ok(())
+    }
+
+    // TODO(teawithsand): make async resolve with custom future with tokio which parses async event
+    //  and then notifies caller using waker
+    //  same for reverse_resolve
+
+    /// resolve performs dns lookup over tor. It invokes `RESOLVE` command which(according to torCP docs):
+    /// ```text
+    /// This command launches a remote hostname lookup request for every specified
+    /// request (or reverse lookup if "mode=reverse" is specified).
+    /// ```
+    /// Note: there is separate function for reverse requests.
+    ///
+    /// # Target
+    /// Target is anything convertible into `ResolveTarget`: hostname given as `&str` or ip address.
+    /// Ipv6 literal is sent in square brackets like `[::1]`.
+    ///
+    /// # Result
+    /// Result is passed as `ADDRMAP` event so one should setup event listener to use it.
+    /// It's `NewAddressMapping` event.
+    pub async fn resolve<'a>(&mut self, target: impl Into<ResolveTarget<'a>>) -> Result<(), ConnError> {
+        let target = target.into();
+        if let ResolveTarget::Hostname(hostname) = target {
+            if !is_valid_hostname(hostname) {
+                return Err(ConnError::AuthenticatedConnError(AuthenticatedConnError::InvalidHostnameValue));
+            }
+        }
+
+        self.conn.write_data(&format!("RESOLVE {}\r\n", target).as_bytes()).await?;
+        let (code, _) = self.recv_response().await?;
+        if !is_success_code(code) {
+            return Err(ConnError::InvalidResponseCode(code));
+        }
+        Ok(())
+    }
+
+    /// resolve performs reverse dns lookup over tor. It invokes `RESOLVE` command which(according to torCP docs):
     /// ```text
     /// This command launches a remote hostname lookup request for every specified
     /// request (or reverse lookup if "mode=reverse" is specified).
@@ -468,23 +1200,59 @@ impl<S, F, H> AuthenticatedConn<S, H>
         // assumption: ip can't provide any malicious contents
         self.conn.write_data(&format!("RESOLVE mode=reverse {}\r\n", address.to_string()).as_bytes()).await?;
         let (code, _) = self.recv_response().await?;
-        if code != 250 {
+        if !is_success_code(code) {
             return Err(ConnError::InvalidResponseCode(code));
         }
         Ok(())
     }
 
+    /// redirect_stream invokes `REDIRECTSTREAM` which(according to torCP docs):
+    /// ```text
+    /// Tells the server to change the exit address on the specified stream.  If
+    /// Port is specified, changes the destination port as well.  No remapping
+    /// is performed on the new provided address.
+    /// ```
+    /// It's useful with `STREAM` event handler which intercepts `NEW` streams and rewrites their destination.
+    ///
+    /// # Error
+    /// `AuthenticatedConnError::InvalidHostnameValue` is returned if address is neither valid hostname nor ip address.
+    /// It's checked before anything is sent to tor.
+    ///
+    /// Stream id may be given either as `StreamId`(for instance one taken from stream event) or as plain `u64`.
+    ///
+    /// # TorCP docs
+    /// Ctrl+F in torCP docs: `3.12. REDIRECTSTREAM`
+    pub async fn redirect_stream(&mut self, stream_id: impl Into<StreamId>, address: &str, port: Option<u16>) -> Result<(), ConnError> {
+        let stream_id = stream_id.into();
+        if !is_valid_hostname(address) && address.parse::<IpAddr>().is_err() {
+            return Err(ConnError::AuthenticatedConnError(AuthenticatedConnError::InvalidHostnameValue));
+        }
+
+        let call = match port {
+            Some(port) => format!("REDIRECTSTREAM {} {} {}\r\n", stream_id, address, port),
+            None => format!("REDIRECTSTREAM {} {}\r\n", stream_id, address),
+        };
+        self.conn.write_data(call.as_bytes()).await?;
+        let (code, lines) = self.recv_response().await?;
+        if !is_success_code(code) {
+            return Err(ConnError::from_response_code(code, lines));
+        }
+        Ok(())
+    }
+
     // note: there is no \r\n at the end
     #[allow(dead_code)] // prevents emitting warnings when v2 and v3 features is skipped
-    fn setup_onion_service_call<'a>(
+    pub(crate) fn setup_onion_service_call<'a, L>(
         is_rsa: bool,
         key_blob: &str,
         detach: bool,
         non_anonymous: bool,
         max_streams_close_circuit: bool,
         max_num_streams: Option<u16>,
-        listeners: &mut impl Iterator<Item=&'a (u16, SocketAddr)>,
-    ) -> Result<String, AuthenticatedConnError> {
+        listeners: &mut impl Iterator<Item=&'a L>,
+    ) -> Result<String, AuthenticatedConnError>
+        where L: Clone + Into<OnionListener> + 'a
+    {
         let mut res = String::new();
         res.push_str("ADD_ONION ");
         if is_rsa {
@@ -518,23 +1286,29 @@ impl<S, F, H> AuthenticatedConn<S, H>
         {
             if let Some(max_num_streams) = max_num_streams {
                 res.push_str(&format!("MaxStreams={} ", max_num_streams));
-                res.push_str(" ");
             }
         }
 
         {
             let mut is_first = true;
-            let mut ports = HashSet::new();
-            for (port, address) in listeners {
+            // note: same virtual port may be used more than once.
+            // Tor picks one of targets at random for each connection then, so it's fine.
+            for listener in listeners {
+                let listener: OnionListener = listener.clone().into();
                 if !is_first {
                     res.push(' ');
                 }
-                if ports.contains(port) {
-                    return Err(AuthenticatedConnError::InvalidListenerSpecification);
-                }
-                ports.insert(port);
                 is_first = false;
-                res.push_str(&format!("Port={},{}", port, address));
+                // tor parses target with its own address parser, which does not accept scope id after `%`
+                if let Some(OnionTarget::Tcp(SocketAddr::V6(addr))) = &listener.target {
+                    if addr.scope_id() != 0 {
+                        return Err(AuthenticatedConnError::InvalidListenerSpecification);
+                    }
+                }
+                match listener.target {
+                    Some(target) => res.push_str(&format!("Port={},{}", listener.port, target)),
+                    None => res.push_str(&format!("Port={}", listener.port)),
+                }
             }
             // zero iterations of above loop has ran
             if is_first {
@@ -547,44 +1321,100 @@ impl<S, F, H> AuthenticatedConn<S, H>
     }
 
     #[cfg(any(feature = "v3"))]
-    /// add_onion sends `ADD_ONION` command which spins up new onion service
+    /// add_onion_v3 sends `ADD_ONION` command which spins up new onion service
     /// using given tor secret key and some configuration values.
     ///
-    /// For onion service v2 take a look at `add_onion_v2`
+    /// # Note
+    /// Positional bool arguments are easy to mix up. Use `add_onion_v3_opts`, which takes `AddOnionOptions` instead.
+    /// This function is kept for compatibility and it's going to be deprecated.
     ///
     /// # Parameters
-    /// Take a look at `add_onion_v2`. This function accepts same parameters.
+    /// Take a look at `AddOnionOptions`, it has fields with same names as parameters of this function.
     ///
-    /// It does not support tor-side generated keys yet.
-    pub async fn add_onion_v3(
+    /// # Key retention
+    /// Just like `add_onion_v3_opts` it keeps copy of key if tracking is enabled with `set_track_onion_services`.
+    pub async fn add_onion_v3<'a, L>(
         &mut self,
         key: &crate::onion::TorSecretKeyV3,
         detach: bool,
         non_anonymous: bool,
         max_streams_close_circuit: bool,
         max_num_streams: Option<u16>,
-        listeners: &mut impl Iterator<Item=&(u16, SocketAddr)>,
-    ) -> Result<(), ConnError> {
-        let mut res = Self::setup_onion_service_call(
-            false,
-            &key.as_tor_proto_encoded(),
+        listeners: &mut impl Iterator<Item=&'a L>,
+    ) -> Result<crate::onion::OnionAddress, ConnError>
+        where L: Clone + Into<OnionListener> + 'a
+    {
+        let options = AddOnionOptions {
             detach,
             non_anonymous,
             max_streams_close_circuit,
             max_num_streams,
-            listeners,
+        };
+        self.add_onion_v3_opts(key, &options, listeners).await
+    }
+
+    #[cfg(feature = "v3")]
+    /// add_onion_v3_opts sends `ADD_ONION` command which spins up new onion service
+    /// using given tor secret key and options.
+    ///
+    /// It does not support tor-side generated keys yet.
+    ///
+    /// # Return value
+    /// Address of onion service, taken from `ServiceID` in tor's reply. It should be equal to address derived from key.
+    /// `AuthenticatedConnError::InvalidAddOnionResponse` is returned if reply does not contain valid one.
+    /// Reply is parsed with `parse_add_onion_response`.
+    ///
+    /// # Listeners
+    /// Each listener is anything convertible into `OnionListener`, like `(u16, SocketAddr)` tuple or
+    /// `(u16, OnionTarget)` tuple, so connections may be forwarded to unix socket with `OnionTarget::Unix`.
+    /// Plain `u16` is virtual port without target, so tor forwards it to the same port on `127.0.0.1`.
+    /// Virtual port may be repeated with different targets in order to spread connections across them.
+    ///
+    /// # Key retention
+    /// If tracking is enabled with `set_track_onion_services`, copy of key, options and listeners is kept
+    /// by connection until service is removed with `del_onion`, so it can be returned by `to_state`.
+    /// Otherwise key is not retained after this function returns.
+    ///
+    /// # TorCP docs
+    /// Ctrl+F `3.27. ADD_ONION`
+    pub async fn add_onion_v3_opts<'a, L>(
+        &mut self,
+        key: &crate::onion::TorSecretKeyV3,
+        options: &AddOnionOptions,
+        listeners: &mut impl Iterator<Item=&'a L>,
+    ) -> Result<crate::onion::OnionAddress, ConnError>
+        where L: Clone + Into<OnionListener> + 'a
+    {
+        let listeners = listeners.map(|l| l.clone().into()).collect::<Vec<OnionListener>>();
+        let mut res = Self::setup_onion_service_call(
+            false,
+            &key.to_base64(),
+            options.detach,
+            options.non_anonymous,
+            options.max_streams_close_circuit,
+            options.max_num_streams,
+            &mut listeners.iter(),
         )?;
         res.push_str("\r\n");
 
         self.conn.write_data(res.as_bytes()).await?;
 
-        // we do not really care about contents of response
-        // we can derive all the data from tor's objects at the torut level
-        let (code, _) = self.recv_response().await?;
+        let (code, lines) = self.recv_response().await?;
         if code != 250 {
-            return Err(ConnError::InvalidResponseCode(code));
+            return Err(ConnError::from_response_code(code, lines));
         }
-        Ok(())
+        let response = crate::control::primitives::parse_add_onion_response(&lines)?;
+        let address = response.service_id.parse::<crate::onion::OnionAddressV3>()
+            .map_err(|_| AuthenticatedConnError::InvalidAddOnionResponse)?;
+        let address = crate::onion::OnionAddress::V3(address);
+        if self.track_onion_services {
+            self.onion_services.push(crate::control::primitives::OnionServiceState {
+                key: key.clone(),
+                options: *options,
+                listeners,
+            });
+        }
+        Ok(address)
     }
 
     /// del_onion sends `DEL_ONION` command which stops onion service.
@@ -601,9 +1431,72 @@ impl<S, F, H> AuthenticatedConn<S, H>
         }
         self.conn.write_data(&format!("DEL_ONION {}\r\n", identifier_without_dot_onion).as_bytes()).await?;
         let (code, _) = self.recv_response().await?;
-        if code != 250 {
+        if !is_success_code(code) {
             return Err(ConnError::InvalidResponseCode(code));
         }
+        #[cfg(feature = "v3")]
+        self.onion_services.retain(|s| {
+            s.key.public().get_onion_address().get_address_without_dot_onion() != identifier_without_dot_onion
+        });
+        Ok(())
+    }
+
+    /// restore_state applies state created with `to_state`(possibly on other connection) to this connection.
+    ///
+    /// It sends `SETEVENTS` with events from state, so events set on this connection before are replaced.
+    /// If `readd_onion_services` is true, onion services from state are added with `ADD_ONION` once again.
+    /// Don't do this for detached services which still exist, since tor refuses to add them twice.
+    ///
+    /// # Error
+    /// First error stops restoring, so some of services may have been added already.
+    pub async fn restore_state(&mut self, state: &ConnState, readd_onion_services: bool) -> Result<(), ConnError> {
+        self.set_events(state.events_extended, &mut state.events.iter().map(|e| e.as_str())).await?;
+        #[cfg(feature = "v3")]
+        {
+            if readd_onion_services {
+                for service in state.onion_services.iter() {
+                    self.add_onion_v3_opts(&service.key, &service.options, &mut service.listeners.iter()).await?;
+                }
+            }
+        }
+        #[cfg(not(feature = "v3"))]
+        let _ = readd_onion_services;
+        Ok(())
+    }
+
+    #[cfg(feature = "v3")]
+    /// onion_client_auth_view sends `ONION_CLIENT_AUTH_VIEW` command which lists client authorization credentials
+    /// for onion services v3 registered in tor.
+    ///
+    /// If `addr` is `Some` only credentials for given onion service are returned.
+    ///
+    /// # TorCP docs
+    /// Ctrl+F `3.33. ONION_CLIENT_AUTH_VIEW`
+    pub async fn onion_client_auth_view(&mut self, addr: Option<&crate::onion::OnionAddressV3>) -> Result<Vec<crate::control::primitives::ClientAuthEntry>, ConnError> {
+        // onion address is base32 so it's safe to send it
+        let call = match addr {
+            Some(addr) => format!("ONION_CLIENT_AUTH_VIEW {}\r\n", addr.get_address_without_dot_onion()),
+            None => "ONION_CLIENT_AUTH_VIEW\r\n".to_string(),
+        };
+        self.conn.write_data(call.as_bytes()).await?;
+        self.read_onion_client_auth_view_response().await
+    }
+
+    #[cfg(feature = "v3")]
+    /// onion_client_auth_remove sends `ONION_CLIENT_AUTH_REMOVE` command which removes client authorization credential
+    /// for given onion service v3 from tor.
+    ///
+    /// According to torCP docs tor replies with `251` when there was no credential for given address.
+    /// It's not treated as an error.
+    ///
+    /// # TorCP docs
+    /// Ctrl+F `3.32. ONION_CLIENT_AUTH_REMOVE`
+    pub async fn onion_client_auth_remove(&mut self, addr: &crate::onion::OnionAddressV3) -> Result<(), ConnError> {
+        self.conn.write_data(format!("ONION_CLIENT_AUTH_REMOVE {}\r\n", addr.get_address_without_dot_onion()).as_bytes()).await?;
+        let (code, lines) = self.recv_response().await?;
+        if !is_success_code(code) {
+            return Err(ConnError::from_response_code(code, lines));
+        }
         Ok(())
     }
 
@@ -619,29 +1512,177 @@ impl<S, F, H> AuthenticatedConn<S, H>
     /// # Notes on using options
     /// Extended parameter is ignored in tor newer than `0.2.2.1-alpha` and it's always switched on.
     /// It should default to false.
+    ///
+    /// Events set here are remembered, so `subscribe_event` and `unsubscribe_event` may modify them later.
     pub async fn set_events(&mut self, extended: bool, kinds: &mut impl Iterator<Item=&str>) -> Result<(), ConnError> {
-        let mut req = String::from("SETEVENTS");
-        if extended {
-            req.push_str(" EXTENDED");
-        }
+        let mut events = Vec::new();
         for k in kinds {
             if !is_valid_event(k) {
                 return Err(ConnError::AuthenticatedConnError(AuthenticatedConnError::InvalidEventName));
             }
-            req.push(' ');
-            req.push_str(k);
+            events.push(k.to_string());
         }
-        req.push_str("\r\n");
-        self.conn.write_data(req.as_bytes()).await?;
-        let (code, _) = self.recv_response().await?;
-        if code != 250 {
-            return Err(ConnError::InvalidResponseCode(code));
+        self.send_set_events(extended, events).await
+    }
+
+    /// set_events_typed works like `set_events` but takes `AsyncEventKind`s rather than raw event names,
+    /// so typo in event name is caught at compile time.
+    ///
+    /// Use `set_events` for events which are not in `AsyncEventKind` yet.
+    pub async fn set_events_typed(&mut self, extended: bool, kinds: &[AsyncEventKind]) -> Result<(), ConnError> {
+        self.set_events(extended, &mut kinds.iter().map(|k| k.get_identifier())).await
+    }
+
+    /// subscribe_event adds given event to set of events reported by tor and sends `SETEVENTS` with whole set,
+    /// so events set previously are still reported.
+    ///
+    /// Nothing is sent if event is reported already.
+    pub async fn subscribe_event(&mut self, kind: AsyncEventKind) -> Result<(), ConnError> {
+        let name = kind.get_identifier();
+        if self.events.iter().any(|e| e == name) {
+            return Ok(());
         }
-        Ok(())
+        let mut events = self.events.clone();
+        events.push(name.to_string());
+        self.send_set_events(self.events_extended, events).await
     }
 
-    /// noop implements no-operation call to tor process despite the fact that torCP does not implement it.
+    /// unsubscribe_event removes given event from set of events reported by tor and sends `SETEVENTS` with rest of them.
+    ///
+    /// Nothing is sent if event is not reported.
+    pub async fn unsubscribe_event(&mut self, kind: AsyncEventKind) -> Result<(), ConnError> {
+        let name = kind.get_identifier();
+        if !self.events.iter().any(|e| e == name) {
+            return Ok(());
+        }
+        let events = self.events.iter()
+            .filter(|e| *e != name)
+            .cloned()
+            .collect();
+        self.send_set_events(self.events_extended, events).await
+    }
+
+    // events have to be validated already
+    async fn send_set_events(&mut self, extended: bool, events: Vec<String>) -> Result<(), ConnError> {
+        let mut req = String::from("SETEVENTS");
+        if extended {
+            req.push_str(" EXTENDED");
+        }
+        for e in events.iter() {
+            req.push(' ');
+            req.push_str(e);
+        }
+        req.push_str("\r\n");
+        self.conn.write_data(req.as_bytes()).await?;
+        let (code, _) = self.recv_response().await?;
+        if !is_success_code(code) {
+            return Err(ConnError::InvalidResponseCode(code));
+        }
+        self.events_extended = extended;
+        self.events = events;
+        Ok(())
+    }
+
+    /// use_feature sends `USEFEATURE` command which enables additional features of control protocol
+    /// for this connection. For instance `VERBOSE_NAMES` makes tor use `$fingerprint~nickname` names
+    /// for relays in its replies and events.
+    ///
+    /// It should be called once, right after authentication.
+    /// Features which are always turned on in new tor versions(like `EXTENDED_EVENTS`) are accepted by tor but ignored.
+    ///
+    /// # Error
+    /// It returns `AuthenticatedConnError::InvalidFeatureName` when one of features is not valid feature name.
+    /// It returns error when tor instance returns an error, for instance when feature is not recognized.
+    ///
+    /// # TorCP docs
+    /// Ctrl+F `3.19. USEFEATURE`
+    pub async fn use_feature(&mut self, features: &mut impl Iterator<Item=&str>) -> Result<(), ConnError> {
+        let mut call = String::from("USEFEATURE");
+        let mut has_any_feature = false;
+        for feature in features {
+            if !is_valid_feature(feature) {
+                return Err(ConnError::AuthenticatedConnError(AuthenticatedConnError::InvalidFeatureName));
+            }
+            has_any_feature = true;
+            call.push(' ');
+            call.push_str(feature);
+        }
+        if !has_any_feature {
+            return Ok(());
+        }
+        call.push_str("\r\n");
+        self.conn.write_data(call.as_bytes()).await?;
+
+        let (code, _) = self.recv_response().await?;
+        if !is_success_code(code) {
+            return Err(ConnError::InvalidResponseCode(code));
+        }
+        Ok(())
+    }
+
+    /// post_descriptor sends `+POSTDESCRIPTOR` command which uploads router descriptor to tor process.
+    ///
+    /// # Parameters
+    /// `purpose` - purpose of descriptor like `general`, `controller` or `bridge`. If it's `None` tor uses `general`.
+    /// `cache` - when it's `Some` `cache=yes` or `cache=no` is sent. Otherwise tor decides on its own.
+    /// `descriptor` - text of descriptor. Lines starting with dot are dot-stuffed before sending so they can't
+    /// terminate data block.
+    ///
+    /// # Return value
+    /// Lines of tor's reply are returned.
+    /// According to torCP docs tor replies with `250 OK` if descriptor was added or with `251` if it was not added
+    /// but it's not an error(for instance tor already knows this descriptor). Both are treated as success.
+    ///
+    /// # Error
+    /// It returns `AuthenticatedConnError::InvalidKeywordValue` when purpose is not valid keyword.
+    /// It returns error when tor instance returns an error.
+    ///
+    /// # TorCP docs
+    /// Ctrl+F `3.14. POSTDESCRIPTOR`
+    pub async fn post_descriptor(&mut self, purpose: Option<&str>, cache: Option<bool>, descriptor: &str) -> Result<Vec<String>, ConnError> {
+        let mut call = String::from("+POSTDESCRIPTOR");
+        if let Some(purpose) = purpose {
+            if !is_valid_keyword(purpose) {
+                return Err(ConnError::AuthenticatedConnError(AuthenticatedConnError::InvalidKeywordValue));
+            }
+            call.push_str(" purpose=");
+            call.push_str(purpose);
+        }
+        if let Some(cache) = cache {
+            call.push_str(if cache { " cache=yes" } else { " cache=no" });
+        }
+        call.push_str("\r\n");
+        call.push_str(&encode_multiline_data(descriptor));
+        self.conn.write_data(call.as_bytes()).await?;
+
+        let (code, lines) = self.recv_response().await?;
+        if !is_success_code(code) {
+            return Err(ConnError::InvalidResponseCode(code));
+        }
+        Ok(lines)
+    }
+
+    /// quit sends `QUIT` command, which makes tor close control connection in orderly manner.
+    /// Tor replies with `250 closing connection` and then closes connection.
+    ///
+    /// Connection is consumed, since nothing more can be done with it.
+    /// If ownership was taken with `take_ownership` tor exits after connection is closed.
+    ///
+    /// # TorCP docs
+    /// Ctrl+F `3.9. QUIT`
+    pub async fn quit(mut self) -> Result<(), ConnError> {
+        self.conn.write_data(b"QUIT\r\n").await?;
+        let (code, lines) = self.recv_response().await?;
+        if !is_success_code(code) {
+            return Err(ConnError::from_response_code(code, lines));
+        }
+        Ok(())
+    }
+
+    /// noop implements no-operation call to tor process despite the fact that torCP does not implement it.
     /// It's used to poll any async event without blocking.
+    ///
+    /// It sends real command to tor. To check if connection is alive without doing so use `is_alive`.
     pub async fn noop(&mut self) -> Result<(), ConnError> {
         // right now noop is getting tor's version
         // it should do
@@ -653,11 +1694,16 @@ impl<S, F, H> AuthenticatedConn<S, H>
 #[cfg(test)]
 mod test {
     use std::io::Cursor;
+    use std::net::SocketAddr;
+    use std::path::PathBuf;
 
-    use crate::utils::block_on;
+    use crate::control::OnionTarget;
+    use crate::utils::{block_on, block_on_with_env, TestStream};
 
     use super::*;
 
+    type NoopHandler = fn(AsyncEvent<'static>) -> std::future::Ready<Result<(), ConnError>>;
+
     #[test]
     fn test_can_parse_getconf_response() {
         for (i, o) in [
@@ -727,6 +1773,452 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_can_get_bootstrap_phase() {
+        block_on(async move {
+            let mut stream = TestStream::new(concat!(
+                "250-status/bootstrap-phase=NOTICE BOOTSTRAP PROGRESS=75 TAG=enough_dirinfo SUMMARY=\"Loaded enough directory info to build circuits\"\r\n",
+                "250 OK\r\n",
+                "250-status/bootstrap-phase=NOTICE BOOTSTRAP\r\n",
+                "250 OK\r\n",
+            ).as_bytes());
+            let mut conn = AuthenticatedConn::from(Conn::new(&mut stream));
+            conn.set_async_event_handler(
+                Some(|_| async move { Ok(()) })
+            );
+            let phase = conn.bootstrap_phase().await.unwrap();
+            assert_eq!(phase.progress, 75);
+            assert_eq!(phase.tag, "enough_dirinfo");
+            assert_eq!(phase.summary, "Loaded enough directory info to build circuits");
+            assert!(matches!(conn.bootstrap_phase().await, Err(ConnError::InvalidFormat)));
+            assert_eq!(stream.output, b"GETINFO status/bootstrap-phase\r\nGETINFO status/bootstrap-phase\r\n");
+        });
+    }
+
+    #[test]
+    #[cfg(feature = "v3")]
+    fn test_can_get_onions() {
+        let a1 = crate::onion::TorSecretKeyV3::generate().public().get_onion_address();
+        let a2 = crate::onion::TorSecretKeyV3::generate().public().get_onion_address();
+        block_on(async move {
+            let mut stream = TestStream::new(format!(
+                concat!(
+                    "250-onions/current={}\r\n",
+                    "250 OK\r\n",
+                    "250+onions/detached=\r\n{}\r\n{}\r\n.\r\n",
+                    "250 OK\r\n",
+                    "551 No onion services of the specified type.\r\n",
+                    "250-onions/detached=notanonion\r\n",
+                    "250 OK\r\n",
+                    "551 Internal error\r\n",
+                ),
+                a1.get_address_without_dot_onion(),
+                a1.get_address_without_dot_onion(),
+                a2.get_address_without_dot_onion(),
+            ).as_bytes());
+            let mut conn = AuthenticatedConn::from(Conn::new(&mut stream));
+            conn.set_async_event_handler(
+                Some(|_| async move { Ok(()) })
+            );
+            assert_eq!(conn.get_current_onions().await.unwrap(), vec![a1]);
+            assert_eq!(conn.get_detached_onions().await.unwrap(), vec![a1, a2]);
+            assert_eq!(conn.get_current_onions().await.unwrap(), vec![]);
+            assert!(matches!(conn.get_detached_onions().await, Err(ConnError::InvalidFormat)));
+            assert!(matches!(
+                conn.get_current_onions().await,
+                Err(ConnError::TorError(crate::control::TorErrorKind::InternalError, _))
+            ));
+            assert_eq!(stream.output, concat!(
+                "GETINFO onions/current\r\n",
+                "GETINFO onions/detached\r\n",
+                "GETINFO onions/current\r\n",
+                "GETINFO onions/detached\r\n",
+                "GETINFO onions/current\r\n",
+            ).as_bytes());
+        });
+    }
+
+    #[cfg(feature = "v3")]
+    #[test]
+    fn test_can_adopt_detached_onions() {
+        let make_service = |port: u16| crate::control::primitives::OnionServiceState {
+            key: crate::onion::TorSecretKeyV3::generate(),
+            options: AddOnionOptions {
+                detach: true,
+                ..Default::default()
+            },
+            listeners: vec![OnionListener::from(port)],
+        };
+        let s1 = make_service(80);
+        let s2 = make_service(443);
+        let s3 = make_service(8080);
+        let a1 = s1.key.public().get_onion_address();
+        let a2 = s2.key.public().get_onion_address();
+        block_on(async move {
+            let mut stream = TestStream::new(format!(
+                concat!(
+                    "250-onions/detached={}\r\n",
+                    "250 OK\r\n",
+                    "250+onions/detached=\r\n{}\r\n{}\r\n.\r\n",
+                    "250 OK\r\n",
+                    "250-onions/detached={}\r\n",
+                    "250 OK\r\n",
+                    "551 No onion services of the specified type.\r\n",
+                ),
+                a1.get_address_without_dot_onion(),
+                a1.get_address_without_dot_onion(),
+                a2.get_address_without_dot_onion(),
+                a1.get_address_without_dot_onion(),
+            ).as_bytes());
+            let mut conn = AuthenticatedConn::from(Conn::new(&mut stream));
+            conn.set_async_event_handler(
+                Some(|_| async move { Ok(()) })
+            );
+            // nothing is taken over unless tracking is enabled
+            let detached = conn.adopt_detached(&mut [s1.clone()].iter()).await.unwrap();
+            assert_eq!(detached, vec![a1]);
+            assert_eq!(conn.to_state().onion_services, vec![]);
+
+            conn.set_track_onion_services(true);
+            let detached = conn.adopt_detached(&mut [s1.clone(), s3.clone()].iter()).await.unwrap();
+            assert_eq!(detached, vec![a1, a2]);
+            assert_eq!(conn.to_state().onion_services, vec![s1.clone()]);
+
+            // already tracked services are not added twice
+            let detached = conn.adopt_detached(&mut [s1.clone()].iter()).await.unwrap();
+            assert_eq!(detached, vec![a1]);
+            assert_eq!(conn.to_state().onion_services, vec![s1.clone()]);
+
+            let detached = conn.adopt_detached(&mut std::iter::empty()).await.unwrap();
+            assert_eq!(detached, vec![]);
+        });
+    }
+
+    //noinspection SpellCheckingInspection
+    #[test]
+    fn test_can_get_descriptor() {
+        block_on(async move {
+            let mut stream = TestStream::new(concat!(
+                "250+desc/id/9695DFC35FFEB861329B9F1AB04C46397020CE31=\r\n",
+                "router moria1 128.31.0.34 9101 0 9131\r\n",
+                "platform Tor 0.4.8.9 on Linux\r\n",
+                ".\r\n",
+                "250 OK\r\n",
+                "250+desc/name/moria1=\r\n",
+                "router moria1 128.31.0.34 9101 0 9131\r\n",
+                ".\r\n",
+                "250 OK\r\n",
+                "552 Unrecognized key \"desc/name/nope\"\r\n",
+            ).as_bytes());
+            let mut conn = AuthenticatedConn::from(Conn::new(&mut stream));
+            conn.set_async_event_handler(
+                Some(|_| async move { Ok(()) })
+            );
+            let desc = conn.get_descriptor(DescSelector::ByFingerprint("9695DFC35FFEB861329B9F1AB04C46397020CE31".to_string())).await.unwrap();
+            assert_eq!(desc, "router moria1 128.31.0.34 9101 0 9131\r\nplatform Tor 0.4.8.9 on Linux");
+            let desc = conn.get_descriptor(DescSelector::ByNickname("moria1".to_string())).await.unwrap();
+            assert_eq!(desc, "router moria1 128.31.0.34 9101 0 9131");
+            match conn.get_descriptor(DescSelector::ByNickname("nope".to_string())).await {
+                Err(ConnError::TorError(crate::control::TorErrorKind::UnrecognizedEntity, lines)) => {
+                    assert_eq!(lines, vec!["Unrecognized key \"desc/name/nope\"".to_string()]);
+                }
+                res => panic!("Unexpected result {:?}", res),
+            }
+            assert!(matches!(
+                conn.get_descriptor(DescSelector::ByFingerprint("moria1".to_string())).await,
+                Err(ConnError::AuthenticatedConnError(AuthenticatedConnError::InvalidFingerprint))
+            ));
+            assert!(matches!(
+                conn.get_descriptor(DescSelector::ByNickname("moria1\r\nSIGNAL SHUTDOWN".to_string())).await,
+                Err(ConnError::AuthenticatedConnError(AuthenticatedConnError::InvalidNickname))
+            ));
+            assert_eq!(stream.output, concat!(
+                "GETINFO desc/id/9695DFC35FFEB861329B9F1AB04C46397020CE31\r\n",
+                "GETINFO desc/name/moria1\r\n",
+                "GETINFO desc/name/nope\r\n",
+            ).as_bytes());
+        });
+    }
+
+    //noinspection SpellCheckingInspection
+    #[test]
+    fn test_can_get_router_status() {
+        block_on(async move {
+            let mut stream = TestStream::new(concat!(
+                "250+ns/id/$9695DFC35FFEB861329B9F1AB04C46397020CE31=\r\n",
+                "r moria1 lpXfw1/+uGEym58asExGOXAgzjE IpcU7dolas8+Q+oAzwgvZIWx7PA 2024-01-01 12:00:00 128.31.0.34 9101 9131\r\n",
+                "s Authority Fast Running Stable V2Dir Valid\r\n",
+                "w Bandwidth=20\r\n",
+                ".\r\n",
+                "250 OK\r\n",
+                "552 Unrecognized key \"ns/id/9695DFC35FFEB861329B9F1AB04C46397020CE32\"\r\n",
+            ).as_bytes());
+            let mut conn = AuthenticatedConn::from(Conn::new(&mut stream));
+            conn.set_async_event_handler(
+                Some(|_| async move { Ok(()) })
+            );
+            let status = conn.get_router_status("$9695DFC35FFEB861329B9F1AB04C46397020CE31").await.unwrap();
+            assert_eq!(status.nickname, "moria1");
+            assert_eq!(status.address, Ipv4Addr::new(128, 31, 0, 34));
+            assert!(status.has_flag("Authority"));
+            assert_eq!(status.bandwidth, Some(20));
+            conn.get_router_status("9695DFC35FFEB861329B9F1AB04C46397020CE32").await.unwrap_err();
+            assert!(matches!(
+                conn.get_router_status("moria1\r\nSIGNAL SHUTDOWN").await,
+                Err(ConnError::AuthenticatedConnError(AuthenticatedConnError::InvalidFingerprint))
+            ));
+            assert_eq!(stream.output, concat!(
+                "GETINFO ns/id/$9695DFC35FFEB861329B9F1AB04C46397020CE31\r\n",
+                "GETINFO ns/id/9695DFC35FFEB861329B9F1AB04C46397020CE32\r\n",
+            ).as_bytes());
+        });
+    }
+
+    #[test]
+    fn test_can_pull_events() {
+        block_on(async move {
+            let mut stream = TestStream::new(b"650 BW 1 2\r\n650-CIRC 1 BUILT\r\n650 OK\r\n250 OK\r\n");
+            let mut conn = AuthenticatedConn::<_, NoopHandler>::from(Conn::new(&mut stream));
+            let event = conn.next_event().await.unwrap();
+            assert_eq!(event.code, 650);
+            assert_eq!(event.lines, ["BW 1 2"]);
+            let event = conn.next_event().await.unwrap();
+            assert_eq!(event.lines, ["CIRC 1 BUILT", "OK"]);
+            assert!(matches!(conn.next_event().await, Err(ConnError::InvalidResponseCode(250))));
+            assert!(matches!(conn.next_event().await, Err(ConnError::ConnectionClosed)));
+        });
+    }
+
+    #[test]
+    fn test_can_get_info_in_batches() {
+        block_on(async move {
+            let mut stream = TestStream::new(concat!(
+                "250-version=0.4.5.6\r\n250-address=1.2.3.4\r\n250 OK\r\n",
+                "250-dormant=0\r\n250-version=0.4.5.6\r\n250 OK\r\n",
+                "250-traffic/read=123\r\n250 OK\r\n",
+            ).as_bytes());
+            let mut conn = AuthenticatedConn::from(Conn::new(&mut stream));
+            conn.set_async_event_handler(
+                Some(|_| async move { Ok(()) })
+            );
+            conn.set_max_get_info_line_length(30);
+            let res = conn.get_info_multiple(
+                &mut ["version", "address", "dormant", "version", "traffic/read"].iter().copied()
+            ).await.unwrap();
+            assert_eq!(res.len(), 4);
+            assert_eq!(res["version"], ["0.4.5.6", "0.4.5.6"]);
+            assert_eq!(res["address"], ["1.2.3.4"]);
+            assert_eq!(res["dormant"], ["0"]);
+            assert_eq!(res["traffic/read"], ["123"]);
+            assert_eq!(std::str::from_utf8(&stream.output).unwrap(), concat!(
+                "GETINFO version address\r\n",
+                "GETINFO dormant version\r\n",
+                "GETINFO traffic/read\r\n",
+            ));
+        });
+
+        // key longer than limit is sent anyway and missing values are still detected
+        block_on(async move {
+            let mut stream = TestStream::new(concat!(
+                "250-status/circuit-established=1\r\n250 OK\r\n",
+                "250-version=0.4.5.6\r\n250 OK\r\n",
+                "250 OK\r\n",
+            ).as_bytes());
+            let mut conn = AuthenticatedConn::from(Conn::new(&mut stream));
+            conn.set_async_event_handler(
+                Some(|_| async move { Ok(()) })
+            );
+            conn.set_max_get_info_line_length(10);
+            let res = conn.get_info_multiple(
+                &mut ["status/circuit-established", "version", "version"].iter().copied()
+            ).await;
+            assert!(matches!(res, Err(ConnError::InvalidFormat)));
+            assert_eq!(std::str::from_utf8(&stream.output).unwrap(), concat!(
+                "GETINFO status/circuit-established\r\n",
+                "GETINFO version\r\n",
+                "GETINFO version\r\n",
+            ));
+        });
+    }
+
+    #[test]
+    fn test_can_get_config_text() {
+        block_on(async move {
+            let mut stream = TestStream::new(concat!(
+                "250+config-text=\r\n",
+                "ControlPort 9051\r\n",
+                "DataDirectory /var/lib/tor\r\n",
+                "HiddenServiceDir /var/lib/tor/hs\r\n",
+                "HiddenServicePort 80 127.0.0.1:8080\r\n",
+                "DisableNetwork\r\n",
+                ".\r\n",
+                "250 OK\r\n",
+                "250+config-text=\r\n",
+                "ControlPort 9051\r\n",
+                "HiddenServicePort 80 127.0.0.1:8080\r\n",
+                ".\r\n",
+                "250 OK\r\n",
+                "250+config-text=\r\n",
+                ".\r\n",
+                "250 OK\r\n",
+            ).as_bytes());
+            let mut conn = AuthenticatedConn::from(Conn::new(&mut stream));
+            conn.set_async_event_handler(
+                Some(|_| async move { Ok(()) })
+            );
+            assert_eq!(
+                conn.get_config_text().await.unwrap(),
+                concat!(
+                    "ControlPort 9051\r\n",
+                    "DataDirectory /var/lib/tor\r\n",
+                    "HiddenServiceDir /var/lib/tor/hs\r\n",
+                    "HiddenServicePort 80 127.0.0.1:8080\r\n",
+                    "DisableNetwork",
+                ),
+            );
+            assert_eq!(conn.get_config_lines().await.unwrap(), [
+                ("ControlPort", "9051"),
+                ("HiddenServicePort", "80 127.0.0.1:8080"),
+            ].iter().map(|(k, v)| (k.to_string(), v.to_string())).collect::<Vec<_>>());
+            assert_eq!(conn.get_config_lines().await.unwrap(), []);
+        });
+    }
+
+    #[test]
+    fn test_can_get_info_names() {
+        block_on(async move {
+            let mut stream = TestStream::new(concat!(
+                "250+info/names=\r\n",
+                "accounting/bytes -- Number of bytes read/written so far in the accounting interval.\r\n",
+                "config/* -- Current configuration values.\r\n",
+                "md/all -- All known microdescriptors.\r\n",
+                "version -- The current version of Tor.\r\n",
+                ".\r\n",
+                "250 OK\r\n",
+                "250+info/names=\r\n",
+                "version The current version of Tor.\r\n",
+                ".\r\n",
+                "250 OK\r\n",
+            ).as_bytes());
+            let mut conn = AuthenticatedConn::from(Conn::new(&mut stream));
+            conn.set_async_event_handler(
+                Some(|_| async move { Ok(()) })
+            );
+            let names = conn.get_info_names().await.unwrap();
+            assert_eq!(names, [
+                ("accounting/bytes", "Number of bytes read/written so far in the accounting interval."),
+                ("config/*", "Current configuration values."),
+                ("md/all", "All known microdescriptors."),
+                ("version", "The current version of Tor."),
+            ].iter().map(|(k, v)| (k.to_string(), v.to_string())).collect::<Vec<_>>());
+            assert!(names.iter().any(|(k, _)| k == "md/all"));
+            assert!(matches!(conn.get_info_names().await, Err(ConnError::InvalidFormat)));
+            assert_eq!(stream.output, b"GETINFO info/names\r\nGETINFO info/names\r\n");
+        });
+    }
+
+    #[test]
+    fn test_can_get_external_address() {
+        block_on(async move {
+            let mut stream = TestStream::new(concat!(
+                "250-address=1.2.3.4\r\n",
+                "250 OK\r\n",
+                "551 Address unknown\r\n",
+                "250-address=1.2.3\r\n",
+                "250 OK\r\n",
+                "250-address/v6=2001:db8::1\r\n",
+                "250 OK\r\n",
+                "250-address/v6=1.2.3.4\r\n",
+                "250 OK\r\n",
+            ).as_bytes());
+            let mut conn = AuthenticatedConn::from(Conn::new(&mut stream));
+            conn.set_async_event_handler(
+                Some(|_| async move { Ok(()) })
+            );
+            assert_eq!(conn.external_address().await.unwrap(), IpAddr::from([1, 2, 3, 4]));
+            assert!(matches!(conn.external_address().await, Err(ConnError::TorError(crate::control::TorErrorKind::InternalError, _))));
+            assert!(matches!(conn.external_address().await, Err(ConnError::InvalidFormat)));
+            assert_eq!(conn.external_address_v6().await.unwrap(), "2001:db8::1".parse::<IpAddr>().unwrap());
+            assert!(matches!(conn.external_address_v6().await, Err(ConnError::InvalidFormat)));
+            assert_eq!(std::str::from_utf8(&stream.output).unwrap(), concat!(
+                "GETINFO address\r\n",
+                "GETINFO address\r\n",
+                "GETINFO address\r\n",
+                "GETINFO address/v6\r\n",
+                "GETINFO address/v6\r\n",
+            ));
+        });
+    }
+
+    #[test]
+    fn test_can_quit() {
+        block_on(async move {
+            let mut stream = TestStream::new(b"650 CIRC 1 CLOSED\r\n250 closing connection\r\n");
+            let mut conn = AuthenticatedConn::from(Conn::new(&mut stream));
+            conn.set_async_event_handler(
+                Some(|_| async move { Ok(()) })
+            );
+            conn.quit().await.unwrap();
+            assert_eq!(stream.output, b"QUIT\r\n");
+        });
+    }
+
+    #[test]
+    fn test_can_load_conf() {
+        block_on(async move {
+            let mut stream = TestStream::new(b"250 OK\r\n552 Invalid config file: Unknown option 'Invalid'\r\n");
+            let mut conn = AuthenticatedConn::from(Conn::new(&mut stream));
+            conn.set_async_event_handler(
+                Some(|_| async move { Ok(()) })
+            );
+            conn.load_conf("SocksPort 9050\nControlPort 9051\n.\n").await.unwrap();
+            let err = conn.load_conf("Invalid 1").await.unwrap_err();
+            assert!(matches!(err, ConnError::TorError(crate::control::TorErrorKind::UnrecognizedEntity, _)));
+            assert_eq!(
+                std::str::from_utf8(&stream.output).unwrap(),
+                concat!(
+                    "+LOADCONF\r\nSocksPort 9050\r\nControlPort 9051\r\n..\r\n.\r\n",
+                    "+LOADCONF\r\nInvalid 1\r\n.\r\n",
+                ),
+            );
+        });
+    }
+
+    #[test]
+    fn test_can_get_conf_ordered() {
+        block_on(async move {
+            let mut stream = TestStream::new(concat!(
+                "250-SocksPort=9050\r\n",
+                "250-SocksPort=\"127.0.0.1:9150\"\r\n",
+                "250-ControlPort=9051\r\n",
+                "250 Bridge\r\n",
+                "552 Unrecognized configuration key \"NoSuchOption\"\r\n",
+            ).as_bytes());
+            let mut conn = AuthenticatedConn::from(Conn::new(&mut stream));
+            conn.set_async_event_handler(
+                Some(|_| async move { Ok(()) })
+            );
+            let res = conn.get_conf_ordered(&mut ["SOCKSPORT", "controlport", "Bridge"].iter().copied()).await.unwrap();
+            assert_eq!(res, vec![
+                ("SocksPort".to_string(), Some("9050".to_string())),
+                ("SocksPort".to_string(), Some("127.0.0.1:9150".to_string())),
+                ("ControlPort".to_string(), Some("9051".to_string())),
+                ("Bridge".to_string(), None),
+            ]);
+
+            match conn.get_conf_ordered(&mut ["NoSuchOption"].iter().copied()).await {
+                Err(ConnError::TorError(crate::control::TorErrorKind::UnrecognizedEntity, lines)) => {
+                    assert_eq!(lines, vec!["Unrecognized configuration key \"NoSuchOption\"".to_string()]);
+                }
+                res => panic!("Unexpected result: {:?}", res),
+            }
+
+            assert_eq!(conn.get_conf_ordered(&mut [].iter().copied()).await.unwrap(), vec![]);
+            conn.get_conf_ordered(&mut ["SocksPort", "Bridge\r\nSIGNAL HALT"].iter().copied()).await.unwrap_err();
+            assert_eq!(stream.output, b"GETCONF SOCKSPORT controlport Bridge\r\nGETCONF NoSuchOption\r\n");
+        });
+    }
+
     #[test]
     fn test_can_parse_getinfo_response() {
         for (i, o) in [
@@ -788,6 +2280,1013 @@ mod test {
             })
         }
     }
+
+    //noinspection SpellCheckingInspection
+    #[cfg(feature = "v3")]
+    #[test]
+    fn test_can_view_and_remove_onion_client_auth() {
+        use std::str::FromStr;
+
+        use crate::onion::OnionAddressV3;
+
+        let oa = OnionAddressV3::from_str("p53lf57qovyuvwsc6xnrppyply3vtqm7l6pcobkmyqsiofyeznfu5uqd").unwrap();
+        block_on(async move {
+            let mut stream = TestStream::new(concat!(
+                "250-ONION_CLIENT_AUTH_VIEW p53lf57qovyuvwsc6xnrppyply3vtqm7l6pcobkmyqsiofyeznfu5uqd\r\n",
+                "250-CLIENT p53lf57qovyuvwsc6xnrppyply3vtqm7l6pcobkmyqsiofyeznfu5uqd x25519:yLcvBzVBd2G2q4xIvKfgaKWYcjaWzwM4iUFxXJ4UUlQ= Flags=Permanent\r\n",
+                "250 OK\r\n",
+                "250 OK\r\n",
+                "552 Unrecognized v3 addr \"p53lf57qovyuvwsc6xnrppyply3vtqm7l6pcobkmyqsiofyeznfu5uqd\"\r\n",
+                "552 Unrecognized v3 addr \"p53lf57qovyuvwsc6xnrppyply3vtqm7l6pcobkmyqsiofyeznfu5uqd\"\r\n",
+            ).as_bytes());
+            let mut conn = AuthenticatedConn::from(Conn::new(&mut stream));
+            conn.set_async_event_handler(
+                Some(|_| async move { Ok(()) })
+            );
+            let res = conn.onion_client_auth_view(Some(&oa)).await.unwrap();
+            assert_eq!(res.len(), 1);
+            assert_eq!(res[0].onion_address, oa);
+            assert_eq!(res[0].key_type, "x25519");
+            assert_eq!(res[0].flags, vec!["Permanent".to_string()]);
+
+            conn.onion_client_auth_remove(&oa).await.unwrap();
+
+            assert!(matches!(
+                conn.onion_client_auth_view(Some(&oa)).await,
+                Err(ConnError::TorError(crate::control::TorErrorKind::UnrecognizedEntity, _))
+            ));
+            assert!(matches!(
+                conn.onion_client_auth_remove(&oa).await,
+                Err(ConnError::TorError(crate::control::TorErrorKind::UnrecognizedEntity, _))
+            ));
+            assert_eq!(
+                std::str::from_utf8(&stream.output).unwrap(),
+                concat!(
+                "ONION_CLIENT_AUTH_VIEW p53lf57qovyuvwsc6xnrppyply3vtqm7l6pcobkmyqsiofyeznfu5uqd\r\n",
+                "ONION_CLIENT_AUTH_REMOVE p53lf57qovyuvwsc6xnrppyply3vtqm7l6pcobkmyqsiofyeznfu5uqd\r\n",
+                "ONION_CLIENT_AUTH_VIEW p53lf57qovyuvwsc6xnrppyply3vtqm7l6pcobkmyqsiofyeznfu5uqd\r\n",
+                "ONION_CLIENT_AUTH_REMOVE p53lf57qovyuvwsc6xnrppyply3vtqm7l6pcobkmyqsiofyeznfu5uqd\r\n",
+                )
+            );
+        });
+    }
+
+    #[test]
+    fn test_can_connect() {
+        block_on(async move {
+            let mut stream = TestStream::new(concat!(
+                "250-PROTOCOLINFO 1\r\n",
+                "250-AUTH METHODS=NULL\r\n",
+                "250-VERSION Tor=\"0.4.2.5\"\r\n",
+                "250 OK\r\n",
+                "250 OK\r\n",
+            ).as_bytes());
+            let _conn: AuthenticatedConn<_, NoopHandler> = AuthenticatedConn::connect(&mut stream, None).await.unwrap();
+            assert_eq!(stream.output, b"PROTOCOLINFO 1\r\nAUTHENTICATE\r\n");
+        });
+        block_on(async move {
+            let mut stream = TestStream::new(concat!(
+                "250-PROTOCOLINFO 1\r\n",
+                "250-AUTH METHODS=HASHEDPASSWORD\r\n",
+                "250-VERSION Tor=\"0.4.2.5\"\r\n",
+                "250 OK\r\n",
+            ).as_bytes());
+            let res = AuthenticatedConn::<_, NoopHandler>::connect(&mut stream, None).await;
+            match res {
+                Err(ConnError::UnauthenticatedConnError(UnauthenticatedConnError::NoAuthDataAvailable)) => {}
+                _ => panic!("Expected NoAuthDataAvailable error"),
+            }
+        });
+    }
+
+    #[test]
+    fn test_can_get_inner_stream() {
+        block_on(async move {
+            let stream = TestStream::new(b"250 OK\r\n");
+            let mut conn = AuthenticatedConn::from(Conn::new(stream));
+            conn.set_async_event_handler(
+                Some(|_| async move { Ok(()) })
+            );
+            conn.use_feature(&mut ["EXTENDED_EVENTS"].iter().copied()).await.unwrap();
+            assert_eq!(conn.get_ref().output, b"USEFEATURE EXTENDED_EVENTS\r\n");
+
+            let stream = conn.into_inner();
+            assert_eq!(stream.output, b"USEFEATURE EXTENDED_EVENTS\r\n");
+        });
+    }
+
+    #[test]
+    fn test_can_set_conf_multiple_values_for_same_key() {
+        block_on(async move {
+            let mut stream = TestStream::new(b"250 OK\r\n");
+            let mut conn = AuthenticatedConn::from(Conn::new(&mut stream));
+            conn.set_async_event_handler(
+                Some(|_| async move { Ok(()) })
+            );
+            conn.set_conf_multiple(&mut [
+                ("SocksPort", Some("9050")),
+                ("SocksPort", Some("127.0.0.1:9150")),
+            ].iter().copied()).await.unwrap();
+            assert_eq!(
+                std::str::from_utf8(&stream.output).unwrap(),
+                "SETCONF SocksPort=\"9050\" SocksPort=\"127.0.0.1:9150\"\r\n"
+            );
+        });
+    }
+
+    #[test]
+    fn test_set_conf_rejects_invalid_keys() {
+        for key in [
+            "",
+            "SocksPort\r\nSETCONF EvilOption",
+            "SocksPort\r\n SETCONF eviloption",
+            "SocksPort SETCONF",
+            "SocksPort=1",
+            "Socks/Port",
+            "SocksPort\0",
+        ].iter() {
+            block_on(async move {
+                let mut stream = TestStream::new(b"250 OK\r\n");
+                let mut conn = AuthenticatedConn::from(Conn::new(&mut stream));
+                conn.set_async_event_handler(
+                    Some(|_| async move { Ok(()) })
+                );
+                assert!(matches!(
+                    conn.set_conf(key, Some("1")).await,
+                    Err(ConnError::AuthenticatedConnError(AuthenticatedConnError::InvalidKeywordValue))
+                ), "{:?}", key);
+                assert!(stream.output.is_empty(), "{:?}", key);
+            });
+        }
+    }
+
+    #[test]
+    fn test_set_conf_escapes_control_chars_in_values() {
+        for (value, line) in [
+            ("9050\r\nSETCONF EvilOption=1", "SETCONF SocksPort=\"9050\\r\\nSETCONF\\40EvilOption=1\"\r\n"),
+            ("\n", "SETCONF SocksPort=\"\\n\"\r\n"),
+            ("\r", "SETCONF SocksPort=\"\\r\"\r\n"),
+            ("\0\x07\x1b", "SETCONF SocksPort=\"\\0\\7\\33\"\r\n"),
+            ("\"quoted\"", "SETCONF SocksPort=\"\\\"quoted\\\"\"\r\n"),
+        ].iter() {
+            assert_eq!(
+                make_set_conf_call(&mut std::iter::once(("SocksPort", Some(*value)))).unwrap().unwrap(),
+                *line,
+                "{:?}", value
+            );
+        }
+        assert_eq!(make_set_conf_call(&mut std::iter::empty()).unwrap(), None);
+    }
+
+    #[test]
+    fn test_can_get_and_set_learn_circuit_build_timeout() {
+        block_on(async move {
+            let mut stream = TestStream::new(concat!(
+                "250 OK\r\n",
+                "250 LearnCircuitBuildTimeout=0\r\n",
+                "250 LearnCircuitBuildTimeout=1\r\n",
+                "250 LearnCircuitBuildTimeout\r\n",
+                "250 LearnCircuitBuildTimeout=yes\r\n",
+            ).as_bytes());
+            let mut conn = AuthenticatedConn::from(Conn::new(&mut stream));
+            conn.set_async_event_handler(
+                Some(|_| async move { Ok(()) })
+            );
+            conn.set_learn_circuit_build_timeout(false).await.unwrap();
+            assert!(!conn.get_learn_circuit_build_timeout().await.unwrap());
+            assert!(conn.get_learn_circuit_build_timeout().await.unwrap());
+            assert!(conn.get_learn_circuit_build_timeout().await.unwrap());
+            assert!(matches!(conn.get_learn_circuit_build_timeout().await, Err(ConnError::InvalidFormat)));
+            assert_eq!(std::str::from_utf8(&stream.output).unwrap(), concat!(
+                "SETCONF LearnCircuitBuildTimeout=\"0\"\r\n",
+                "GETCONF LearnCircuitBuildTimeout\r\n",
+                "GETCONF LearnCircuitBuildTimeout\r\n",
+                "GETCONF LearnCircuitBuildTimeout\r\n",
+                "GETCONF LearnCircuitBuildTimeout\r\n",
+            ));
+        });
+    }
+
+    #[test]
+    fn test_can_get_and_set_leave_streams_unattached() {
+        block_on(async move {
+            let mut stream = TestStream::new(concat!(
+                "250 OK\r\n",
+                "250 __LeaveStreamsUnattached=1\r\n",
+                "250 OK\r\n",
+                "250 __LeaveStreamsUnattached\r\n",
+            ).as_bytes());
+            let mut conn = AuthenticatedConn::from(Conn::new(&mut stream));
+            conn.set_async_event_handler(
+                Some(|_| async move { Ok(()) })
+            );
+            conn.set_leave_streams_unattached(true).await.unwrap();
+            assert!(conn.get_leave_streams_unattached().await.unwrap());
+            conn.set_leave_streams_unattached(false).await.unwrap();
+            assert!(!conn.get_leave_streams_unattached().await.unwrap());
+            assert_eq!(std::str::from_utf8(&stream.output).unwrap(), concat!(
+                "SETCONF __LeaveStreamsUnattached=\"1\"\r\n",
+                "GETCONF __LeaveStreamsUnattached\r\n",
+                "SETCONF __LeaveStreamsUnattached=\"0\"\r\n",
+                "GETCONF __LeaveStreamsUnattached\r\n",
+            ));
+        });
+    }
+
+    #[test]
+    fn test_can_set_conf_value() {
+        block_on(async move {
+            let mut stream = TestStream::new(b"250 OK\r\n250 OK\r\n250 OK\r\n");
+            let mut conn = AuthenticatedConn::from(Conn::new(&mut stream));
+            conn.set_async_event_handler(
+                Some(|_| async move { Ok(()) })
+            );
+            conn.set_conf_value("DisableNetwork", true.into()).await.unwrap();
+            conn.set_conf_value("SocksPort", 9050u16.into()).await.unwrap();
+            conn.set_conf_value("SocksPort", ConfValue::None).await.unwrap();
+            assert_eq!(
+                std::str::from_utf8(&stream.output).unwrap(),
+                "SETCONF DisableNetwork=\"1\"\r\nSETCONF SocksPort=\"9050\"\r\nSETCONF SocksPort\r\n"
+            );
+        });
+    }
+
+    #[test]
+    fn test_can_connect_to_address() {
+        use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+        block_on_with_env(async move {
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let addr = listener.local_addr().unwrap();
+            let server = tokio::spawn(async move {
+                let (stream, _) = listener.accept().await.unwrap();
+                let mut stream = BufReader::new(stream);
+                let mut commands = Vec::new();
+                for reply in [
+                    &b"250-PROTOCOLINFO 1\r\n250-AUTH METHODS=NULL\r\n250-VERSION Tor=\"0.4.5.7\"\r\n250 OK\r\n"[..],
+                    &b"250 OK\r\n"[..],
+                    &b"250-version=0.4.5.7\r\n250 OK\r\n"[..],
+                ].iter() {
+                    let mut line = String::new();
+                    stream.read_line(&mut line).await.unwrap();
+                    commands.push(line);
+                    stream.get_mut().write_all(reply).await.unwrap();
+                }
+                commands
+            });
+
+            let mut conn: AuthenticatedConn<_, NoopHandler> = connect(addr).await.unwrap();
+            assert_eq!(conn.get_info("version").await.unwrap(), "0.4.5.7");
+            assert_eq!(server.await.unwrap(), vec![
+                "PROTOCOLINFO 1\r\n",
+                "AUTHENTICATE\r\n",
+                "GETINFO version\r\n",
+            ]);
+        });
+    }
+
+    #[test]
+    fn test_set_conf_accepts_unnecessary_operation_reply() {
+        block_on(async move {
+            let mut stream = TestStream::new(b"251 Operation was unnecessary\r\n252 Unknown\r\n250 OK\r\n");
+            let mut conn = AuthenticatedConn::from(Conn::new(&mut stream));
+            conn.set_async_event_handler(
+                Some(|_| async move { Ok(()) })
+            );
+            conn.set_conf("DisableNetwork", Some("1")).await.unwrap();
+            assert!(matches!(
+                conn.set_conf("DisableNetwork", Some("1")).await,
+                Err(ConnError::InvalidResponseCode(252))
+            ));
+            conn.set_conf("DisableNetwork", Some("0")).await.unwrap();
+        });
+    }
+
+    #[test]
+    fn test_set_conf_handles_async_event_before_reply() {
+        block_on(async move {
+            let mut stream = TestStream::new(concat!(
+                "650 CIRC 1 BUILT\r\n",
+                "250 OK\r\n",
+                "552 Unrecognized option: Unknown option 'Invalid'.  Failing.\r\n",
+                "250 OK\r\n",
+            ).as_bytes());
+            let events = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+            let mut conn = AuthenticatedConn::from(Conn::new(&mut stream));
+            let handler_events = events.clone();
+            conn.set_async_event_handler(Some(move |event: AsyncEvent<'static>| {
+                handler_events.lock().unwrap().push(event.lines[0].to_string());
+                async move { Ok(()) }
+            }));
+            conn.set_conf("DisableNetwork", Some("1")).await.unwrap();
+            assert_eq!(*events.lock().unwrap(), vec!["CIRC 1 BUILT".to_string()]);
+
+            match conn.set_conf("Invalid", Some("1")).await {
+                Err(ConnError::TorError(crate::control::TorErrorKind::UnrecognizedEntity, lines)) => {
+                    assert_eq!(lines, vec!["Unrecognized option: Unknown option 'Invalid'.  Failing.".to_string()]);
+                }
+                res => panic!("Unexpected result {:?}", res),
+            }
+            // connection stays in sync
+            conn.set_conf("DisableNetwork", Some("0")).await.unwrap();
+        });
+    }
+
+    #[test]
+    fn test_can_send_signal() {
+        block_on(async move {
+            let mut stream = TestStream::new(b"250 OK\r\n250-status/circuit-established=0\r\n250 OK\r\n552 Unrecognized signal\r\n");
+            let mut conn = AuthenticatedConn::from(Conn::new(&mut stream));
+            conn.set_async_event_handler(
+                Some(|_| async move { Ok(()) })
+            );
+            assert_eq!(conn.signal(TorSignal::NewNym).await.unwrap(), vec!["OK"]);
+            assert!(!conn.is_circuit_established().await.unwrap());
+            conn.signal(TorSignal::Heartbeat).await.unwrap_err();
+            assert_eq!(
+                std::str::from_utf8(&stream.output).unwrap(),
+                "SIGNAL NEWNYM\r\nGETINFO status/circuit-established\r\nSIGNAL HEARTBEAT\r\n"
+            );
+        });
+    }
+
+    #[test]
+    fn test_can_get_listeners() {
+        block_on(async move {
+            let mut stream = TestStream::new(concat!(
+                "250-net/listeners/socks=\"127.0.0.1:9050\" \"unix:/run/tor/socks\"\r\n250 OK\r\n",
+                "250-net/listeners/dir=\r\n250 OK\r\n",
+                "250-net/listeners/or=\"localhost\"\r\n250 OK\r\n",
+            ).as_bytes());
+            let mut conn = AuthenticatedConn::from(Conn::new(&mut stream));
+            conn.set_async_event_handler(
+                Some(|_| async move { Ok(()) })
+            );
+            assert_eq!(conn.get_listeners("socks").await.unwrap(), vec![
+                ListenerAddr::Tcp("127.0.0.1:9050".parse().unwrap()),
+                ListenerAddr::Unix("/run/tor/socks".into()),
+            ]);
+            assert_eq!(conn.get_listeners("dir").await.unwrap(), vec![]);
+            assert!(matches!(conn.get_listeners("or").await, Err(ConnError::InvalidFormat)));
+            assert!(matches!(
+                conn.get_listeners("socks/../control").await,
+                Err(ConnError::AuthenticatedConnError(AuthenticatedConnError::InvalidKeywordValue))
+            ));
+            assert_eq!(
+                std::str::from_utf8(&stream.output).unwrap(),
+                "GETINFO net/listeners/socks\r\nGETINFO net/listeners/dir\r\nGETINFO net/listeners/or\r\n",
+            );
+        });
+    }
+
+    #[test]
+    fn test_can_get_circuits_by_purpose() {
+        block_on(async move {
+            let mut stream = TestStream::new(concat!(
+                "250+circuit-status=\r\n",
+                "1 BUILT $7EA6EAD6FD83083C538F44038BBFA077587DD755~moria1 PURPOSE=GENERAL\r\n",
+                "2 BUILT $7EA6EAD6FD83083C538F44038BBFA077587DD755~moria1 PURPOSE=HS_SERVICE_REND\r\n",
+                "3 LAUNCHED PURPOSE=HS_SERVICE_REND\r\n",
+                ".\r\n250 OK\r\n",
+                "250-circuit-status=\r\n250 OK\r\n",
+                "250+circuit-status=\r\n1 UNKNOWN\r\n.\r\n250 OK\r\n",
+            ).as_bytes());
+            let mut conn = AuthenticatedConn::from(Conn::new(&mut stream));
+            conn.set_async_event_handler(
+                Some(|_| async move { Ok(()) })
+            );
+            let circuits = conn.get_circuits_by_purpose("HS_SERVICE_REND").await.unwrap();
+            assert_eq!(
+                circuits.iter().map(|c| c.id.to_string()).collect::<Vec<_>>(),
+                vec!["2", "3"],
+            );
+            assert!(conn.get_circuits_by_purpose("GENERAL").await.unwrap().is_empty());
+            assert!(matches!(conn.get_circuits_by_purpose("GENERAL").await, Err(ConnError::InvalidFormat)));
+            assert_eq!(
+                std::str::from_utf8(&stream.output).unwrap(),
+                "GETINFO circuit-status\r\nGETINFO circuit-status\r\nGETINFO circuit-status\r\n",
+            );
+        });
+    }
+
+    #[test]
+    fn test_can_get_version_status() {
+        block_on(async move {
+            let mut stream = TestStream::new(concat!(
+                "250-status/version/current=recommended\r\n250 OK\r\n",
+                "250-status/version/current=new in series\r\n250 OK\r\n",
+                "250-status/version/current=something new\r\n250 OK\r\n",
+            ).as_bytes());
+            let mut conn = AuthenticatedConn::from(Conn::new(&mut stream));
+            conn.set_async_event_handler(
+                Some(|_| async move { Ok(()) })
+            );
+            assert_eq!(conn.version_status().await.unwrap(), VersionStatus::Recommended);
+            assert_eq!(conn.version_status().await.unwrap(), VersionStatus::NewInSeries);
+            assert_eq!(conn.version_status().await.unwrap(), VersionStatus::Unknown);
+            assert_eq!(
+                std::str::from_utf8(&stream.output).unwrap(),
+                "GETINFO status/version/current\r\n".repeat(3),
+            );
+        });
+    }
+
+    #[test]
+    fn test_can_check_dormant() {
+        block_on(async move {
+            let mut stream = TestStream::new(concat!(
+                "250 OK\r\n",
+                "250-dormant=1\r\n250 OK\r\n",
+                "250 OK\r\n",
+                "250-dormant=0\r\n250 OK\r\n",
+                "250-dormant=yes\r\n250 OK\r\n",
+                "552 Unrecognized key \"dormant\"\r\n",
+            ).as_bytes());
+            let mut conn = AuthenticatedConn::from(Conn::new(&mut stream));
+            conn.set_async_event_handler(
+                Some(|_| async move { Ok(()) })
+            );
+            conn.signal(TorSignal::Dormant).await.unwrap();
+            assert!(conn.is_dormant().await.unwrap());
+            conn.signal(TorSignal::Active).await.unwrap();
+            assert!(!conn.is_dormant().await.unwrap());
+            assert!(matches!(conn.is_dormant().await, Err(ConnError::InvalidFormat)));
+            assert!(matches!(conn.is_dormant().await, Err(ConnError::TorError(crate::control::TorErrorKind::UnrecognizedEntity, _))));
+            assert_eq!(
+                std::str::from_utf8(&stream.output).unwrap(),
+                concat!(
+                    "SIGNAL DORMANT\r\nGETINFO dormant\r\n",
+                    "SIGNAL ACTIVE\r\nGETINFO dormant\r\n",
+                    "GETINFO dormant\r\nGETINFO dormant\r\n",
+                ),
+            );
+        });
+    }
+
+    #[test]
+    fn test_can_drop_timeouts() {
+        block_on(async move {
+            let mut stream = TestStream::new(b"250 OK\r\n510 Unrecognized command \"DROPTIMEOUTS\"\r\n");
+            let mut conn = AuthenticatedConn::from(Conn::new(&mut stream));
+            conn.set_async_event_handler(
+                Some(|_| async move { Ok(()) })
+            );
+            conn.drop_timeouts().await.unwrap();
+            let err = conn.drop_timeouts().await.unwrap_err();
+            assert!(matches!(err, ConnError::TorError(crate::control::TorErrorKind::UnrecognizedCmd, _)));
+            assert_eq!(stream.output, b"DROPTIMEOUTS\r\nDROPTIMEOUTS\r\n");
+        });
+    }
+
+    #[test]
+    fn test_can_get_traffic_counters() {
+        block_on(async move {
+            let mut stream = TestStream::new(concat!(
+                "250-traffic/read=18446744073709551615\r\n250 OK\r\n",
+                "250-traffic/written=1234\r\n250 OK\r\n",
+                "250-traffic/read=-1\r\n250 OK\r\n",
+            ).as_bytes());
+            let mut conn = AuthenticatedConn::from(Conn::new(&mut stream));
+            conn.set_async_event_handler(
+                Some(|_| async move { Ok(()) })
+            );
+            assert_eq!(conn.traffic_read().await.unwrap(), u64::MAX);
+            assert_eq!(conn.traffic_written().await.unwrap(), 1234);
+            assert!(matches!(conn.traffic_read().await, Err(ConnError::ParseIntError(_))));
+            assert_eq!(stream.output, b"GETINFO traffic/read\r\nGETINFO traffic/written\r\nGETINFO traffic/read\r\n");
+        });
+    }
+
+    #[test]
+    fn test_can_take_ownership_with_pid() {
+        block_on(async move {
+            let mut stream = TestStream::new(b"250 OK\r\n250 OK\r\n");
+            let mut conn = AuthenticatedConn::from(Conn::new(&mut stream));
+            conn.set_async_event_handler(
+                Some(|_| async move { Ok(()) })
+            );
+            conn.take_ownership_with_pid(1234).await.unwrap();
+            assert_eq!(
+                std::str::from_utf8(&stream.output).unwrap(),
+                "SETCONF __OwningControllerProcess=\"1234\"\r\nTAKEOWNERSHIP\r\n"
+            );
+        });
+    }
+
+    #[test]
+    fn test_can_resolve() {
+        block_on(async move {
+            let mut stream = TestStream::new(b"250 OK\r\n250 OK\r\n250 OK\r\n250 OK\r\n");
+            let mut conn = AuthenticatedConn::from(Conn::new(&mut stream));
+            conn.set_async_event_handler(
+                Some(|_| async move { Ok(()) })
+            );
+            conn.resolve("example.com").await.unwrap();
+            conn.resolve(&"example.org".to_string()).await.unwrap();
+            conn.resolve(Ipv4Addr::new(127, 0, 0, 1)).await.unwrap();
+            conn.resolve(std::net::Ipv6Addr::LOCALHOST).await.unwrap();
+            match conn.resolve("example.com\r\nSIGNAL HALT").await {
+                Err(ConnError::AuthenticatedConnError(AuthenticatedConnError::InvalidHostnameValue)) => {}
+                _ => panic!("Invalid hostname should be rejected"),
+            }
+            assert_eq!(
+                std::str::from_utf8(&stream.output).unwrap(),
+                concat!(
+                    "RESOLVE example.com\r\n",
+                    "RESOLVE example.org\r\n",
+                    "RESOLVE 127.0.0.1\r\n",
+                    "RESOLVE [::1]\r\n",
+                ),
+            );
+        });
+    }
+
+    #[test]
+    fn test_can_redirect_stream() {
+        block_on(async move {
+            let mut stream = TestStream::new(b"250 OK\r\n250 OK\r\n250 OK\r\n552 Unknown stream \"3\"\r\n");
+            let mut conn = AuthenticatedConn::from(Conn::new(&mut stream));
+            conn.set_async_event_handler(
+                Some(|_| async move { Ok(()) })
+            );
+            conn.redirect_stream(1, "example.com", Some(443)).await.unwrap();
+            conn.redirect_stream(2, "127.0.0.1", None).await.unwrap();
+            conn.redirect_stream(StreamId::Other("abc2".to_string()), "::1", None).await.unwrap();
+            assert!(conn.redirect_stream(3, "example.com", None).await.is_err());
+            for addr in ["", "example.com 80", "example.com\r\nSIGNAL HALT", "1.2.3.4:80"].iter() {
+                match conn.redirect_stream(4, addr, None).await {
+                    Err(ConnError::AuthenticatedConnError(AuthenticatedConnError::InvalidHostnameValue)) => {}
+                    _ => panic!("Address {:?} should be rejected", addr),
+                }
+            }
+            assert_eq!(
+                std::str::from_utf8(&stream.output).unwrap(),
+                concat!(
+                    "REDIRECTSTREAM 1 example.com 443\r\n",
+                    "REDIRECTSTREAM 2 127.0.0.1\r\n",
+                    "REDIRECTSTREAM abc2 ::1\r\n",
+                    "REDIRECTSTREAM 3 example.com\r\n",
+                ),
+            );
+        });
+    }
+
+    #[test]
+    fn test_can_use_stateful_async_event_handler() {
+        block_on(async move {
+            let mut stream = TestStream::new(b"650 CIRC 1 BUILT\r\n650 CIRC 2 BUILT\r\n250 OK\r\n");
+            let mut events = Vec::new();
+            {
+                let mut conn = AuthenticatedConn::from(Conn::new(&mut stream));
+                conn.set_async_event_handler(Some(|event: AsyncEvent<'static>| {
+                    events.push(event.lines[0].to_string());
+                    std::future::ready(Ok(()))
+                }));
+                conn.take_ownership().await.unwrap();
+            }
+            assert_eq!(events, vec!["CIRC 1 BUILT", "CIRC 2 BUILT"]);
+        });
+    }
+
+    #[test]
+    fn test_can_handle_empty_async_event() {
+        block_on(async move {
+            let mut stream = TestStream::new(b"650 \r\n250 OK\r\n250 \r\n");
+            let mut events = Vec::new();
+            {
+                let mut conn = AuthenticatedConn::from(Conn::new(&mut stream));
+                conn.set_async_event_handler(Some(|event: AsyncEvent<'static>| {
+                    events.push(event.lines.iter().map(|l| l.to_string()).collect::<Vec<_>>());
+                    std::future::ready(Ok(()))
+                }));
+                conn.take_ownership().await.unwrap();
+                conn.drop_ownership().await.unwrap();
+            }
+            assert_eq!(events, vec![vec![String::new()]]);
+        });
+    }
+
+    #[test]
+    fn test_can_get_info_streaming() {
+        block_on(async move {
+            let mut stream = TestStream::new(
+                b"650+NS\r\nr event line\r\n.\r\n650 OK\r\n\
+                250+dir/status-vote/current/consensus=\r\nnetwork-status-version 3\r\n..dot line\r\n.\r\n250 OK\r\n"
+            );
+            let mut events = Vec::new();
+            {
+                let mut conn = AuthenticatedConn::from(Conn::new(&mut stream));
+                conn.set_async_event_handler(Some(|event: AsyncEvent<'static>| {
+                    events.push(event.lines.iter().map(|l| l.to_string()).collect::<Vec<_>>());
+                    std::future::ready(Ok(()))
+                }));
+                let mut res = Vec::new();
+                conn.get_info_streaming("dir/status-vote/current/consensus", |line| res.push(line.to_string())).await.unwrap();
+                assert_eq!(res, vec!["network-status-version 3", ".dot line"]);
+            }
+            assert_eq!(events, vec![vec!["NS\r\nr event line".to_string(), "OK".to_string()]]);
+            assert_eq!(stream.output, b"GETINFO dir/status-vote/current/consensus\r\n");
+        });
+        block_on(async move {
+            let mut stream = TestStream::new(b"250-version=0.4.5.6\r\n250 OK\r\n");
+            let mut conn = AuthenticatedConn::from(Conn::new(&mut stream));
+            conn.set_async_event_handler(
+                Some(|_| async move { Ok(()) })
+            );
+            let mut res = Vec::new();
+            conn.get_info_streaming("version", |line| res.push(line.to_string())).await.unwrap();
+            assert_eq!(res, vec!["0.4.5.6"]);
+        });
+        block_on(async move {
+            let mut stream = TestStream::new(b"552 Unrecognized key \"nope\"\r\n");
+            let mut conn = AuthenticatedConn::from(Conn::new(&mut stream));
+            conn.set_async_event_handler(
+                Some(|_| async move { Ok(()) })
+            );
+            let err = conn.get_info_streaming("nope", |_| {}).await.unwrap_err();
+            assert!(matches!(err, ConnError::TorError(crate::control::TorErrorKind::UnrecognizedEntity, _)));
+        });
+    }
+
+    #[test]
+    fn test_can_set_events_typed() {
+        block_on(async move {
+            let mut stream = TestStream::new(b"250 OK\r\n250 OK\r\n");
+            let mut conn = AuthenticatedConn::from(Conn::new(&mut stream));
+            conn.set_async_event_handler(
+                Some(|_| async move { Ok(()) })
+            );
+            conn.set_events_typed(true, &[
+                AsyncEventKind::CircuitStatusChanged,
+                AsyncEventKind::StatusClient,
+                AsyncEventKind::HiddenServiceDescriptors,
+            ]).await.unwrap();
+            assert_eq!(conn.subscribed_events(), ["CIRC", "STATUS_CLIENT", "HS_DESC"]);
+            conn.set_events_typed(false, &[]).await.unwrap();
+            assert_eq!(stream.output, b"SETEVENTS EXTENDED CIRC STATUS_CLIENT HS_DESC\r\nSETEVENTS\r\n");
+        });
+    }
+
+    #[test]
+    fn test_can_subscribe_and_unsubscribe_events() {
+        block_on(async move {
+            let mut stream = TestStream::new(concat!(
+                "250 OK\r\n",
+                "250 OK\r\n",
+                "250 OK\r\n",
+                "552 Unrecognized event \"STREAM\"\r\n",
+                "250 OK\r\n",
+            ).as_bytes());
+            let mut conn = AuthenticatedConn::from(Conn::new(&mut stream));
+            conn.set_async_event_handler(
+                Some(|_| async move { Ok(()) })
+            );
+            conn.set_events(false, &mut ["CIRC"].iter().copied()).await.unwrap();
+            conn.subscribe_event(AsyncEventKind::HiddenServiceDescriptors).await.unwrap();
+            // already subscribed, nothing is sent
+            conn.subscribe_event(AsyncEventKind::CircuitStatusChanged).await.unwrap();
+            assert_eq!(conn.subscribed_events(), ["CIRC", "HS_DESC"]);
+
+            conn.unsubscribe_event(AsyncEventKind::CircuitStatusChanged).await.unwrap();
+            // not subscribed, nothing is sent
+            conn.unsubscribe_event(AsyncEventKind::StreamStatusChanged).await.unwrap();
+            assert_eq!(conn.subscribed_events(), ["HS_DESC"]);
+
+            // rejected subscription does not change tracked set
+            conn.subscribe_event(AsyncEventKind::StreamStatusChanged).await.unwrap_err();
+            assert_eq!(conn.subscribed_events(), ["HS_DESC"]);
+
+            conn.unsubscribe_event(AsyncEventKind::HiddenServiceDescriptors).await.unwrap();
+            assert!(conn.subscribed_events().is_empty());
+
+            assert_eq!(std::str::from_utf8(&stream.output).unwrap(), concat!(
+                "SETEVENTS CIRC\r\n",
+                "SETEVENTS CIRC HS_DESC\r\n",
+                "SETEVENTS HS_DESC\r\n",
+                "SETEVENTS HS_DESC STREAM\r\n",
+                "SETEVENTS\r\n",
+            ));
+        });
+    }
+
+    #[test]
+    fn test_can_use_feature() {
+        block_on(async move {
+            let mut stream = TestStream::new(b"250 OK\r\n");
+            let mut conn = AuthenticatedConn::from(Conn::new(&mut stream));
+            conn.set_async_event_handler(
+                Some(|_| async move { Ok(()) })
+            );
+            conn.use_feature(&mut ["EXTENDED_EVENTS", "VERBOSE_NAMES"].iter().copied()).await.unwrap();
+            assert_eq!(stream.output, b"USEFEATURE EXTENDED_EVENTS VERBOSE_NAMES\r\n");
+        });
+        block_on(async move {
+            let mut stream = TestStream::new(b"");
+            let mut conn = AuthenticatedConn::from(Conn::new(&mut stream));
+            conn.set_async_event_handler(
+                Some(|_| async move { Ok(()) })
+            );
+            conn.use_feature(&mut ["VERBOSE_NAMES\r\nDROPGUARDS"].iter().copied()).await.unwrap_err();
+            assert!(stream.output.is_empty());
+        });
+    }
+
+    #[test]
+    fn test_can_setup_onion_service_call() {
+        let listeners = [
+            (80, SocketAddr::new(Ipv4Addr::new(127, 0, 0, 1).into(), 8080)),
+            (443, SocketAddr::new(Ipv4Addr::new(127, 0, 0, 1).into(), 8443)),
+        ];
+        let res = AuthenticatedConn::<TestStream, NoopHandler>::setup_onion_service_call(
+            false,
+            "KEYBLOB",
+            false,
+            false,
+            false,
+            Some(5),
+            &mut listeners.iter(),
+        ).unwrap();
+        assert_eq!(res, "ADD_ONION ED25519-V3:KEYBLOB Flags=DiscardPK MaxStreams=5 Port=80,127.0.0.1:8080 Port=443,127.0.0.1:8443 ");
+
+        let res = AuthenticatedConn::<TestStream, NoopHandler>::setup_onion_service_call(
+            false,
+            "KEYBLOB",
+            true,
+            false,
+            true,
+            None,
+            &mut listeners.iter(),
+        ).unwrap();
+        assert_eq!(res, "ADD_ONION ED25519-V3:KEYBLOB Flags=DiscardPK,Detach,MaxStreamsCloseCircuit Port=80,127.0.0.1:8080 Port=443,127.0.0.1:8443 ");
+
+        let listeners = [
+            (80, OnionTarget::Unix(PathBuf::from("/run/web.sock"))),
+            (443, OnionTarget::Tcp(SocketAddr::new(Ipv4Addr::new(127, 0, 0, 1).into(), 8443))),
+        ];
+        let res = AuthenticatedConn::<TestStream, NoopHandler>::setup_onion_service_call(
+            false,
+            "KEYBLOB",
+            false,
+            false,
+            false,
+            None,
+            &mut listeners.iter(),
+        ).unwrap();
+        assert_eq!(res, "ADD_ONION ED25519-V3:KEYBLOB Flags=DiscardPK Port=80,unix:\"/run/web.sock\" Port=443,127.0.0.1:8443 ");
+
+        let listeners = [
+            (80, OnionTarget::Unix(PathBuf::from("/run/web.sock"))),
+            (80, OnionTarget::Tcp(SocketAddr::new(Ipv4Addr::new(127, 0, 0, 1).into(), 8080))),
+        ];
+        let res = AuthenticatedConn::<TestStream, NoopHandler>::setup_onion_service_call(
+            false,
+            "KEYBLOB",
+            false,
+            false,
+            false,
+            None,
+            &mut listeners.iter(),
+        ).unwrap();
+        assert_eq!(res, "ADD_ONION ED25519-V3:KEYBLOB Flags=DiscardPK Port=80,unix:\"/run/web.sock\" Port=80,127.0.0.1:8080 ");
+
+        let listeners = [
+            (80, SocketAddr::new(Ipv4Addr::new(127, 0, 0, 1).into(), 8080)),
+            (80, SocketAddr::new(Ipv4Addr::new(127, 0, 0, 1).into(), 8081)),
+        ];
+        let res = AuthenticatedConn::<TestStream, NoopHandler>::setup_onion_service_call(
+            false,
+            "KEYBLOB",
+            false,
+            false,
+            false,
+            None,
+            &mut listeners.iter(),
+        ).unwrap();
+        assert_eq!(res, "ADD_ONION ED25519-V3:KEYBLOB Flags=DiscardPK Port=80,127.0.0.1:8080 Port=80,127.0.0.1:8081 ");
+
+        let listeners = [
+            (80, "[::1]:8080".parse::<SocketAddr>().unwrap()),
+            (443, "[2001:db8::1]:8443".parse().unwrap()),
+        ];
+        let res = AuthenticatedConn::<TestStream, NoopHandler>::setup_onion_service_call(
+            false,
+            "KEYBLOB",
+            false,
+            false,
+            false,
+            None,
+            &mut listeners.iter(),
+        ).unwrap();
+        assert_eq!(res, "ADD_ONION ED25519-V3:KEYBLOB Flags=DiscardPK Port=80,[::1]:8080 Port=443,[2001:db8::1]:8443 ");
+
+        let listeners = [
+            (80, SocketAddr::V6(std::net::SocketAddrV6::new("fe80::1".parse().unwrap(), 8080, 0, 2))),
+        ];
+        let res = AuthenticatedConn::<TestStream, NoopHandler>::setup_onion_service_call(
+            false,
+            "KEYBLOB",
+            false,
+            false,
+            false,
+            None,
+            &mut listeners.iter(),
+        );
+        assert!(matches!(res, Err(AuthenticatedConnError::InvalidListenerSpecification)));
+
+        let res = AuthenticatedConn::<TestStream, NoopHandler>::setup_onion_service_call(
+            false,
+            "KEYBLOB",
+            false,
+            false,
+            false,
+            None,
+            &mut std::iter::empty::<&u16>(),
+        );
+        assert!(matches!(res, Err(AuthenticatedConnError::InvalidListenerSpecification)));
+
+        let res = AuthenticatedConn::<TestStream, NoopHandler>::setup_onion_service_call(
+            false,
+            "KEYBLOB",
+            false,
+            false,
+            false,
+            None,
+            &mut [80u16, 443].iter(),
+        ).unwrap();
+        assert_eq!(res, "ADD_ONION ED25519-V3:KEYBLOB Flags=DiscardPK Port=80 Port=443 ");
+
+        let listeners = [
+            (80, None),
+            (443, Some(OnionTarget::Tcp(SocketAddr::new(Ipv4Addr::new(127, 0, 0, 1).into(), 8443)))),
+        ];
+        let res = AuthenticatedConn::<TestStream, NoopHandler>::setup_onion_service_call(
+            false,
+            "KEYBLOB",
+            false,
+            false,
+            false,
+            None,
+            &mut listeners.iter(),
+        ).unwrap();
+        assert_eq!(res, "ADD_ONION ED25519-V3:KEYBLOB Flags=DiscardPK Port=80 Port=443,127.0.0.1:8443 ");
+    }
+
+    #[cfg(feature = "v3")]
+    #[test]
+    fn test_can_add_onion_v3() {
+        block_on(async move {
+            let key = crate::onion::TorSecretKeyV3::generate();
+            let addr = key.public().get_onion_address();
+            let input = format!("250-ServiceID={}\r\n250 OK\r\n", addr.get_address_without_dot_onion());
+            let mut stream = TestStream::new(input.as_bytes());
+            let mut conn = AuthenticatedConn::from(Conn::new(&mut stream));
+            conn.set_async_event_handler(
+                Some(|_| async move { Ok(()) })
+            );
+            let res = conn.add_onion_v3(&key, false, false, false, None, &mut [80u16].iter()).await.unwrap();
+            assert_eq!(res, crate::onion::OnionAddress::V3(addr));
+            // key is not retained unless tracking is enabled
+            assert_eq!(conn.to_state().onion_services, vec![]);
+        });
+    }
+
+    #[cfg(feature = "v3")]
+    #[test]
+    fn test_can_add_onion_v3_opts() {
+        block_on(async move {
+            let key = crate::onion::TorSecretKeyV3::generate();
+            let addr = key.public().get_onion_address();
+            let input = format!("250-ServiceID={}\r\n250 OK\r\n", addr.get_address_without_dot_onion());
+            let mut stream = TestStream::new(input.as_bytes());
+            {
+                let mut conn = AuthenticatedConn::from(Conn::new(&mut stream));
+                conn.set_async_event_handler(
+                    Some(|_| async move { Ok(()) })
+                );
+                let options = AddOnionOptions {
+                    detach: true,
+                    max_num_streams: Some(3),
+                    ..Default::default()
+                };
+                let res = conn.add_onion_v3_opts(&key, &options, &mut [80u16].iter()).await.unwrap();
+                assert_eq!(res, crate::onion::OnionAddress::V3(addr));
+            }
+            assert_eq!(
+                std::str::from_utf8(&stream.output).unwrap(),
+                format!("ADD_ONION ED25519-V3:{} Flags=DiscardPK,Detach MaxStreams=3 Port=80 \r\n", key.to_base64()),
+            );
+        });
+    }
+
+    #[test]
+    #[cfg(feature = "v3")]
+    fn test_add_onion_v3_opts_returns_tor_error() {
+        block_on(async move {
+            let key = crate::onion::TorSecretKeyV3::generate();
+            let mut stream = TestStream::new(b"550 Onion address collision\r\n512 Invalid argument\r\n");
+            let mut conn = AuthenticatedConn::from(Conn::new(&mut stream));
+            conn.set_async_event_handler(
+                Some(|_| async move { Ok(()) })
+            );
+            conn.set_track_onion_services(true);
+            match conn.add_onion_v3_opts(&key, &AddOnionOptions::default(), &mut [80u16].iter()).await {
+                Err(ConnError::TorError(crate::control::TorErrorKind::UnspecifiedTorError, lines)) => {
+                    assert_eq!(lines, vec!["Onion address collision".to_string()]);
+                }
+                res => panic!("Unexpected result {:?}", res),
+            }
+            assert!(matches!(
+                conn.add_onion_v3_opts(&key, &AddOnionOptions::default(), &mut [80u16].iter()).await,
+                Err(ConnError::TorError(crate::control::TorErrorKind::SyntaxErrorCmdArg, _))
+            ));
+            // failed services are not tracked
+            assert_eq!(conn.to_state().onion_services, vec![]);
+        });
+    }
+
+    #[test]
+    #[cfg(feature = "v3")]
+    fn test_can_save_and_restore_state() {
+        let key = crate::onion::TorSecretKeyV3::generate();
+        let addr = key.public().get_onion_address().get_address_without_dot_onion();
+        let other_key = crate::onion::TorSecretKeyV3::generate();
+        let other_addr = other_key.public().get_onion_address().get_address_without_dot_onion();
+        let options = AddOnionOptions {
+            detach: true,
+            ..Default::default()
+        };
+
+        let state = block_on(async {
+            let input = format!(
+                "250 OK\r\n250-ServiceID={}\r\n250 OK\r\n250-ServiceID={}\r\n250 OK\r\n250 OK\r\n",
+                addr, other_addr,
+            );
+            let mut stream = TestStream::new(input.as_bytes());
+            let mut conn = AuthenticatedConn::from(Conn::new(&mut stream));
+            conn.set_async_event_handler(
+                Some(|_| async move { Ok(()) })
+            );
+            conn.set_track_onion_services(true);
+            conn.set_events_typed(false, &[AsyncEventKind::HiddenServiceDescriptors]).await.unwrap();
+            conn.add_onion_v3_opts(&key, &options, &mut [80u16].iter()).await.unwrap();
+            conn.add_onion_v3_opts(&other_key, &options, &mut [443u16].iter()).await.unwrap();
+            conn.del_onion(&other_addr).await.unwrap();
+            conn.to_state()
+        });
+        assert_eq!(state.events, ["HS_DESC"]);
+        assert_eq!(state.onion_services.len(), 1);
+        assert_eq!(state.onion_services[0].key, key);
+        assert_eq!(state.onion_services[0].options, options);
+        assert_eq!(state.onion_services[0].listeners, [OnionListener::from(80)]);
+
+        block_on(async {
+            let input = format!("250 OK\r\n250-ServiceID={}\r\n250 OK\r\n250 OK\r\n", addr);
+            let mut stream = TestStream::new(input.as_bytes());
+            {
+                let mut conn = AuthenticatedConn::from(Conn::new(&mut stream));
+                conn.set_async_event_handler(
+                    Some(|_| async move { Ok(()) })
+                );
+                conn.set_track_onion_services(true);
+                conn.restore_state(&state, true).await.unwrap();
+                conn.restore_state(&state, false).await.unwrap();
+                assert_eq!(conn.to_state(), state);
+
+                conn.set_track_onion_services(false);
+                assert_eq!(conn.to_state().onion_services, vec![]);
+            }
+            assert_eq!(std::str::from_utf8(&stream.output).unwrap(), format!(
+                "SETEVENTS HS_DESC\r\nADD_ONION ED25519-V3:{} Flags=DiscardPK,Detach Port=80 \r\nSETEVENTS HS_DESC\r\n",
+                key.to_base64(),
+            ));
+        });
+    }
+
+    #[test]
+    fn test_can_post_descriptor() {
+        block_on(async move {
+            let mut stream = TestStream::new(b"250 OK\r\n");
+            let mut conn = AuthenticatedConn::from(Conn::new(&mut stream));
+            conn.set_async_event_handler(
+                Some(|_| async move { Ok(()) })
+            );
+            let res = conn.post_descriptor(Some("general"), Some(false), "router test\n.\n..dots\nend").await.unwrap();
+            assert_eq!(res, vec!["OK"]);
+            assert_eq!(
+                std::str::from_utf8(&stream.output).unwrap(),
+                "+POSTDESCRIPTOR purpose=general cache=no\r\nrouter test\r\n..\r\n...dots\r\nend\r\n.\r\n"
+            );
+        });
+        block_on(async move {
+            let mut stream = TestStream::new(b"");
+            let mut conn = AuthenticatedConn::from(Conn::new(&mut stream));
+            conn.set_async_event_handler(
+                Some(|_| async move { Ok(()) })
+            );
+            conn.post_descriptor(Some("general\r\nDROPGUARDS"), None, "").await.unwrap_err();
+            assert!(stream.output.is_empty());
+        });
+    }
 }
 
 // TODO(teawithsand): cleanup testing initialization
@@ -795,7 +3294,7 @@ mod test {
 mod test_with_tor {
     use std::thread::sleep;
     use std::time::Duration;
-    use std::net::{IpAddr, Ipv4Addr};
+    use std::net::{IpAddr, Ipv4Addr, SocketAddr};
     use std::str::FromStr;
 
     use tokio::net::{TcpStream};
@@ -805,6 +3304,39 @@ mod test_with_tor {
 
     use super::*;
 
+    #[test]
+    fn test_can_load_conf() {
+        let _c = run_testing_tor_instance(
+            &[
+                "--DisableNetwork", "1",
+                "--ControlPort", &TOR_TESTING_PORT.to_string(),
+            ]);
+
+        block_on_with_env(async move {
+            let s = TcpStream::connect(&format!("127.0.0.1:{}", TOR_TESTING_PORT)).await.unwrap();
+            let mut utc = UnauthenticatedConn::new(s);
+            let proto_info = utc.load_protocol_info().await.unwrap();
+
+            assert!(proto_info.auth_methods.contains(&TorAuthMethod::Null));
+            utc.authenticate(&TorAuthData::Null).await.unwrap();
+            let mut ac = utc.into_authenticated().await;
+            ac.set_async_event_handler(Some(|_| {
+                async move { Ok(()) }
+            }));
+
+            ac.load_conf(&format!(
+                "DisableNetwork 1\nControlPort {}\nSocksPort 17539\n",
+                TOR_TESTING_PORT,
+            )).await.unwrap();
+
+            let res = ac.get_conf("SocksPort").await.unwrap();
+            assert_eq!(res.len(), 1);
+            assert_eq!(res[0].as_ref().map(|r| r as &str), Some("17539"));
+
+            ac.load_conf("NotAnOption 1\n").await.unwrap_err();
+        });
+    }
+
     #[test]
     fn test_can_get_configuration_value_set_it_and_get_it_again() {
         let _c = run_testing_tor_instance(
@@ -865,6 +3397,38 @@ mod test_with_tor {
         });
     }
 
+    #[cfg(unix)]
+    #[test]
+    fn test_can_connect_via_unix_socket() {
+        use std::os::unix::fs::PermissionsExt;
+        use tokio::net::UnixStream;
+
+        // tor requires directory containing control socket not to be accessible by other users
+        let dir = std::env::temp_dir().join(format!("torut-testing-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o700)).unwrap();
+        let socket_path = dir.join("control");
+
+        let _c = run_testing_tor_instance(
+            &[
+                "--DisableNetwork", "1",
+                "--ControlSocket", socket_path.to_str().unwrap(),
+            ]);
+
+        block_on_with_env(async move {
+            let s = UnixStream::connect(&socket_path).await.unwrap();
+            let mut ac = AuthenticatedConn::connect(s, Some(TorAuthData::Null)).await.unwrap();
+            ac.set_async_event_handler(Some(|_| {
+                async move { Ok(()) }
+            }));
+
+            let version = ac.get_info("version").await.unwrap();
+            assert!(!version.is_empty());
+        });
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
     #[test]
     fn test_can_get_information_from_tor() {
         let _c = run_testing_tor_instance(
@@ -998,9 +3562,10 @@ mod test_with_tor {
 
             let key = crate::onion::TorSecretKeyV3::generate();
 
-            ac.add_onion_v3(&key, false, false, false, None, &mut [
+            let addr = ac.add_onion_v3(&key, false, false, false, None, &mut [
                 (15787, SocketAddr::new(IpAddr::from(Ipv4Addr::new(127,0,0,1)), 15787)),
             ].iter()).await.unwrap();
+            assert_eq!(addr, crate::onion::OnionAddress::V3(key.public().get_onion_address()));
 
             // additional actions to check if connection is in corrupted state
             ac.take_ownership().await.unwrap();
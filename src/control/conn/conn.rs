@@ -4,15 +4,20 @@ use std::fmt::{self, Display, Formatter};
 use std::io;
 use std::num::ParseIntError;
 use std::option::Option::None;
+use std::pin::Pin;
 use std::str::{FromStr, Utf8Error};
 use std::string::FromUtf8Error;
+use std::task::Poll;
 
-use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, ReadBuf};
 
 use crate::control::TorErrorKind;
 
 /// UnauthenticatedConnError describes subset of `ConnError`s returned by `UnauthenticatedConn`
+///
+/// It's wrapped in `ConnError::UnauthenticatedConnError`.
 #[derive(Debug, From)]
+#[non_exhaustive]
 pub enum UnauthenticatedConnError {
     /// Fetching authentication info twice causes tor to break connections so we forbid that and return
     /// this error code when programmer tries to do so.
@@ -23,6 +28,10 @@ pub enum UnauthenticatedConnError {
     ///
     /// Right now it's not implemented and is never returned.
     ServerHashMismatch,
+
+    /// NoAuthDataAvailable is returned when authentication data was not provided and it can't be created automatically
+    /// for instance because tor accepts password authentication only.
+    NoAuthDataAvailable,
 }
 
 impl Display for UnauthenticatedConnError {
@@ -30,6 +39,7 @@ impl Display for UnauthenticatedConnError {
         match self {
             Self::InfoFetchedTwice => write!(f, "Authentication info fetched twice"),
             Self::ServerHashMismatch => write!(f, "Tor cookie hashes do not match"),
+            Self::NoAuthDataAvailable => write!(f, "No authentication method which may be used automatically is available"),
         }
     }
 }
@@ -37,13 +47,18 @@ impl Display for UnauthenticatedConnError {
 impl Error for UnauthenticatedConnError {}
 
 /// AuthenticatedConnError describes subset of `ConnError`s returned by `AuthenticatedConn`
+///
+/// It's wrapped in `ConnError::AuthenticatedConnError`.
+/// All these errors are returned *before* anything is sent to tor, when user-provided value
+/// could corrupt connection flow(for instance could inject another command), so connection remains usable.
 #[derive(Debug, From)]
+#[non_exhaustive]
 pub enum AuthenticatedConnError {
     /// InvalidKeywordValue when user-provided keyword is not valid
     /// It's also returned when user-provided option is not valid.
     InvalidKeywordValue,
 
-    /// InvalidHostnameValue when user-provided domain passed to resolve is not valid
+    /// InvalidHostnameValue when user-provided domain passed to resolve or address passed to redirect_stream is not valid
     InvalidHostnameValue,
 
     /// InvalidListenerSpecification is returned when one tries to spin up new onion service and
@@ -55,13 +70,33 @@ pub enum AuthenticatedConnError {
 
     /// InvalidEventName is returned when name of given event passed to conn is invalid and may corrupt connection flow
     InvalidEventName,
+
+    /// InvalidFeatureName is returned when name of feature passed to `USEFEATURE` is invalid and may corrupt connection flow
+    InvalidFeatureName,
+
+    /// InvalidFingerprint is returned when relay fingerprint passed as argument is not valid
+    InvalidFingerprint,
+
+    /// InvalidNickname is returned when relay nickname passed as argument is not valid
+    InvalidNickname,
+
+    /// InvalidAddOnionResponse is returned when tor's reply to `ADD_ONION` does not contain valid `ServiceID`
+    InvalidAddOnionResponse,
 }
 
 impl Display for AuthenticatedConnError {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        // TODO(teawithsand): proper explanation for these errors
-        //  despite the fact that they are low level it's hard to write user facing message for them
-        write!(f, "{:?} (Read torut's docs)", self)
+        match self {
+            Self::InvalidKeywordValue => write!(f, "Invalid keyword or option name provided"),
+            Self::InvalidHostnameValue => write!(f, "Invalid hostname or address provided"),
+            Self::InvalidListenerSpecification => write!(f, "Invalid onion service port specification provided"),
+            Self::InvalidOnionServiceIdentifier => write!(f, "Invalid onion service identifier provided"),
+            Self::InvalidEventName => write!(f, "Invalid event name provided"),
+            Self::InvalidFeatureName => write!(f, "Invalid feature name provided"),
+            Self::InvalidFingerprint => write!(f, "Invalid relay fingerprint provided"),
+            Self::InvalidNickname => write!(f, "Invalid relay nickname provided"),
+            Self::InvalidAddOnionResponse => write!(f, "Invalid ADD_ONION response received"),
+        }
     }
 }
 
@@ -69,6 +104,7 @@ impl Error for AuthenticatedConnError {}
 
 /// ConnError is able to wrap any error that a connection may return
 #[derive(Debug, From)]
+#[non_exhaustive]
 pub enum ConnError {
     IOError(io::Error),
     Utf8Error(Utf8Error),
@@ -84,12 +120,25 @@ pub enum ConnError {
     /// Usually this indicates some error on tor's side
     InvalidResponseCode(u16),
 
+    /// TorError is returned when tor replied with error code which is known to torut.
+    /// It contains typed error code and lines of tor's reply which usually explain what went wrong.
+    TorError(TorErrorKind, Vec<String>),
+
     InvalidFormat,
     InvalidCharacterFound,
     NonAsciiByteFound,
     ResponseCodeMismatch,
 
     TooManyBytesRead,
+
+    /// DataBlockTooBig is returned instead of `ConnError::TooManyBytesRead` when limit of bytes was reached
+    /// while reading multiline(`CmdData`) block, which was never terminated with single dot line.
+    DataBlockTooBig,
+
+    /// ConnectionClosed is returned when tor closed connection(or it was reset) while response was being read.
+    /// It's returned instead of `ConnError::IOError` with `UnexpectedEof` or `ConnectionReset` kind,
+    /// so "tor went away" can be told apart from other IO errors without inspecting them.
+    ConnectionClosed,
 }
 
 impl Display for ConnError {
@@ -107,14 +156,32 @@ impl Display for ConnError {
                         write!(f, "Tor returned error response code: {}", code)
                     }
                 }
+                Self::TorError(kind, lines) => {
+                    write!(f, "Tor returned error response: {:?} - {}", kind, lines.join(" "))
+                }
                 Self::InvalidFormat | Self::InvalidCharacterFound | Self::NonAsciiByteFound | Self::ResponseCodeMismatch => write!(f, "Invalid response got from tor"),
                 Self::TooManyBytesRead => write!(f, "Tor response was too big to process"),
+                Self::DataBlockTooBig => write!(f, "Tor response data block was too big to process"),
+                Self::ConnectionClosed => write!(f, "Connection to tor was closed"),
                 _ => write!(f, "Unknown ConnError"),
             }
         }
     }
 }
 
+impl ConnError {
+    /// from_response_code creates error from code of tor's reply which is not the expected one.
+    ///
+    /// If code is known `TorErrorKind` then `ConnError::TorError` is returned.
+    /// Otherwise it's `ConnError::InvalidResponseCode`.
+    pub(crate) fn from_response_code(code: u16, lines: Vec<String>) -> Self {
+        match TorErrorKind::try_from(code) {
+            Ok(kind) => ConnError::TorError(kind, lines),
+            Err(_) => ConnError::InvalidResponseCode(code),
+        }
+    }
+}
+
 impl Error for ConnError {
     fn source(&self) -> Option<&(dyn Error + 'static)> {
         match self {
@@ -129,6 +196,21 @@ impl Error for ConnError {
     }
 }
 
+impl From<ConnError> for io::Error {
+    /// Converts `ConnError` into `io::Error`, so it can be propagated through `io::Result` boundaries.
+    ///
+    /// `ConnError::IOError` is unwrapped into underlying error.
+    /// `ConnError::ConnectionClosed` is wrapped into `io::Error` of kind `io::ErrorKind::UnexpectedEof`.
+    /// All other variants are wrapped into `io::Error` of kind `io::ErrorKind::InvalidData`.
+    fn from(err: ConnError) -> Self {
+        match err {
+            ConnError::IOError(err) => err,
+            err @ ConnError::ConnectionClosed => io::Error::new(io::ErrorKind::UnexpectedEof, err),
+            err => io::Error::new(io::ErrorKind::InvalidData, err),
+        }
+    }
+}
+
 /// Conn wraps any `AsyncRead + AsyncWrite` stream and implements parsing responses from tor and sending data to it.
 ///
 /// It's stateless component. It does not contain any information about connection like authentication state.
@@ -138,24 +220,42 @@ impl Error for ConnError {
 /// Unless you need it you should use higher level apis.
 pub struct Conn<S> {
     stream: S,
+    // byte read by `is_alive`, which has to be returned by next read
+    peeked_byte: Option<u8>,
 }
 
 impl<S> Conn<S> {
     pub fn new(stream: S) -> Self {
         Self {
-            stream
+            stream,
+            peeked_byte: None,
         }
     }
 
+    /// into_inner returns underlying stream.
+    ///
+    /// Note: byte read by `is_alive`(if any) is lost.
     pub fn into_inner(self) -> S {
         self.stream
     }
+
+    /// get_ref returns reference to underlying stream
+    pub fn get_ref(&self) -> &S {
+        &self.stream
+    }
 }
 
 /// MAX_SINGLE_RECV_BYTES describes how many bytes may be received during single call to `receive_data`
 /// It's used to prevent DoS(OOM allocating).
 const MAX_SINGLE_RECV_BYTES: usize = 1024 * 1024 * 1;// 1MB
 
+/// is_valid_response_code checks if code is in range used by torCP.
+/// Tor uses 2xx codes for success, 4xx and 5xx for errors and 650 for asynchronous events, so anything outside
+/// `200..=699` means that stream is not tor's control connection or that it got out of sync.
+fn is_valid_response_code(code: u16) -> bool {
+    (200..=699).contains(&code)
+}
+
 impl<S> Conn<S>
     where S: AsyncRead + Unpin
 {
@@ -169,15 +269,41 @@ impl<S> Conn<S>
     /// # Performance considerations
     /// This function allocates all stuff and does not allow writing to any preallocated buffer.
     /// It neither does not allow for any kind of borrowing from one big buffer.
+    /// Use `receive_data_into` if you want to reuse buffers between calls.
     ///
     /// Personally I think it's not needed. It's tor api how many data you want receive from it?
     /// Anyway this won't be ran on any embedded device(because it has to be able to run tor, it has to run at least some
     /// linux so I probably can allocate a few strings on it...)
     ///
     /// # Possible performance issues
-    /// It uses byte-by-byte reading. Thanks to this feature there is no state in `Conn` struct.
+    /// It uses byte-by-byte reading. Thanks to this feature there is (almost) no state in `Conn` struct.
     /// Use some sort of buffered reader in order to minimize overhead.
     pub async fn receive_data(&mut self) -> Result<(u16, Vec<String>), ConnError> {
+        let mut lines = Vec::new();
+        let code = self.receive_data_into(&mut lines).await?;
+        Ok((code, lines))
+    }
+
+    /// receive_data_into works like `receive_data` but it puts lines of response into given vector
+    /// instead of allocating new one.
+    ///
+    /// Strings already present in `lines` are cleared and reused, so their capacity is not lost.
+    /// Once it's done `lines` contains exactly lines of response, superfluous strings are truncated.
+    /// It's meant for hot loops like the one processing `BW` events.
+    ///
+    /// # Error
+    /// When error is returned contents of `lines` are unspecified.
+    ///
+    /// # Return value
+    /// Response code of received response.
+    pub async fn receive_data_into(&mut self, lines: &mut Vec<String>) -> Result<u16, ConnError> {
+        self.receive_data_with_sink(lines, &mut |_, _| false).await
+    }
+
+    // receive_data_with_sink is the parser behind all the `receive_data*` functions.
+    // Each data line of multiline block is passed to `sink` first and it's appended to block's reply line
+    // only if `sink` did not consume it.
+    async fn receive_data_with_sink(&mut self, lines: &mut Vec<String>, sink: &mut impl FnMut(u16, &str) -> bool) -> Result<u16, ConnError> {
         // ok. let's first think about the format.
         // it's rather simple
         // docs: https://gitweb.torproject.org/torspec.git/tree/control-spec.txt
@@ -215,7 +341,8 @@ impl<S> Conn<S>
                 # Torut developer note: above compatibility note is not implemented
         */
 
-        let mut lines = Vec::new();
+        // number of strings in `lines` which are already filled with this response
+        let mut lines_used = 0;
         let mut response_code = None;
 
         let mut state = 0;
@@ -224,13 +351,12 @@ impl<S> Conn<S>
         let mut bytes_read = 0;
         loop {
             if bytes_read >= MAX_SINGLE_RECV_BYTES {
+                if state == 4 || state == 5 {
+                    return Err(ConnError::DataBlockTooBig);
+                }
                 return Err(ConnError::TooManyBytesRead);
             }
-            let b = {
-                let mut buf = [0u8; 1];
-                self.stream.read_exact(&mut buf[..]).await?;
-                buf[0]
-            };
+            let b = self.read_byte().await?;
 
             bytes_read += 1;
 
@@ -251,9 +377,9 @@ impl<S> Conn<S>
                     let text = std::str::from_utf8(&current_line_buffer)?;
                     let parsed_response_code = u16::from_str(text)?;
 
-                    // some fancy behaviour of from str may occur(?)
-                    // let's leave this assert even for prod use
-                    assert!(parsed_response_code < 1000, "Invalid response code");
+                    if !is_valid_response_code(parsed_response_code) {
+                        return Err(ConnError::InvalidResponseCode(parsed_response_code));
+                    }
 
                     if let Some(response_code) = response_code {
                         if response_code != parsed_response_code {
@@ -296,16 +422,11 @@ impl<S> Conn<S>
                 {
                     current_line_buffer.truncate(current_line_buffer.len() - 2);
 
-                    let res = {
-                        let mut line_buffer = Vec::new();
-                        std::mem::swap(&mut current_line_buffer, &mut line_buffer);
-                        String::from_utf8(line_buffer)
-                    };
                     // only valid ascii remember?
                     // if so it's valid utf8
-                    debug_assert!(res.is_ok());
-                    let text = res?;
-                    lines.push(text);
+                    let text = std::str::from_utf8(&current_line_buffer)?;
+                    put_line(lines, &mut lines_used, text);
+                    current_line_buffer.clear();
 
                     // if it's last line break loop
                     if state == 2 {
@@ -314,47 +435,140 @@ impl<S> Conn<S>
                         state = 0;
                     }
                 }
-            } else if state == 4 {
-                // multiline read mode reads lines until it eventually found \r\n.\r\n sequence
+            } else if state == 4 || state == 5 {
+                // multiline read mode reads lines until it eventually finds line with single dot.
+                // First line is reply line, the following ones are data lines.
                 current_line_buffer.push(b);
-                if current_line_buffer.len() >= 5 &&
-                    current_line_buffer[current_line_buffer.len() - 5] == b'\r' &&
-                    current_line_buffer[current_line_buffer.len() - 4] == b'\n' &&
-                    current_line_buffer[current_line_buffer.len() - 3] == b'.' &&
-                    current_line_buffer[current_line_buffer.len() - 2] == b'\r' &&
-                    current_line_buffer[current_line_buffer.len() - 1] == b'\n'
-                {
-                    current_line_buffer.truncate(current_line_buffer.len() - 5);
-
-                    let res = {
-                        let mut line_buffer = Vec::new();
-                        std::mem::swap(&mut current_line_buffer, &mut line_buffer);
-                        String::from_utf8(line_buffer)
-                    };
+                if current_line_buffer.ends_with(b"\r\n") {
+                    current_line_buffer.truncate(current_line_buffer.len() - 2);
 
                     // only valid ascii remember?
                     // if so it's valid utf8
-                    debug_assert!(res.is_ok());
-                    let text = res?;
-                    lines.push(text);
-
-                    // there may be more lines incoming after this one
-                    state = 0;
+                    let text = std::str::from_utf8(&current_line_buffer)?;
+                    if state == 4 {
+                        put_line(lines, &mut lines_used, text);
+                        state = 5;
+                    } else if text == "." {
+                        // there may be more lines incoming after this one
+                        state = 0;
+                    } else {
+                        // lines starting with dot are dot-stuffed by tor
+                        let data = text.strip_prefix('.').unwrap_or(text);
+                        if sink(response_code.unwrap(), data) {
+                            // consumed lines are not stored, so they do not count towards the limit
+                            bytes_read -= current_line_buffer.len() + 2;
+                        } else {
+                            let block = &mut lines[lines_used - 1];
+                            block.push_str("\r\n");
+                            block.push_str(data);
+                        }
+                    }
+                    current_line_buffer.clear();
                 }
             } else {
                 unreachable!("Invalid state!");
             }
         }
-        if response_code.is_none() {
-            return Err(ConnError::InvalidFormat);
+        lines.truncate(lines_used);
+        let response_code = response_code.ok_or(ConnError::InvalidFormat)?;
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            code = response_code,
+            lines = ?lines.iter().map(|l| super::redact::redact_reply(l)).collect::<Vec<_>>(),
+            "received reply from tor",
+        );
+        Ok(response_code)
+    }
+}
+
+/// put_line stores line at `lines[*used]`, reusing string which is already there if there is one.
+fn put_line(lines: &mut Vec<String>, used: &mut usize, text: &str) {
+    if let Some(line) = lines.get_mut(*used) {
+        line.clear();
+        line.push_str(text);
+    } else {
+        lines.push(text.to_string());
+    }
+    *used += 1;
+}
+
+impl<S> Conn<S>
+    where S: AsyncRead + Unpin
+{
+    // read_byte reads single byte from stream or returns one read by `is_alive`
+    async fn read_byte(&mut self) -> Result<u8, ConnError> {
+        if let Some(b) = self.peeked_byte.take() {
+            return Ok(b);
+        }
+        let mut buf = [0u8; 1];
+        match self.stream.read_exact(&mut buf[..]).await {
+            Ok(_) => Ok(buf[0]),
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof || err.kind() == io::ErrorKind::ConnectionReset => {
+                Err(ConnError::ConnectionClosed)
+            }
+            Err(err) => Err(ConnError::IOError(err)),
         }
-        return Ok((response_code.unwrap(), lines));
+    }
+
+    /// is_alive checks if stream was not closed by tor, without sending anything to it.
+    ///
+    /// It polls stream for read once and returns immediately:
+    /// - if there is no data to read then connection is alive
+    /// - if there is data to read(for instance asynchronous event) then connection is alive as well.
+    ///   Single byte is read and stored in conn, so it's returned by the next read and no data is lost.
+    /// - if EOF was reached or IO error occurred connection is dead
+    ///
+    /// # Note
+    /// It detects connections closed by remote side only. Connection which is broken in other way
+    /// (for instance network cable got unplugged) may look alive. Send some command(like `GETINFO version`) to check it.
+    pub async fn is_alive(&mut self) -> bool {
+        if self.peeked_byte.is_some() {
+            return true;
+        }
+        let stream = &mut self.stream;
+        let peeked_byte = &mut self.peeked_byte;
+        std::future::poll_fn(|cx| {
+            let mut buf = [0u8; 1];
+            let mut read_buf = ReadBuf::new(&mut buf[..]);
+            let res = match Pin::new(&mut *stream).poll_read(cx, &mut read_buf) {
+                Poll::Pending => true,
+                Poll::Ready(Ok(())) if read_buf.filled().is_empty() => false,
+                Poll::Ready(Ok(())) => {
+                    *peeked_byte = Some(read_buf.filled()[0]);
+                    true
+                }
+                Poll::Ready(Err(_)) => false,
+            };
+            Poll::Ready(res)
+        }).await
+    }
+
+    /// receive_data_streaming receives single response from tor just like `receive_data` but it does not have to
+    /// accumulate contents of multiline(`CmdData`) blocks.
+    ///
+    /// Each line of multiline block(with dot-stuffing already removed) is passed to `sink` together with response code.
+    /// If `sink` returns true line is considered consumed and it's not stored anywhere.
+    /// Otherwise line is appended to the reply line which started the block, so result is the same as
+    /// `receive_data` would return.
+    ///
+    /// # Limits
+    /// `MAX_SINGLE_RECV_BYTES` limit applies to all the data which is not consumed by `sink`, so single consumed line
+    /// can't be longer than that either. Total size of consumed data is not limited.
+    pub async fn receive_data_streaming(&mut self, sink: &mut impl FnMut(u16, &str) -> bool) -> Result<(u16, Vec<String>), ConnError> {
+        let mut lines = Vec::new();
+        let code = self.receive_data_with_sink(&mut lines, sink).await?;
+        Ok((code, lines))
     }
 }
 
 impl<S> Conn<S> where S: AsyncWrite + Unpin {
     /// write_data writes *RAW* data into tor controller and flushes stream
+    ///
+    /// When `tracing` feature is enabled written data is traced with `debug` level.
+    /// Obvious secrets like `AUTHENTICATE` arguments or private keys are redacted.
     pub async fn write_data(&mut self, data: &[u8]) -> Result<(), ConnError> {
+        #[cfg(feature = "tracing")]
+        tracing::debug!(command = %super::redact::redact_command(data), "sending command to tor");
         self.stream.write_all(data).await?;
         self.stream.flush().await?;
         Ok(())
@@ -377,9 +591,18 @@ mod test {
             ("250-LANDER=MAAR\r\n250 L2\r\n", Some((250, vec!["LANDER=MAAR", "L2"]))),
             ("250-default\r\n250 key=value\r\n", Some((250, vec!["default", "key=value"]))),
             ("250-abc\r\n250+abcd\r\n second line\r\n.\r\n250 OK\r\n", Some((250, vec!["abc", "abcd\r\n second line", "OK"]))),
+            ("250+abcd\r\n..\r\n.. dots\r\n.\r\n250 OK\r\n", Some((250, vec!["abcd\r\n.\r\n. dots", "OK"]))),
+            ("250 \r\n", Some((250, vec![""]))),
+            ("650 \r\n", Some((650, vec![""]))),
+            ("250-\r\n250 \r\n", Some((250, vec!["", ""]))),
+            ("250-abc\r\n250 \r\n", Some((250, vec!["abc", ""]))),
+            ("250+\r\n.\r\n250 \r\n", Some((250, vec!["", ""]))),
             ("250-abc\r\n250+abcd\r\n second line\r\n.\r\n250 OK", None),
             ("250-abc\r\n250+abcd\r\n second line\r\n.\r\n", None),
             ("250-abc\r\n250+abcd\r\n second line", None),
+            ("250 ", None),
+            ("250 \r", None),
+            ("250\r\n", None),
         ].iter().cloned() {
             // eprintln!("{:?} -> {:?}", input, output);
             block_on(async move {
@@ -396,4 +619,241 @@ mod test {
             });
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_conn_does_not_misframe_data_blocks() {
+        for (input, output) in [
+            // dot which is not alone in line does not terminate block
+            ("250+abcd\r\na.\r\n.a\r\n. \r\n\r\n.\r\n250 OK\r\n", vec!["abcd\r\na.\r\na\r\n \r\n", "OK"]),
+            ("250+abcd\r\nx\r\n...\r\n.\r\n250 OK\r\n", vec!["abcd\r\nx\r\n..", "OK"]),
+            // reply line which starts block is not dot-stuffed
+            ("250+.abcd\r\n..x\r\n.\r\n250 OK\r\n", vec![".abcd\r\n.x", "OK"]),
+            ("250+.\r\n.\r\n250 OK\r\n", vec![".", "OK"]),
+        ].iter() {
+            block_on(async move {
+                let mut cursor = Cursor::new(Vec::from(*input));
+                let mut conn = Conn::new(&mut cursor);
+                let (code, res) = conn.receive_data().await.unwrap();
+                assert_eq!(code, 250);
+                assert_eq!(res, *output, "{:?}", input);
+
+                let mut cursor = Cursor::new(Vec::from(*input));
+                let mut conn = Conn::new(&mut cursor);
+                let (code, res) = conn.receive_data_streaming(&mut |_, _| false).await.unwrap();
+                assert_eq!(code, 250);
+                assert_eq!(res, *output, "{:?}", input);
+            });
+        }
+    }
+
+    #[test]
+    fn test_conn_reports_too_big_data_block() {
+        block_on(async move {
+            let mut data = b"250+abcd\r\n".to_vec();
+            while data.len() <= MAX_SINGLE_RECV_BYTES {
+                data.extend_from_slice(b"line\r\n");
+            }
+            let mut cursor = Cursor::new(data.clone());
+            let mut conn = Conn::new(&mut cursor);
+            assert!(matches!(conn.receive_data().await, Err(ConnError::DataBlockTooBig)));
+
+            let mut cursor = Cursor::new(data);
+            let mut conn = Conn::new(&mut cursor);
+            assert!(matches!(conn.receive_data_streaming(&mut |_, _| false).await, Err(ConnError::DataBlockTooBig)));
+
+            let mut data = b"250-abcd".to_vec();
+            data.resize(MAX_SINGLE_RECV_BYTES + 1, b'a');
+            let mut cursor = Cursor::new(data);
+            let mut conn = Conn::new(&mut cursor);
+            assert!(matches!(conn.receive_data().await, Err(ConnError::TooManyBytesRead)));
+        });
+    }
+
+    #[test]
+    fn test_conn_does_not_misframe_empty_lines() {
+        block_on(async move {
+            let mut cursor = Cursor::new(Vec::from(&b"650 \r\n250 \r\n650-\r\n650 OK\r\n250 OK\r\n"[..]));
+            let mut conn = Conn::new(&mut cursor);
+            assert_eq!(conn.receive_data().await.unwrap(), (650, vec![String::new()]));
+            assert_eq!(conn.receive_data().await.unwrap(), (250, vec![String::new()]));
+            assert_eq!(conn.receive_data().await.unwrap(), (650, vec![String::new(), "OK".to_string()]));
+            assert_eq!(conn.receive_data().await.unwrap(), (250, vec!["OK".to_string()]));
+            conn.receive_data().await.unwrap_err();
+        });
+    }
+
+    #[test]
+    fn test_conn_can_receive_data_into_buffer() {
+        block_on(async move {
+            let mut cursor = Cursor::new(Vec::from(&b"250-L1\r\n250+abcd\r\n..dots\r\n.\r\n250 OK\r\n650 BW 1 2\r\n250 \r\n251 x"[..]));
+            let mut conn = Conn::new(&mut cursor);
+            let mut lines = vec![String::with_capacity(64)];
+            let capacity = lines[0].capacity();
+
+            assert_eq!(conn.receive_data_into(&mut lines).await.unwrap(), 250);
+            assert_eq!(lines, ["L1", "abcd\r\n.dots", "OK"]);
+            assert_eq!(lines[0].capacity(), capacity);
+
+            assert_eq!(conn.receive_data_into(&mut lines).await.unwrap(), 650);
+            assert_eq!(lines, ["BW 1 2"]);
+            assert_eq!(lines[0].capacity(), capacity);
+
+            assert_eq!(conn.receive_data_into(&mut lines).await.unwrap(), 250);
+            assert_eq!(lines, [""]);
+
+            conn.receive_data_into(&mut lines).await.unwrap_err();
+        });
+    }
+
+    #[test]
+    fn test_conn_can_read_response_streaming() {
+        for (input, output, streamed) in [
+            ("250 Ok line one\r\n", Some((250u16, vec!["Ok line one"])), vec![]),
+            ("250-L1\r\n250 L2\r\n", Some((250, vec!["L1", "L2"])), vec![]),
+            ("250-abc\r\n250+abcd\r\n second line\r\n.\r\n250 OK\r\n", Some((250, vec!["abc", "abcd", "OK"])), vec![" second line"]),
+            ("250+abcd\r\n..\r\n.. dots\r\n.\r\n250 OK\r\n", Some((250, vec!["abcd", "OK"])), vec![".", ". dots"]),
+            ("250+empty\r\n.\r\n250 OK\r\n", Some((250, vec!["empty", "OK"])), vec![]),
+            // async events are not streamed by sink below
+            ("650+NS\r\nr line\r\n.\r\n650 OK\r\n", Some((650, vec!["NS\r\nr line", "OK"])), vec![]),
+            ("250 \r\n", Some((250, vec![""])), vec![]),
+            ("650 \r\n", Some((650, vec![""])), vec![]),
+            ("250-abc\r\n251 OK\r\n", None, vec![]),
+            ("250-abc\r\n250+abcd\r\n second line\r\n.\r\n250 OK", None, vec![" second line"]),
+            ("250-abc\r\n250+abcd\r\n second line", None, vec![]),
+            ("250_abc\r\n", None, vec![]),
+            ("25\r\n", None, vec![]),
+        ].iter().cloned() {
+            block_on(async move {
+                let mut cursor = Cursor::new(Vec::from(input));
+                let mut conn = Conn::new(&mut cursor);
+                let mut given_streamed = Vec::new();
+                let res = conn.receive_data_streaming(&mut |code, line| {
+                    if code == 650 {
+                        return false;
+                    }
+                    given_streamed.push(line.to_string());
+                    true
+                }).await;
+                if let Some((valid_code, valid_res)) = output {
+                    let (given_code, given_res) = res.unwrap();
+                    assert_eq!(valid_code, given_code);
+                    let res2_ref = given_res.iter().map(|s| s as &str).collect::<Vec<_>>();
+                    assert_eq!(valid_res, res2_ref);
+                } else {
+                    res.unwrap_err();
+                }
+                assert_eq!(given_streamed, streamed);
+            });
+        }
+    }
+
+    #[test]
+    fn test_conn_error_converts_into_io_error() {
+        let err = io::Error::from(ConnError::IOError(io::Error::new(io::ErrorKind::UnexpectedEof, "eof")));
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        assert_eq!(err.to_string(), "eof");
+
+        let err = io::Error::from(ConnError::InvalidFormat);
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(err.to_string(), "Invalid response got from tor");
+
+        let err = io::Error::from(ConnError::from_response_code(515, vec!["Authentication failed".to_string()]));
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(err.to_string(), "Tor returned error response: BadAuth - Authentication failed");
+
+        let err = io::Error::from(ConnError::InvalidResponseCode(515));
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.get_ref().unwrap().is::<ConnError>());
+    }
+
+    #[test]
+    fn test_conn_rejects_out_of_range_response_codes() {
+        for (input, output) in [
+            ("200 OK\r\n", Ok(200u16)),
+            ("250 OK\r\n", Ok(250)),
+            ("251 OK\r\n", Ok(251)),
+            ("451 Resource exhausted\r\n", Ok(451)),
+            ("552 Unrecognized key\r\n", Ok(552)),
+            ("650 CIRC 1 BUILT\r\n", Ok(650)),
+            ("699 OK\r\n", Ok(699)),
+            ("000 OK\r\n", Err(0u16)),
+            ("199 OK\r\n", Err(199)),
+            ("700 OK\r\n", Err(700)),
+            ("999 OK\r\n", Err(999)),
+            ("999-OK\r\n999 OK\r\n", Err(999)),
+        ].iter().cloned() {
+            block_on(async move {
+                let mut cursor = Cursor::new(Vec::from(input));
+                let mut conn = Conn::new(&mut cursor);
+                let res = conn.receive_data().await;
+                let mut cursor = Cursor::new(Vec::from(input));
+                let mut conn = Conn::new(&mut cursor);
+                let streaming_res = conn.receive_data_streaming(&mut |_, _| true).await;
+                for res in [res, streaming_res].iter() {
+                    match (res, output) {
+                        (Ok((code, _)), Ok(expected)) => assert_eq!(*code, expected, "{:?}", input),
+                        (Err(ConnError::InvalidResponseCode(code)), Err(expected)) => assert_eq!(*code, expected, "{:?}", input),
+                        (res, _) => panic!("Unexpected result {:?} for {:?}", res, input),
+                    }
+                }
+            });
+        }
+    }
+
+    #[test]
+    fn test_conn_reports_closed_connection() {
+        for input in [
+            "",
+            "25",
+            "250-abc\r\n",
+            "250+abc\r\nline\r\n",
+        ].iter() {
+            block_on(async move {
+                let mut cursor = Cursor::new(Vec::from(*input));
+                let mut conn = Conn::new(&mut cursor);
+                assert!(matches!(conn.receive_data().await, Err(ConnError::ConnectionClosed)), "{:?}", input);
+                let mut cursor = Cursor::new(Vec::from(*input));
+                let mut conn = Conn::new(&mut cursor);
+                assert!(matches!(
+                    conn.receive_data_streaming(&mut |_, _| true).await,
+                    Err(ConnError::ConnectionClosed)
+                ), "{:?}", input);
+            });
+        }
+
+        let err = io::Error::from(ConnError::ConnectionClosed);
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn test_conn_error_displays_inner_error() {
+        let err = ConnError::from(AuthenticatedConnError::InvalidEventName);
+        assert_eq!(err.to_string(), "ConnError: Invalid event name provided");
+        assert!(err.source().unwrap().is::<AuthenticatedConnError>());
+
+        let err = ConnError::from(UnauthenticatedConnError::InfoFetchedTwice);
+        assert_eq!(err.to_string(), "ConnError: Authentication info fetched twice");
+        assert!(err.source().unwrap().is::<UnauthenticatedConnError>());
+    }
+
+    #[test]
+    fn test_conn_can_check_if_alive() {
+        block_on(async move {
+            let (client, server) = tokio::io::duplex(64);
+            let mut conn = Conn::new(client);
+            assert!(conn.is_alive().await);
+
+            let mut server = server;
+            server.write_all(b"650 CIRC 1 BUILT\r\n").await.unwrap();
+            assert!(conn.is_alive().await);
+            assert!(conn.is_alive().await);
+            // byte read by is_alive is not lost
+            let (code, lines) = conn.receive_data().await.unwrap();
+            assert_eq!(code, 650);
+            assert_eq!(lines, vec!["CIRC 1 BUILT"]);
+
+            drop(server);
+            assert!(!conn.is_alive().await);
+        });
+    }
+}
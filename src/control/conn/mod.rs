@@ -1,7 +1,11 @@
 pub use authenticated_conn::*;
 pub use conn::*;
+pub use reconnecting_conn::*;
 pub use unauthenticated_conn::*;
 
 mod conn;
 mod unauthenticated_conn;
 mod authenticated_conn;
+mod reconnecting_conn;
+#[cfg(feature = "tracing")]
+mod redact;
@@ -0,0 +1,272 @@
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+
+use tokio::io::{AsyncRead, AsyncWrite};
+
+use crate::control::conn::{AuthenticatedConn, ConnError};
+use crate::control::primitives::{AsyncEvent, TorAuthData};
+
+/// ReconnectingConn wraps `AuthenticatedConn` and recreates it when tor's control connection dies,
+/// for instance because tor was restarted.
+///
+/// It stores everything required to create connection again:
+/// - connector, which is function creating new stream(like `TcpStream::connect` or `UnixStream::connect` call)
+/// - authentication data(if `None` it's created from protocol info on each connect, so new cookie is read after restart)
+/// - async event handler
+/// - events set with `set_events` and whether ownership should be taken
+///
+/// Ownership is property of control connection, so tor does not consider new connection an owner after reconnect.
+/// That's why `TAKEOWNERSHIP`(and `__OwningControllerProcess` if pid was given) is issued again
+/// each time connection is created, when it's enabled with `set_take_ownership` or `set_take_ownership_with_pid`.
+///
+/// When command executed with `run` fails with IO error(or `ConnError::ConnectionClosed`),
+/// connection is dropped, recreated and command is retried once.
+///
+/// # Note
+/// Command is retried, so it may be executed twice when IO error occurs after tor received it.
+/// Keep it in mind when running non-idempotent commands like `ADD_ONION`.
+pub struct ReconnectingConn<S, H, C> {
+    connector: C,
+    auth: Option<TorAuthData<'static>>,
+    async_event_handler: Option<H>,
+    events: Option<(bool, Vec<String>)>,
+    take_ownership: bool,
+    owning_controller_pid: Option<u32>,
+    conn: Option<AuthenticatedConn<S, H>>,
+}
+
+impl<S, H, C> ReconnectingConn<S, H, C> {
+    /// new creates `ReconnectingConn`. It does not connect until first command is executed.
+    pub fn new(connector: C, auth: Option<TorAuthData<'static>>) -> Self {
+        Self {
+            connector,
+            auth,
+            async_event_handler: None,
+            events: None,
+            take_ownership: false,
+            owning_controller_pid: None,
+            conn: None,
+        }
+    }
+
+    /// set_take_ownership sets whether `TAKEOWNERSHIP` should be issued each time connection is created.
+    /// Disabling it forgets pid set with `set_take_ownership_with_pid` as well.
+    ///
+    /// It takes effect on next connect. Use `run` with `take_ownership` to take ownership of current connection.
+    pub fn set_take_ownership(&mut self, take_ownership: bool) {
+        self.take_ownership = take_ownership;
+        if !take_ownership {
+            self.owning_controller_pid = None;
+        }
+    }
+
+    /// set_take_ownership_with_pid works like `set_take_ownership(true)` but `__OwningControllerProcess` is set
+    /// to given pid before `TAKEOWNERSHIP` each time connection is created.
+    /// Take a look at `AuthenticatedConn::take_ownership_with_pid` for details.
+    pub fn set_take_ownership_with_pid(&mut self, pid: u32) {
+        self.take_ownership = true;
+        self.owning_controller_pid = Some(pid);
+    }
+
+    /// is_connected returns true if there is connection which was not found dead yet
+    pub fn is_connected(&self) -> bool {
+        self.conn.is_some()
+    }
+
+    /// get_ref returns reference to current connection if there is one
+    pub fn get_ref(&self) -> Option<&AuthenticatedConn<S, H>> {
+        self.conn.as_ref()
+    }
+
+    /// disconnect drops current connection(if any), so next command creates new one
+    pub fn disconnect(&mut self) {
+        self.conn = None;
+    }
+}
+
+impl<S, H, C> ReconnectingConn<S, H, C>
+    where H: Clone
+{
+    /// set_async_event_handler sets handler used to process asynchronous events on current and all future connections
+    pub fn set_async_event_handler(&mut self, handler: Option<H>) {
+        if let Some(conn) = &mut self.conn {
+            conn.set_async_event_handler(handler.clone());
+        }
+        self.async_event_handler = handler;
+    }
+}
+
+impl<S, H, F, C, CF> ReconnectingConn<S, H, C>
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+        H: FnMut(AsyncEvent<'static>) -> F + Clone,
+        F: Future<Output=Result<(), ConnError>>,
+        C: FnMut() -> CF,
+        CF: Future<Output=Result<S, io::Error>>,
+{
+    /// get_conn returns current connection or creates new one if there is none.
+    ///
+    /// Commands executed directly on returned connection are not retried.
+    /// If one of them fails with IO error or `ConnError::ConnectionClosed` call `disconnect`, so connection is recreated next time.
+    pub async fn get_conn(&mut self) -> Result<&mut AuthenticatedConn<S, H>, ConnError> {
+        if self.conn.is_none() {
+            let stream = (self.connector)().await?;
+            let mut conn = AuthenticatedConn::connect(stream, self.auth.clone()).await?;
+            conn.set_async_event_handler(self.async_event_handler.clone());
+            if self.take_ownership {
+                match self.owning_controller_pid {
+                    Some(pid) => conn.take_ownership_with_pid(pid).await?,
+                    None => conn.take_ownership().await?,
+                }
+            }
+            if let Some((extended, events)) = &self.events {
+                conn.set_events(*extended, &mut events.iter().map(|e| e.as_str())).await?;
+            }
+            self.conn = Some(conn);
+        }
+        Ok(self.conn.as_mut().unwrap())
+    }
+
+    /// run executes given command on connection. If it fails with IO error or because connection was closed,
+    /// connection is recreated and command is executed once again.
+    ///
+    /// Future returned from `f` may borrow connection only, so values used by command have to be moved into it.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use tokio::net::TcpStream;
+    /// use torut::control::{ConnError, ReconnectingConn};
+    ///
+    /// # async fn example() -> Result<(), ConnError> {
+    /// let mut conn = ReconnectingConn::new(|| TcpStream::connect("127.0.0.1:9051"), None);
+    /// conn.set_async_event_handler(Some(|_| async move { Ok(()) }));
+    ///
+    /// let version = conn.run(|c| Box::pin(c.get_info("version"))).await?;
+    /// println!("Tor version: {}", version);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn run<T, R>(&mut self, mut f: R) -> Result<T, ConnError>
+        where R: for<'a> FnMut(&'a mut AuthenticatedConn<S, H>) -> Pin<Box<dyn Future<Output=Result<T, ConnError>> + 'a>>
+    {
+        let mut retried = false;
+        loop {
+            let conn = self.get_conn().await?;
+            match f(conn).await {
+                Err(err @ ConnError::IOError(_)) | Err(err @ ConnError::ConnectionClosed) => {
+                    self.conn = None;
+                    if retried {
+                        return Err(err);
+                    }
+                    retried = true;
+                }
+                res => return res,
+            }
+        }
+    }
+
+    /// set_events works like `AuthenticatedConn::set_events` but events are also remembered
+    /// and set again each time connection is recreated.
+    pub async fn set_events(&mut self, extended: bool, kinds: &mut impl Iterator<Item=&str>) -> Result<(), ConnError> {
+        let events: Vec<String> = kinds.map(|k| k.to_string()).collect();
+        {
+            let events = events.clone();
+            self.run(move |c| {
+                let events = events.clone();
+                Box::pin(async move {
+                    c.set_events(extended, &mut events.iter().map(|e| e.as_str())).await
+                })
+            }).await?;
+        }
+        self.events = Some((extended, events));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::utils::{block_on, TestStream};
+
+    use super::*;
+
+    type NoopHandler = fn(AsyncEvent<'static>) -> std::future::Ready<Result<(), ConnError>>;
+
+    const CONNECT_RESPONSE: &str = concat!(
+        "250-PROTOCOLINFO 1\r\n",
+        "250-AUTH METHODS=NULL\r\n",
+        "250-VERSION Tor=\"0.4.2.5\"\r\n",
+        "250 OK\r\n",
+        "250 OK\r\n",
+    );
+
+    #[test]
+    fn test_can_reconnect() {
+        block_on(async move {
+            let mut connection_count = 0;
+            let mut conn: ReconnectingConn<TestStream, NoopHandler, _> = ReconnectingConn::new(move || {
+                connection_count += 1;
+                let input = if connection_count == 1 {
+                    // tor dies after receiving GETINFO
+                    format!("{}250 OK\r\n250 OK\r\n", CONNECT_RESPONSE)
+                } else {
+                    format!("{}250 OK\r\n250 OK\r\n250-version=0.4.2.5\r\n250 OK\r\n", CONNECT_RESPONSE)
+                };
+                std::future::ready(Ok(TestStream::new(input.as_bytes())))
+            }, None);
+            conn.set_async_event_handler(Some(|_| std::future::ready(Ok(()))));
+            conn.set_take_ownership(true);
+            conn.set_events(false, &mut ["CIRC", "STREAM"].iter().copied()).await.unwrap();
+
+            let version = conn.run(|c| Box::pin(c.get_info("version"))).await.unwrap();
+            assert_eq!(version, "0.4.2.5");
+            assert_eq!(
+                std::str::from_utf8(&conn.get_ref().unwrap().get_ref().output).unwrap(),
+                "PROTOCOLINFO 1\r\nAUTHENTICATE\r\nTAKEOWNERSHIP\r\nSETEVENTS CIRC STREAM\r\nGETINFO version\r\n"
+            );
+        });
+    }
+
+    #[test]
+    fn test_retakes_ownership_with_pid_on_reconnect() {
+        block_on(async move {
+            let mut connection_count = 0;
+            let mut conn: ReconnectingConn<TestStream, NoopHandler, _> = ReconnectingConn::new(move || {
+                connection_count += 1;
+                let input = if connection_count == 1 {
+                    // tor dies right after ownership is taken
+                    format!("{}250 OK\r\n250 OK\r\n", CONNECT_RESPONSE)
+                } else {
+                    format!("{}250 OK\r\n250 OK\r\n250-version=0.4.2.5\r\n250 OK\r\n", CONNECT_RESPONSE)
+                };
+                std::future::ready(Ok(TestStream::new(input.as_bytes())))
+            }, None);
+            conn.set_take_ownership_with_pid(1234);
+
+            let version = conn.run(|c| Box::pin(c.get_info("version"))).await.unwrap();
+            assert_eq!(version, "0.4.2.5");
+            assert_eq!(
+                std::str::from_utf8(&conn.get_ref().unwrap().get_ref().output).unwrap(),
+                concat!(
+                    "PROTOCOLINFO 1\r\nAUTHENTICATE\r\n",
+                    "SETCONF __OwningControllerProcess=\"1234\"\r\nTAKEOWNERSHIP\r\n",
+                    "GETINFO version\r\n",
+                ),
+            );
+        });
+    }
+
+    #[test]
+    fn test_retries_only_once() {
+        block_on(async move {
+            let mut conn: ReconnectingConn<TestStream, NoopHandler, _> = ReconnectingConn::new(|| {
+                std::future::ready(Ok(TestStream::new(CONNECT_RESPONSE.as_bytes())))
+            }, Some(TorAuthData::Null));
+            match conn.run(|c| Box::pin(c.get_info("version"))).await {
+                Err(ConnError::ConnectionClosed) => {}
+                _ => panic!("Expected connection closed error"),
+            }
+            assert!(!conn.is_connected());
+        });
+    }
+}
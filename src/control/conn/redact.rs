@@ -0,0 +1,93 @@
+/// REDACTED is text which replaces secret values in traced data
+const REDACTED: &str = "<redacted>";
+
+/// redact_command returns command written to tor with obvious secrets replaced.
+///
+/// `AUTHENTICATE` arguments(password or cookie based hash), key blobs of `ADD_ONION` and `ONION_CLIENT_AUTH_ADD`
+/// and `ClientAuth=` credentials are hidden. Command keywords and the rest of arguments are kept.
+pub(crate) fn redact_command(data: &[u8]) -> String {
+    let text = String::from_utf8_lossy(data);
+    let mut res = String::with_capacity(text.len());
+    for line in text.split_terminator("\r\n") {
+        if !res.is_empty() {
+            res.push_str("\r\n");
+        }
+        if line.len() > "AUTHENTICATE".len() && line.starts_with("AUTHENTICATE ") {
+            res.push_str("AUTHENTICATE ");
+            res.push_str(REDACTED);
+        } else {
+            redact_words(line, &mut res);
+        }
+    }
+    res
+}
+
+/// redact_reply returns reply line received from tor with private keys and client auth credentials replaced
+pub(crate) fn redact_reply(line: &str) -> String {
+    let mut res = String::with_capacity(line.len());
+    redact_words(line, &mut res);
+    res
+}
+
+fn redact_words(line: &str, res: &mut String) {
+    for (i, word) in line.split(' ').enumerate() {
+        if i != 0 {
+            res.push(' ');
+        }
+        res.push_str(&redact_word(word));
+    }
+}
+
+fn redact_word(word: &str) -> std::borrow::Cow<'_, str> {
+    for prefix in ["PrivateKey=", "ClientAuth="].iter() {
+        if word.starts_with(prefix) {
+            return format!("{}{}", prefix, REDACTED).into();
+        }
+    }
+    for key_type in ["ED25519-V3:", "RSA1024:", "x25519:"].iter() {
+        if word.starts_with(key_type) {
+            return format!("{}{}", key_type, REDACTED).into();
+        }
+    }
+    word.into()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_can_redact_command() {
+        for (i, o) in [
+            ("GETINFO version\r\n", "GETINFO version"),
+            ("AUTHENTICATE\r\n", "AUTHENTICATE"),
+            ("AUTHENTICATE \"password\"\r\n", "AUTHENTICATE <redacted>"),
+            ("AUTHENTICATE 0123abcd\r\n", "AUTHENTICATE <redacted>"),
+            ("AUTHCHALLENGE SAFECOOKIE 0123abcd\r\n", "AUTHCHALLENGE SAFECOOKIE 0123abcd"),
+            (
+                "ADD_ONION ED25519-V3:S0m3+K3y/Bl0b== Flags=Detach Port=80,127.0.0.1:8080\r\n",
+                "ADD_ONION ED25519-V3:<redacted> Flags=Detach Port=80,127.0.0.1:8080",
+            ),
+            ("ADD_ONION NEW:BEST Port=80 ClientAuth=bob:Bl0b\r\n", "ADD_ONION NEW:BEST Port=80 ClientAuth=<redacted>"),
+            (
+                "ONION_CLIENT_AUTH_ADD abcd x25519:S0m3K3y= ClientName=bob\r\n",
+                "ONION_CLIENT_AUTH_ADD abcd x25519:<redacted> ClientName=bob",
+            ),
+            ("SETEVENTS CIRC\r\nGETINFO version\r\n", "SETEVENTS CIRC\r\nGETINFO version"),
+        ].iter() {
+            assert_eq!(redact_command(i.as_bytes()), *o, "{:?}", i);
+        }
+    }
+
+    #[test]
+    fn test_can_redact_reply() {
+        for (i, o) in [
+            ("version=0.4.5.7", "version=0.4.5.7"),
+            ("ServiceID=abcd", "ServiceID=abcd"),
+            ("PrivateKey=ED25519-V3:S0m3K3y==", "PrivateKey=<redacted>"),
+            ("CLIENT abcd x25519:S0m3K3y=", "CLIENT abcd x25519:<redacted>"),
+        ].iter() {
+            assert_eq!(redact_reply(i), *o, "{:?}", i);
+        }
+    }
+}
@@ -51,6 +51,18 @@ impl<S> UnauthenticatedConn<S> {
     pub fn take_protocol_info(&mut self) -> Option<TorPreAuthInfo<'static>> {
         self.protocol_info.take()
     }
+
+    /// into_inner returns underlying stream, for instance in order to close it explicitly
+    ///
+    /// Protocol info loaded before is dropped.
+    pub fn into_inner(self) -> S {
+        self.conn.into_inner()
+    }
+
+    /// get_ref returns reference to underlying stream
+    pub fn get_ref(&self) -> &S {
+        self.conn.get_ref()
+    }
 }
 
 /// TOR_SAFECOOKIE_CONSTANT is passed to HMAC for `SAFECOOKIE` auth procedure
@@ -72,9 +84,20 @@ pub(crate) struct AuthChallengeResponse {
 impl<S> UnauthenticatedConn<S>
     where S: AsyncRead + Unpin
 {
+    // recv response and skip async events until there are some
+    // there is no event handler yet, so they are just dropped
+    async fn recv_response(&mut self) -> Result<(u16, Vec<String>), ConnError> {
+        loop {
+            let (code, lines) = self.conn.receive_data().await?;
+            if code != 650 {
+                return Ok((code, lines));
+            }
+        }
+    }
+
     // exposed for testing and fuzzing
     pub(crate) async fn read_protocol_info<'a>(&'a mut self) -> Result<&'a TorPreAuthInfo<'static>, ConnError> {
-        let (code, lines) = self.conn.receive_data().await?;
+        let (code, lines) = self.recv_response().await?;
 
         // 250 code is hardcoded at spec right now
         // we do not expect async events yet
@@ -84,8 +107,16 @@ impl<S> UnauthenticatedConn<S>
         if lines.len() < 3 {
             return Err(ConnError::InvalidFormat);
         }
-        if lines[0] != "PROTOCOLINFO 1" {
-            return Err(ConnError::InvalidFormat);
+        // all tor versions send `PROTOCOLINFO 1` but newer protocol versions are expected to be backward compatible
+        let protocol_version = lines[0].strip_prefix("PROTOCOLINFO ")
+            .and_then(|v| v.parse::<u32>().ok())
+            .filter(|v| *v >= 1)
+            .ok_or(ConnError::InvalidFormat)?;
+        #[cfg(feature = "tracing")]
+        {
+            if protocol_version > 1 {
+                tracing::warn!(protocol_version, "tor sent unknown PROTOCOLINFO version, parsing it as version 1");
+            }
         }
         let mut res = HashMap::new();
         for l in &lines[1..lines.len() - 1] {
@@ -177,6 +208,7 @@ impl<S> UnauthenticatedConn<S>
         self.was_protocol_info_loaded = true;
         {
             self.protocol_info = Some(TorPreAuthInfo {
+                protocol_version,
                 auth_methods,
                 cookie_file: cookie_path.map(|v| Cow::Owned(v)),
                 tor_version: Cow::Owned(version),
@@ -192,7 +224,7 @@ impl<S> UnauthenticatedConn<S>
     // (250 )AUTHCHALLENGE SERVERHASH=3AB21C1D4E7337F2CC4460C9973B13EE42944E6455131A8CA0CF10628BCBACF2 \
     // SERVERNONCE=DB3B06356534DE8732C8C858F543D0E55B8D44A2353F913B5F36E23A61537D86
     pub(crate) async fn read_auth_challenge_response(&mut self) -> Result<AuthChallengeResponse, ConnError> {
-        let (code, mut lines) = self.conn.receive_data().await?;
+        let (code, mut lines) = self.recv_response().await?;
         if code != 250 {
             return Err(ConnError::InvalidResponseCode(code));
         }
@@ -253,6 +285,10 @@ impl<S> UnauthenticatedConn<S>
     /// It does not check if provided auth method associated with given tor auth data is valid.
     /// It trusts programmer to do so.
     /// In worst case it won't work(tor won't let us in)
+    ///
+    /// # Error
+    /// When tor refuses authentication `ConnError::TorError` is returned.
+    /// Usually it's `TorErrorKind::BadAuth` when provided password or cookie is invalid.
     pub async fn authenticate(&mut self, data: &TorAuthData<'_>) -> Result<(), ConnError> {
         match data {
             TorAuthData::Null => {
@@ -315,9 +351,9 @@ impl<S> UnauthenticatedConn<S>
                 self.conn.write_data(&buf[..]).await?;
             }
         }
-        let (code, _) = self.conn.receive_data().await?;
+        let (code, lines) = self.recv_response().await?;
         if code != 250 {
-            return Err(ConnError::InvalidResponseCode(code));
+            return Err(ConnError::from_response_code(code, lines));
         }
         Ok(())
     }
@@ -327,13 +363,36 @@ impl<S> UnauthenticatedConn<S>
     pub async fn into_authenticated<H>(self) -> AuthenticatedConn<S, H> {
         AuthenticatedConn::from(self.conn)
     }
+
+    /// try_into_authenticated creates `AuthenticatedConn` from this one but unlike `into_authenticated`
+    /// it checks if connection is really authenticated first by sending `GETINFO version`,
+    /// which tor answers only for authenticated controllers.
+    ///
+    /// # Error
+    /// On failure this connection is returned back together with error.
+    /// Note that tor closes control connection when it receives any command other than `PROTOCOLINFO`,
+    /// `AUTHCHALLENGE`, `AUTHENTICATE` or `QUIT` before authentication, so in practice returned connection
+    /// is useful for inspecting or dropping underlying stream rather than for retrying authentication.
+    ///
+    /// It does not apply any timeout. Wrap it in `tokio::time::timeout` if tor may not respond.
+    pub async fn try_into_authenticated<H>(mut self) -> Result<AuthenticatedConn<S, H>, (Self, ConnError)> {
+        if let Err(err) = self.conn.write_data(b"GETINFO version\r\n").await {
+            return Err((self, err));
+        }
+        match self.recv_response().await {
+            Ok((250, _)) => Ok(AuthenticatedConn::from(self.conn)),
+            Ok((code, lines)) => Err((self, ConnError::from_response_code(code, lines))),
+            Err(err) => Err((self, err)),
+        }
+    }
 }
 
 #[cfg(test)]
 mod test {
     use std::io::Cursor;
 
-    use crate::utils::block_on;
+    use crate::control::{AsyncEvent, TorErrorKind};
+    use crate::utils::{block_on, TestStream};
 
     use super::*;
 
@@ -349,6 +408,7 @@ mod test {
                 ),
                 Some(
                     TorPreAuthInfo {
+                        protocol_version: 1,
                         tor_version: Cow::Owned("0.4.2.5".to_string()),
                         auth_methods: [
                             TorAuthMethod::Null,
@@ -366,6 +426,7 @@ mod test {
                 ),
                 Some(
                     TorPreAuthInfo {
+                        protocol_version: 1,
                         tor_version: Cow::Owned("0.4.2.5".to_string()),
                         auth_methods: [
                             // sets do not have order!
@@ -385,6 +446,7 @@ mod test {
                 ),
                 Some(
                     TorPreAuthInfo {
+                        protocol_version: 1,
                         tor_version: Cow::Owned("0.4.2.5".to_string()),
                         auth_methods: [
                             // sets do not have order!
@@ -396,6 +458,62 @@ mod test {
                     }
                 )
             ),
+            (
+                // async events are skipped
+                concat!(
+                "650 NOTICE New control connection opened.\r\n",
+                "650+NS\r\nr line\r\n.\r\n650 OK\r\n",
+                "250-PROTOCOLINFO 1\r\n",
+                "250-AUTH METHODS=NULL\r\n",
+                "250-VERSION Tor=\"0.4.2.5\"\r\n",
+                "250 OK\r\n",
+                ),
+                Some(
+                    TorPreAuthInfo {
+                        protocol_version: 1,
+                        tor_version: Cow::Owned("0.4.2.5".to_string()),
+                        auth_methods: [
+                            TorAuthMethod::Null,
+                        ].iter().copied().collect(),
+                        cookie_file: None,
+                    }
+                )
+            ),
+            (
+                // newer protocol version is accepted
+                concat!(
+                "250-PROTOCOLINFO 2\r\n",
+                "250-AUTH METHODS=NULL\r\n",
+                "250-VERSION Tor=\"0.4.2.5\"\r\n",
+                "250 OK\r\n",
+                ),
+                Some(
+                    TorPreAuthInfo {
+                        protocol_version: 2,
+                        tor_version: Cow::Owned("0.4.2.5".to_string()),
+                        auth_methods: [
+                            TorAuthMethod::Null,
+                        ].iter().copied().collect(),
+                        cookie_file: None,
+                    }
+                )
+            ),
+            (
+                "250-PROTOCOLINFO 0\r\n250-AUTH METHODS=NULL\r\n250-VERSION Tor=\"0.4.2.5\"\r\n250 OK\r\n",
+                None
+            ),
+            (
+                "250-PROTOCOLINFO x\r\n250-AUTH METHODS=NULL\r\n250-VERSION Tor=\"0.4.2.5\"\r\n250 OK\r\n",
+                None
+            ),
+            (
+                "250-PROTOCOLINFO\r\n250-AUTH METHODS=NULL\r\n250-VERSION Tor=\"0.4.2.5\"\r\n250 OK\r\n",
+                None
+            ),
+            (
+                "650 NOTICE New control connection opened.\r\n",
+                None
+            ),
         ].iter().cloned() {
             block_on(async move {
                 let mut conn = UnauthenticatedConn::new(Cursor::new(i.as_bytes()));
@@ -411,6 +529,88 @@ mod test {
             });
         }
     }
+
+    #[test]
+    fn test_can_make_auth_data_with_password() {
+        let mut info = TorPreAuthInfo {
+            protocol_version: 1,
+            tor_version: Cow::Borrowed("0.4.2.5"),
+            auth_methods: [
+                TorAuthMethod::HashedPassword,
+            ].iter().copied().collect(),
+            cookie_file: None,
+        };
+        assert_eq!(info.make_auth_data().unwrap(), None);
+        assert_eq!(info.make_auth_data_with_password(None).unwrap(), None);
+        assert_eq!(
+            info.make_auth_data_with_password(Some("password")).unwrap(),
+            Some(TorAuthData::HashedPassword(Cow::Borrowed("password")))
+        );
+
+        info.auth_methods.insert(TorAuthMethod::Null);
+        assert_eq!(info.make_auth_data_with_password(Some("password")).unwrap(), Some(TorAuthData::Null));
+    }
+
+    #[test]
+    fn test_authenticate_skips_async_events() {
+        block_on(async move {
+            let mut stream = TestStream::new(b"650 WARN Some warning\r\n250 OK\r\n");
+            let mut conn = UnauthenticatedConn::new(&mut stream);
+            conn.authenticate(&TorAuthData::Null).await.unwrap();
+        });
+    }
+
+    #[test]
+    fn test_authenticate_returns_tor_error_kind() {
+        block_on(async move {
+            let mut stream = TestStream::new(b"515 Authentication failed: Password did not match HashedControlPassword value from configuration\r\n");
+            let mut conn = UnauthenticatedConn::new(&mut stream);
+            match conn.authenticate(&TorAuthData::HashedPassword(Cow::Borrowed("password"))).await {
+                Err(ConnError::TorError(TorErrorKind::BadAuth, lines)) => {
+                    assert_eq!(lines.len(), 1);
+                }
+                res => panic!("Unexpected authenticate result: {:?}", res),
+            }
+        });
+    }
+
+    #[test]
+    fn test_can_authenticate_with_empty_password() {
+        block_on(async move {
+            let mut stream = TestStream::new(b"250 OK\r\n");
+            {
+                let mut conn = UnauthenticatedConn::new(&mut stream);
+                conn.authenticate(&TorAuthData::HashedPassword(Cow::Borrowed(""))).await.unwrap();
+            }
+            assert_eq!(stream.output, b"AUTHENTICATE \"\"\r\n");
+        });
+    }
+
+    type NoopHandler = fn(AsyncEvent<'static>) -> std::future::Ready<Result<(), ConnError>>;
+
+    #[test]
+    fn test_try_into_authenticated_checks_auth() {
+        block_on(async move {
+            let mut stream = TestStream::new(b"250-version=0.4.5.6\r\n250 OK\r\n");
+            let conn = UnauthenticatedConn::new(&mut stream);
+            let res = conn.try_into_authenticated::<NoopHandler>().await;
+            assert!(res.is_ok());
+            drop(res);
+            assert_eq!(stream.output, b"GETINFO version\r\n");
+        });
+
+        block_on(async move {
+            let mut stream = TestStream::new(b"514 Authentication required.\r\n");
+            let conn = UnauthenticatedConn::new(&mut stream);
+            match conn.try_into_authenticated::<NoopHandler>().await {
+                Err((_conn, ConnError::TorError(TorErrorKind::AuthRequired, lines))) => {
+                    assert_eq!(lines, vec!["Authentication required.".to_string()]);
+                }
+                Ok(_) => panic!("Unauthenticated connection was converted"),
+                Err((_, err)) => panic!("Unexpected error: {:?}", err),
+            }
+        });
+    }
 }
 
 #[cfg(all(test, testtor))]
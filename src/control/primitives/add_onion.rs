@@ -0,0 +1,149 @@
+use crate::control::AuthenticatedConnError;
+use crate::utils::parse_single_key_value;
+
+/// AddOnionOptions contains settings of onion service created with `ADD_ONION` command,
+/// so they don't have to be passed as a bunch of positional arguments.
+///
+/// # Example
+/// ```
+/// use torut::control::AddOnionOptions;
+/// let options = AddOnionOptions {
+///     detach: true,
+///     max_num_streams: Some(16),
+///     ..Default::default()
+/// };
+/// assert!(!options.non_anonymous);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct AddOnionOptions {
+    /// detach makes onion service outlive control connection which created it.
+    /// Detached services have to be removed with `DEL_ONION` explicitly.
+    pub detach: bool,
+
+    /// non_anonymous creates single-hop onion service.
+    /// It works only if tor is configured to run non-anonymous onion services.
+    pub non_anonymous: bool,
+
+    /// max_streams_close_circuit closes circuit when `max_num_streams` is exceeded
+    /// instead of just rejecting new streams.
+    pub max_streams_close_circuit: bool,
+
+    /// max_num_streams is maximum number of streams per rendezvous circuit.
+    /// `None` means no limit.
+    pub max_num_streams: Option<u16>,
+}
+
+/// AddOnionResponse is parsed reply to `ADD_ONION` command like:
+/// ```text
+/// 250-ServiceID=vww6ybal4bd7szmgncyruucpgfkqahzddi37ktceo3ah7ngmcopnpyyd
+/// 250-PrivateKey=ED25519-V3:<base64 key blob>
+/// 250 OK
+/// ```
+///
+/// # TorCP docs
+/// Ctrl+F `3.27. ADD_ONION`
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct AddOnionResponse {
+    /// service_id is address of onion service without `.onion` part
+    pub service_id: String,
+
+    /// private_key is key blob like `ED25519-V3:<base64>`.
+    /// Tor sends it only when it has generated key itself and `DiscardPK` flag was not set.
+    pub private_key: Option<String>,
+
+    /// client_auth contains values of `ClientAuth=` and `ClientAuthV3=` lines in order they were sent
+    pub client_auth: Vec<String>,
+}
+
+/// parse_add_onion_response parses lines of successful(250) `ADD_ONION` reply.
+/// Unknown lines and trailing `OK` are ignored.
+///
+/// # Error
+/// `AuthenticatedConnError::InvalidAddOnionResponse` is returned when `ServiceID=` line is missing, duplicated or malformed.
+pub fn parse_add_onion_response(lines: &[String]) -> Result<AddOnionResponse, AuthenticatedConnError> {
+    let mut service_id = None;
+    let mut res = AddOnionResponse::default();
+    for line in lines {
+        match parse_single_key_value(line) {
+            Ok(("ServiceID", value)) => {
+                if service_id.is_some() || value.is_empty() || !value.chars().all(|c| c.is_ascii_alphanumeric()) {
+                    return Err(AuthenticatedConnError::InvalidAddOnionResponse);
+                }
+                service_id = Some(value);
+            }
+            Ok(("PrivateKey", value)) => {
+                if res.private_key.is_some() || value.is_empty() {
+                    return Err(AuthenticatedConnError::InvalidAddOnionResponse);
+                }
+                res.private_key = Some(value.to_string());
+            }
+            Ok(("ClientAuth", value)) | Ok(("ClientAuthV3", value)) => {
+                res.client_auth.push(value.to_string());
+            }
+            _ => {}
+        }
+    }
+    res.service_id = service_id
+        .ok_or(AuthenticatedConnError::InvalidAddOnionResponse)?
+        .to_string();
+    Ok(res)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_can_parse_add_onion_response() {
+        for (i, o) in [
+            (
+                &[
+                    "ServiceID=vww6ybal4bd7szmgncyruucpgfkqahzddi37ktceo3ah7ngmcopnpyyd",
+                    "OK",
+                ][..],
+                Some(AddOnionResponse {
+                    service_id: "vww6ybal4bd7szmgncyruucpgfkqahzddi37ktceo3ah7ngmcopnpyyd".to_string(),
+                    private_key: None,
+                    client_auth: vec![],
+                })
+            ),
+            (
+                &[
+                    "ServiceID=vww6ybal4bd7szmgncyruucpgfkqahzddi37ktceo3ah7ngmcopnpyyd",
+                    "PrivateKey=ED25519-V3:KEYBLOB+/=",
+                    "ClientAuthV3=ABCDEF",
+                    "ClientAuthV3=GHIJKL",
+                    "OK",
+                ][..],
+                Some(AddOnionResponse {
+                    service_id: "vww6ybal4bd7szmgncyruucpgfkqahzddi37ktceo3ah7ngmcopnpyyd".to_string(),
+                    private_key: Some("ED25519-V3:KEYBLOB+/=".to_string()),
+                    client_auth: vec!["ABCDEF".to_string(), "GHIJKL".to_string()],
+                })
+            ),
+            (
+                &[
+                    "ServiceID=exampleonion1234",
+                    "PrivateKey=RSA1024:KEYBLOB",
+                    "ClientAuth=bob:BLOB",
+                    "OK",
+                ][..],
+                Some(AddOnionResponse {
+                    service_id: "exampleonion1234".to_string(),
+                    private_key: Some("RSA1024:KEYBLOB".to_string()),
+                    client_auth: vec!["bob:BLOB".to_string()],
+                })
+            ),
+            (&["OK"][..], None),
+            (&[][..], None),
+            (&["ServiceID=", "OK"][..], None),
+            (&["ServiceID=not an address", "OK"][..], None),
+            (&["ServiceID=abc", "ServiceID=def", "OK"][..], None),
+            (&["ServiceID=abc", "PrivateKey=", "OK"][..], None),
+        ].iter() {
+            let lines = i.iter().map(|l| l.to_string()).collect::<Vec<_>>();
+            assert_eq!(parse_add_onion_response(&lines).ok(), *o, "{:?}", i);
+        }
+    }
+}
@@ -1,7 +1,14 @@
 use std::borrow::Cow;
 use std::collections::HashSet;
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
 use std::str::FromStr;
-use std::io::Read;
+use std::io::{self, Read};
+use std::path::{Component, Path, PathBuf};
+
+use rand::{RngCore, thread_rng};
+use sha1::{Digest, Sha1};
+use tokio::io::AsyncReadExt;
 
 /// TorAuthMethod describes method which tor accepts as authentication method
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -46,6 +53,10 @@ pub const COOKIE_LENGTH: usize = 32;
 #[derive(Debug, Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub struct TorPreAuthInfo<'a> {
+    /// protocol_version is version of `PROTOCOLINFO` reply, which is `1` for all tor versions so far.
+    /// Newer versions are accepted as well, since they are expected to be backward compatible.
+    /// They are parsed just like version `1`; with `tracing` feature a warning is logged when one is received.
+    pub protocol_version: u32,
     pub tor_version: Cow<'a, str>,
     pub auth_methods: HashSet<TorAuthMethod>,
     // for any modern os path is valid string. No need for base64-decode it or something like that.
@@ -69,30 +80,202 @@ impl<'a> TorPreAuthInfo<'a> {
     /// Example of such method which requires care is HashedPassword. It can't be automated by design.
     /// 
     /// It returns `std::io::Error` when reading cookiefile fails.
+    /// If cookie file is shorter or longer than `COOKIE_LENGTH`, which usually means that `cookie_file` points to wrong file,
+    /// returned error has `InvalidData` kind and it wraps `AuthError::CookieFileWrongSize`.
     pub fn make_auth_data(&self) -> Result<Option<TorAuthData<'static>>, std::io::Error> {
+        self.make_auth_data_with_password(None)
+    }
+
+    /// make_auth_data_with_password works just like `make_auth_data` but it's also able to use
+    /// `HashedPassword` auth method if password was provided.
+    ///
+    /// Password is used only when neither null nor cookie authentication is available.
+    ///
+    /// # Returns
+    /// It returns `Ok(None)` when there is no authentication method which may be used.
+    /// For instance when only `HashedPassword` is allowed but no password was given.
+    ///
+    /// It returns `std::io::Error` when reading cookiefile fails.
+    pub fn make_auth_data_with_password(&self, password: Option<&str>) -> Result<Option<TorAuthData<'static>>, std::io::Error> {
+        self.make_auth_data_from_fs(password, None)
+    }
+
+    /// make_auth_data_with_base works just like `make_auth_data` but cookie file path reported by tor
+    /// is resolved relative to `base` directory rather than root of controller's filesystem.
+    ///
+    /// It's useful when tor runs in chroot or container, so `cookie_file` is path in tor's filesystem.
+    /// For instance with `base` set to `/var/lib/tor-chroot` cookie file `/var/run/tor/control.authcookie`
+    /// is read from `/var/lib/tor-chroot/var/run/tor/control.authcookie`.
+    /// `..` components of cookie file path are ignored, so file outside of `base` is never read.
+    ///
+    /// # Returns
+    /// Same as `make_auth_data`.
+    pub fn make_auth_data_with_base(&self, base: &Path) -> Result<Option<TorAuthData<'static>>, std::io::Error> {
+        self.make_auth_data_from_fs(None, Some(base))
+    }
+
+    fn make_auth_data_from_fs(&self, password: Option<&str>, base: Option<&Path>) -> Result<Option<TorAuthData<'static>>, std::io::Error> {
+        match self.choose_auth_method(password, base) {
+            AuthMethodChoice::Ready(data) => Ok(data),
+            AuthMethodChoice::ReadCookieFile(path) => {
+                let mut buffer = Vec::with_capacity(COOKIE_LENGTH + 1);
+                // one more byte is read, so too big file is detected as well
+                std::fs::File::open(&path)?
+                    .take(COOKIE_LENGTH as u64 + 1)
+                    .read_to_end(&mut buffer)?;
+                check_cookie_size(&path.to_string_lossy(), &buffer)?;
+
+                Ok(Some(TorAuthData::Cookie(Cow::Owned(buffer))))
+            }
+        }
+    }
+
+    // choose_auth_method picks authentication method for `make_auth_data*` functions.
+    // It does not touch filesystem, so cookie file is read by caller, either with sync or async IO.
+    fn choose_auth_method(&self, password: Option<&str>, base: Option<&Path>) -> AuthMethodChoice {
         if self.auth_methods.contains(&TorAuthMethod::Null) {
-            Ok(Some(TorAuthData::Null))
-        } else if self.auth_methods.contains(&TorAuthMethod::SafeCookie) && self.cookie_file.is_some() {
-            let mut f = std::fs::File::open(self.cookie_file.as_ref().unwrap().as_ref())?;
-            let mut buffer = Vec::new();
-            buffer.resize(COOKIE_LENGTH, 0u8);
-            f.read_exact(&mut buffer[..])?;
-
-            Ok(Some(TorAuthData::Cookie(Cow::Owned(buffer))))
-        } else if self.auth_methods.contains(&TorAuthMethod::Cookie) && self.cookie_file.is_some() {
-            let mut f = std::fs::File::open(self.cookie_file.as_ref().unwrap().as_ref())?;
-            let mut buffer = Vec::new();
-            buffer.resize(COOKIE_LENGTH, 0u8);
-            f.read_exact(&mut buffer[..])?;
-
-            Ok(Some(TorAuthData::Cookie(Cow::Owned(buffer))))
+            AuthMethodChoice::Ready(Some(TorAuthData::Null))
+        } else if (self.auth_methods.contains(&TorAuthMethod::SafeCookie) || self.auth_methods.contains(&TorAuthMethod::Cookie))
+            && self.cookie_file.is_some() {
+            let path = Path::new(self.cookie_file.as_ref().unwrap().as_ref());
+            AuthMethodChoice::ReadCookieFile(match base {
+                Some(base) => join_to_base(base, path),
+                None => path.to_path_buf(),
+            })
+        } else if let Some(password) = password.filter(|_| self.auth_methods.contains(&TorAuthMethod::HashedPassword)) {
+            AuthMethodChoice::Ready(Some(TorAuthData::HashedPassword(Cow::Owned(password.to_string()))))
+        } else {
+            AuthMethodChoice::Ready(None)
+        }
+    }
+
+    /// make_auth_data_from_cookie creates cookie authentication data from given cookie, without touching filesystem.
+    /// It's useful when controller can't access cookie file(for instance because of sandbox),
+    /// but cookie is passed to it by other process.
+    ///
+    /// `SafeCookie` method is used if tor allows it, `Cookie` otherwise.
+    ///
+    /// # Returns
+    /// It returns `Ok(None)` when tor accepts neither `SafeCookie` nor `Cookie` authentication.
+    ///
+    /// It returns `AuthError::InvalidCookieLength` when cookie is not `COOKIE_LENGTH` bytes long.
+    pub fn make_auth_data_from_cookie(&self, cookie: &[u8]) -> Result<Option<TorAuthData<'static>>, AuthError> {
+        if cookie.len() != COOKIE_LENGTH {
+            return Err(AuthError::InvalidCookieLength {
+                expected: COOKIE_LENGTH,
+                actual: cookie.len(),
+            });
+        }
+        if self.auth_methods.contains(&TorAuthMethod::SafeCookie) {
+            Ok(Some(TorAuthData::SafeCookie(Cow::Owned(cookie.to_vec()))))
+        } else if self.auth_methods.contains(&TorAuthMethod::Cookie) {
+            Ok(Some(TorAuthData::Cookie(Cow::Owned(cookie.to_vec()))))
         } else {
             Ok(None)
         }
     }
+
+    /// make_auth_data_async works just like `make_auth_data` but cookie file is read with `tokio::fs`,
+    /// so reading it does not block executor. It matters when cookie lives on slow or networked filesystem.
+    ///
+    /// # Returns
+    /// Same as `make_auth_data`.
+    pub async fn make_auth_data_async(&self) -> Result<Option<TorAuthData<'static>>, std::io::Error> {
+        self.make_auth_data_with_password_async(None).await
+    }
+
+    /// make_auth_data_with_password_async is async version of `make_auth_data_with_password`.
+    /// Take a look at `make_auth_data_async` for details.
+    pub async fn make_auth_data_with_password_async(&self, password: Option<&str>) -> Result<Option<TorAuthData<'static>>, std::io::Error> {
+        self.make_auth_data_from_fs_async(password, None).await
+    }
+
+    /// make_auth_data_with_base_async is async version of `make_auth_data_with_base`.
+    /// Take a look at `make_auth_data_async` for details.
+    pub async fn make_auth_data_with_base_async(&self, base: &Path) -> Result<Option<TorAuthData<'static>>, std::io::Error> {
+        self.make_auth_data_from_fs_async(None, Some(base)).await
+    }
+
+    async fn make_auth_data_from_fs_async(&self, password: Option<&str>, base: Option<&Path>) -> Result<Option<TorAuthData<'static>>, std::io::Error> {
+        match self.choose_auth_method(password, base) {
+            AuthMethodChoice::Ready(data) => Ok(data),
+            AuthMethodChoice::ReadCookieFile(path) => {
+                let mut buffer = Vec::with_capacity(COOKIE_LENGTH + 1);
+                tokio::fs::File::open(&path).await?
+                    .take(COOKIE_LENGTH as u64 + 1)
+                    .read_to_end(&mut buffer).await?;
+                check_cookie_size(&path.to_string_lossy(), &buffer)?;
+
+                Ok(Some(TorAuthData::Cookie(Cow::Owned(buffer))))
+            }
+        }
+    }
+}
+
+// AuthMethodChoice is result of `TorPreAuthInfo::choose_auth_method`
+enum AuthMethodChoice {
+    // Ready contains authentication data which does not require reading any file
+    Ready(Option<TorAuthData<'static>>),
+    // ReadCookieFile contains path of cookie file which has to be read
+    ReadCookieFile(PathBuf),
+}
+
+/// AuthError describes problems with preparing authentication data, which are not plain IO errors.
+///
+/// It's returned wrapped in `std::io::Error`, so it can be obtained with `std::io::Error::get_ref` and `downcast_ref`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum AuthError {
+    /// CookieFileWrongSize is returned when cookie file is not exactly `expected` bytes long.
+    CookieFileWrongSize {
+        expected: usize,
+        path: String,
+    },
+
+    /// InvalidCookieLength is returned when cookie passed directly is not `expected` bytes long.
+    InvalidCookieLength {
+        expected: usize,
+        actual: usize,
+    },
+}
+
+impl Display for AuthError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::CookieFileWrongSize { expected, path } => {
+                write!(f, "Cookie file {:?} is not {} bytes long, is it tor's auth cookie?", path, expected)
+            }
+            Self::InvalidCookieLength { expected, actual } => {
+                write!(f, "Cookie is {} bytes long but it should be {} bytes long", actual, expected)
+            }
+        }
+    }
 }
 
-// TODO(teawithsand): test make_auth_data without tor
+impl Error for AuthError {}
+
+// join_to_base joins path onto base even if path is absolute.
+// Root and `..` components are dropped, so path can't point outside of base(symlinks inside base are not checked though).
+fn join_to_base(base: &Path, path: &Path) -> PathBuf {
+    let mut res = base.to_path_buf();
+    for component in path.components() {
+        match component {
+            Component::Prefix(_) | Component::RootDir | Component::ParentDir => {}
+            component => res.push(component),
+        }
+    }
+    res
+}
+
+fn check_cookie_size(path: &str, cookie: &[u8]) -> Result<(), io::Error> {
+    if cookie.len() != COOKIE_LENGTH {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, AuthError::CookieFileWrongSize {
+            expected: COOKIE_LENGTH,
+            path: path.to_string(),
+        }));
+    }
+    Ok(())
+}
 
 /// TorAuthData contains all data required to authenticate single `UnauthenticatedConn`
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -104,7 +287,11 @@ pub enum TorAuthData<'a> {
     /// It should be NEVER used.
     Null,
 
-    /// Password auth requires password
+    /// Password auth requires password.
+    ///
+    /// Note: despite the name it's *plaintext* password which is sent(quoted) in `AUTHENTICATE` command.
+    /// Tor compares it against `HashedControlPassword` from torrc, which may be generated with
+    /// `TorAuthData::hash_password`.
     HashedPassword(Cow<'a, str>),
 
     /// Cookie authentication requires contents of cookie
@@ -126,4 +313,183 @@ impl<'a> TorAuthData<'a> {
     }
 }
 
-// testing is in unauthenticated conn rs
\ No newline at end of file
+/// S2K_COUNT_SPECIFIER is count byte used by `tor --hash-password`.
+/// It means that 65536 bytes of salt and password are hashed.
+const S2K_COUNT_SPECIFIER: u8 = 96;
+
+impl TorAuthData<'static> {
+    /// hash_password creates value for `HashedControlPassword` torrc option from plaintext password,
+    /// just like `tor --hash-password` does. New random salt is used for each call.
+    ///
+    /// Result looks like `16:<hex encoded salt, count byte and digest>`.
+    ///
+    /// It's meant for generating tor's config only. `AUTHENTICATE` command still sends plaintext password,
+    /// so `TorAuthData::HashedPassword` has to be created with plaintext password, not with value returned from here.
+    ///
+    /// # Example
+    /// ```
+    /// use torut::control::TorAuthData;
+    /// let hashed = TorAuthData::hash_password("secret");
+    /// assert!(hashed.starts_with("16:"));
+    /// assert_eq!(hashed.len(), 3 + 2 * (8 + 1 + 20));
+    /// ```
+    pub fn hash_password(plain: &str) -> String {
+        let mut salt = [0u8; 8];
+        thread_rng().fill_bytes(&mut salt[..]);
+        Self::hash_password_with_salt(plain, salt)
+    }
+
+    /// hash_password_with_salt works like `hash_password` but uses given salt rather than random one.
+    /// It's OpenPGP iterated and salted S2K with SHA1 described in RFC 2440.
+    pub fn hash_password_with_salt(plain: &str, salt: [u8; 8]) -> String {
+        let count = (16usize + (S2K_COUNT_SPECIFIER & 15) as usize) << ((S2K_COUNT_SPECIFIER >> 4) + 6);
+
+        let mut secret = Vec::with_capacity(salt.len() + plain.len());
+        secret.extend_from_slice(&salt[..]);
+        secret.extend_from_slice(plain.as_bytes());
+
+        let mut hasher = Sha1::new();
+        let mut left = count;
+        while left > 0 {
+            let n = std::cmp::min(left, secret.len());
+            hasher.update(&secret[..n]);
+            left -= n;
+        }
+
+        let mut res = Vec::with_capacity(8 + 1 + 20);
+        res.extend_from_slice(&salt[..]);
+        res.push(S2K_COUNT_SPECIFIER);
+        res.extend_from_slice(&hasher.finalize()[..]);
+        format!("16:{}", hex::encode_upper(res))
+    }
+}
+
+// testing is in unauthenticated conn rs
+
+#[cfg(test)]
+mod test {
+    use crate::utils::block_on;
+
+    use super::*;
+
+    #[test]
+    fn test_can_make_auth_data_from_cookie() {
+        let cookie = [7u8; COOKIE_LENGTH];
+        let make_info = |methods: &[TorAuthMethod]| TorPreAuthInfo {
+            protocol_version: 1,
+            tor_version: Cow::Borrowed("0.4.5.6"),
+            auth_methods: methods.iter().copied().collect(),
+            cookie_file: Some(Cow::Borrowed("/does/not/exist")),
+        };
+        for (methods, o) in [
+            (&[TorAuthMethod::SafeCookie, TorAuthMethod::Cookie][..], Some(TorAuthData::SafeCookie(Cow::Borrowed(&cookie[..])))),
+            (&[TorAuthMethod::SafeCookie][..], Some(TorAuthData::SafeCookie(Cow::Borrowed(&cookie[..])))),
+            (&[TorAuthMethod::Cookie, TorAuthMethod::HashedPassword][..], Some(TorAuthData::Cookie(Cow::Borrowed(&cookie[..])))),
+            (&[TorAuthMethod::Null][..], None),
+            (&[TorAuthMethod::HashedPassword][..], None),
+        ].iter() {
+            assert_eq!(make_info(methods).make_auth_data_from_cookie(&cookie[..]).unwrap(), *o, "{:?}", methods);
+        }
+
+        let info = make_info(&[TorAuthMethod::Cookie]);
+        for len in [0, COOKIE_LENGTH - 1, COOKIE_LENGTH + 1].iter() {
+            assert_eq!(
+                info.make_auth_data_from_cookie(&vec![0u8; *len]).unwrap_err(),
+                AuthError::InvalidCookieLength { expected: COOKIE_LENGTH, actual: *len }
+            );
+        }
+    }
+
+    #[test]
+    fn test_can_make_auth_data_from_cookie_file() {
+        let dir = std::env::temp_dir();
+        let cookie = (0..COOKIE_LENGTH as u8).collect::<Vec<_>>();
+        let full_path = dir.join(format!("torut_test_cookie_full_{}", std::process::id()));
+        let long_path = dir.join(format!("torut_test_cookie_long_{}", std::process::id()));
+        let short_path = dir.join(format!("torut_test_cookie_short_{}", std::process::id()));
+        std::fs::write(&full_path, &cookie).unwrap();
+        std::fs::write(&long_path, [&cookie[..], &b"trailing"[..]].concat()).unwrap();
+        std::fs::write(&short_path, &cookie[..COOKIE_LENGTH - 1]).unwrap();
+
+        let make_info = |path: Option<&std::path::Path>, methods: &[TorAuthMethod]| TorPreAuthInfo {
+            protocol_version: 1,
+            tor_version: Cow::Borrowed("0.4.5.6"),
+            auth_methods: methods.iter().copied().collect(),
+            cookie_file: path.map(|p| Cow::Owned(p.to_str().unwrap().to_string())),
+        };
+
+        block_on(async {
+            for path in [&full_path].iter() {
+                for methods in [&[TorAuthMethod::Cookie][..], &[TorAuthMethod::SafeCookie, TorAuthMethod::HashedPassword][..]].iter() {
+                    let info = make_info(Some(path), methods);
+                    let expected = Some(TorAuthData::Cookie(Cow::Owned(cookie.clone())));
+                    assert_eq!(info.make_auth_data().unwrap(), expected);
+                    assert_eq!(info.make_auth_data_async().await.unwrap(), expected);
+                }
+            }
+
+            for path in [&short_path, &long_path].iter() {
+                let info = make_info(Some(path), &[TorAuthMethod::Cookie]);
+                let expected = AuthError::CookieFileWrongSize {
+                    expected: COOKIE_LENGTH,
+                    path: path.to_str().unwrap().to_string(),
+                };
+                for err in [info.make_auth_data().unwrap_err(), info.make_auth_data_async().await.unwrap_err()].iter() {
+                    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+                    assert_eq!(err.get_ref().unwrap().downcast_ref::<AuthError>(), Some(&expected));
+                    assert!(err.to_string().contains(path.to_str().unwrap()));
+                }
+            }
+
+            let info = make_info(Some(std::path::Path::new(&format!("/torut_test_cookie_full_{}", std::process::id()))), &[TorAuthMethod::Cookie]);
+            assert_eq!(info.make_auth_data_with_base(&dir).unwrap(), Some(TorAuthData::Cookie(Cow::Owned(cookie.clone()))));
+            assert_eq!(info.make_auth_data_with_base_async(&dir).await.unwrap(), Some(TorAuthData::Cookie(Cow::Owned(cookie.clone()))));
+            let info = make_info(Some(std::path::Path::new(&format!("torut_test_cookie_short_{}", std::process::id()))), &[TorAuthMethod::Cookie]);
+            let err = info.make_auth_data_with_base(&dir).unwrap_err();
+            assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+            assert!(err.to_string().contains(short_path.to_str().unwrap()));
+
+            let info = make_info(Some(&dir.join("torut_test_cookie_does_not_exist")), &[TorAuthMethod::Cookie]);
+            info.make_auth_data().unwrap_err();
+            info.make_auth_data_async().await.unwrap_err();
+
+            let info = make_info(Some(&short_path), &[TorAuthMethod::Null, TorAuthMethod::Cookie]);
+            assert_eq!(info.make_auth_data_async().await.unwrap(), Some(TorAuthData::Null));
+
+            let info = make_info(None, &[TorAuthMethod::Cookie, TorAuthMethod::HashedPassword]);
+            assert_eq!(info.make_auth_data_async().await.unwrap(), None);
+            assert_eq!(
+                info.make_auth_data_with_password_async(Some("secret")).await.unwrap(),
+                Some(TorAuthData::HashedPassword(Cow::Borrowed("secret")))
+            );
+        });
+
+        for path in [&full_path, &long_path, &short_path].iter() {
+            std::fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_join_to_base_stays_in_base() {
+        let base = Path::new("/var/lib/tor-chroot");
+        for (i, o) in [
+            ("/var/run/tor/control.authcookie", "/var/lib/tor-chroot/var/run/tor/control.authcookie"),
+            ("var/run/tor/control.authcookie", "/var/lib/tor-chroot/var/run/tor/control.authcookie"),
+            ("../../etc/x", "/var/lib/tor-chroot/etc/x"),
+            ("/var/run/../../../etc/./x", "/var/lib/tor-chroot/var/run/etc/x"),
+        ].iter() {
+            assert_eq!(join_to_base(base, Path::new(i)), Path::new(o), "{:?}", i);
+        }
+    }
+
+    #[test]
+    fn test_can_hash_password() {
+        for (password, salt, hashed) in [
+            ("", [0u8; 8], "16:0000000000000000601ADC95BEBE9EEA8C112D40CD04AB7A8D75C4F961"),
+            ("secret", [1, 2, 3, 4, 5, 6, 7, 8], "16:0102030405060708604A9FC603431AADC6ADA83362A68B19F5D1E99637"),
+        ].iter() {
+            assert_eq!(TorAuthData::hash_password_with_salt(password, *salt), *hashed);
+        }
+        assert_ne!(TorAuthData::hash_password("secret"), TorAuthData::hash_password("secret"));
+    }
+}
@@ -0,0 +1,210 @@
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::str::FromStr;
+
+use crate::control::primitives::{AsyncEvent, AsyncEventKind, CircuitId};
+use crate::utils::parse_key_value_line;
+
+/// CircuitStatus is status of circuit reported in `CIRC` event and `circuit-status` info key
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum CircuitStatus {
+    /// Launched is set when circuit ID was assigned to new circuit
+    Launched,
+    /// Built is set when all hops of circuit are finished and it can be used
+    Built,
+    /// GuardWait is set when circuit is built but it waits to see if there is circuit with better guard
+    GuardWait,
+    /// Extended is set when one more hop was finished
+    Extended,
+    /// Failed is set when circuit was closed because it could not be built
+    Failed,
+    /// Closed is set when circuit was closed after it was built
+    Closed,
+}
+
+impl FromStr for CircuitStatus {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, ()> {
+        match s {
+            "LAUNCHED" => Ok(Self::Launched),
+            "BUILT" => Ok(Self::Built),
+            "GUARD_WAIT" => Ok(Self::GuardWait),
+            "EXTENDED" => Ok(Self::Extended),
+            "FAILED" => Ok(Self::Failed),
+            "CLOSED" => Ok(Self::Closed),
+            _ => Err(()),
+        }
+    }
+}
+
+/// CircuitStatusEvent is parsed `CIRC` asynchronous event like:
+/// ```text
+/// 650 CIRC 5 BUILT $7EA6EAD6FD83083C538F44038BBFA077587DD755~moria1 BUILD_FLAGS=IS_INTERNAL,NEED_CAPACITY PURPOSE=HS_SERVICE_REND
+/// ```
+///
+/// Each line of `circuit-status` info key has the same format without leading `CIRC`, so it's parsed with `from_str`.
+///
+/// # TorCP docs
+/// Ctrl+F `4.1.1. Circuit status changed`
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct CircuitStatusEvent {
+    pub id: CircuitId,
+
+    pub status: CircuitStatus,
+
+    /// path contains relays circuit goes through, like `$fingerprint~nickname`.
+    /// It's empty when tor did not send it, for instance for `LAUNCHED` circuit.
+    pub path: Vec<String>,
+
+    /// purpose is value of `PURPOSE` argument, like `GENERAL` or `HS_SERVICE_REND`
+    pub purpose: Option<String>,
+
+    /// args contains all keyword arguments including `PURPOSE`. Quoted values are unquoted.
+    pub args: HashMap<String, String>,
+}
+
+impl FromStr for CircuitStatusEvent {
+    type Err = ();
+
+    /// from_str parses single line of `circuit-status` info key, which is `CIRC` event without event name
+    fn from_str(s: &str) -> Result<Self, ()> {
+        let mut parts = s.splitn(3, ' ');
+        let id = parts.next().ok_or(())?.parse()?;
+        let status = parts.next().ok_or(())?.parse()?;
+        let mut rest = parts.next().unwrap_or("");
+
+        // path is the only positional argument which can follow status and it never contains `=`
+        let mut path = Vec::new();
+        let first = rest.split(' ').next().unwrap_or("");
+        if !first.is_empty() && !first.contains('=') {
+            path = first.split(',').map(|relay| relay.to_string()).collect();
+            rest = rest[first.len()..].trim_start_matches(' ');
+        }
+
+        let args: HashMap<String, String> = parse_key_value_line(rest).ok_or(())?
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.into_owned()))
+            .collect();
+        Ok(Self {
+            id,
+            status,
+            path,
+            purpose: args.get("PURPOSE").cloned(),
+            args,
+        })
+    }
+}
+
+impl<'a> TryFrom<&AsyncEvent<'a>> for CircuitStatusEvent {
+    type Error = ();
+
+    fn try_from(event: &AsyncEvent<'a>) -> Result<Self, ()> {
+        let line = event.lines.first().ok_or(())?;
+
+        let mut parts = line.splitn(2, ' ');
+        match parts.next().map(AsyncEventKind::from_str) {
+            Some(Ok(AsyncEventKind::CircuitStatusChanged)) => {}
+            _ => return Err(()),
+        }
+        parts.next().ok_or(())?.parse()
+    }
+}
+
+/// parse_circuit_status parses value of `circuit-status` info key, which contains one circuit per line.
+/// Empty value means that there are no circuits.
+///
+/// # Returns
+/// `None` is returned if any line could not be parsed.
+pub fn parse_circuit_status(text: &str) -> Option<Vec<CircuitStatusEvent>> {
+    text.lines()
+        .map(|l| l.trim())
+        .filter(|l| !l.is_empty())
+        .map(|l| l.parse().ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use std::borrow::Cow;
+
+    use super::*;
+
+    #[test]
+    fn test_can_parse_circuit_status_event() {
+        for (i, o) in [
+            (
+                "CIRC 5 BUILT $7EA6EAD6FD83083C538F44038BBFA077587DD755~moria1,$F2044413DAC2E02E3D6BCF4735A19BCA1DE97281~gabelmoo BUILD_FLAGS=IS_INTERNAL,NEED_CAPACITY PURPOSE=HS_SERVICE_REND TIME_CREATED=2021-03-01T10:00:00.000000",
+                Some(CircuitStatusEvent {
+                    id: CircuitId::Numeric(5),
+                    status: CircuitStatus::Built,
+                    path: vec![
+                        "$7EA6EAD6FD83083C538F44038BBFA077587DD755~moria1".to_string(),
+                        "$F2044413DAC2E02E3D6BCF4735A19BCA1DE97281~gabelmoo".to_string(),
+                    ],
+                    purpose: Some("HS_SERVICE_REND".to_string()),
+                    args: [
+                        ("BUILD_FLAGS", "IS_INTERNAL,NEED_CAPACITY"),
+                        ("PURPOSE", "HS_SERVICE_REND"),
+                        ("TIME_CREATED", "2021-03-01T10:00:00.000000"),
+                    ].iter()
+                        .map(|(k, v)| (k.to_string(), v.to_string()))
+                        .collect(),
+                })
+            ),
+            (
+                "CIRC 6 LAUNCHED BUILD_FLAGS=NEED_CAPACITY PURPOSE=GENERAL",
+                Some(CircuitStatusEvent {
+                    id: CircuitId::Numeric(6),
+                    status: CircuitStatus::Launched,
+                    path: vec![],
+                    purpose: Some("GENERAL".to_string()),
+                    args: [("BUILD_FLAGS", "NEED_CAPACITY"), ("PURPOSE", "GENERAL")].iter()
+                        .map(|(k, v)| (k.to_string(), v.to_string()))
+                        .collect(),
+                })
+            ),
+            (
+                "CIRC 7 CLOSED $7EA6EAD6FD83083C538F44038BBFA077587DD755~moria1",
+                Some(CircuitStatusEvent {
+                    id: CircuitId::Numeric(7),
+                    status: CircuitStatus::Closed,
+                    path: vec!["$7EA6EAD6FD83083C538F44038BBFA077587DD755~moria1".to_string()],
+                    purpose: None,
+                    args: HashMap::new(),
+                })
+            ),
+            ("CIRC 5 UNKNOWN", None),
+            ("CIRC 5", None),
+            ("CIRC ! BUILT", None),
+            ("CIRC 5 BUILT $7EA6EAD6FD83083C538F44038BBFA077587DD755~moria1 PURPOSE", None),
+            ("GUARD ENTRY $7EA6EAD6FD83083C538F44038BBFA077587DD755 UP", None),
+            ("", None),
+        ].iter() {
+            let event = AsyncEvent {
+                code: 650,
+                lines: vec![Cow::Borrowed(*i)],
+            };
+            assert_eq!(CircuitStatusEvent::try_from(&event).ok(), *o, "{:?}", i);
+        }
+    }
+
+    #[test]
+    fn test_can_parse_circuit_status() {
+        let res = parse_circuit_status(concat!(
+            "\r\n",
+            "1 BUILT $7EA6EAD6FD83083C538F44038BBFA077587DD755~moria1 PURPOSE=GENERAL\r\n",
+            "2 LAUNCHED PURPOSE=HS_SERVICE_REND",
+        )).unwrap();
+        assert_eq!(res.len(), 2);
+        assert_eq!(res[0].id, CircuitId::Numeric(1));
+        assert_eq!(res[0].purpose.as_deref(), Some("GENERAL"));
+        assert_eq!(res[1].status, CircuitStatus::Launched);
+        assert_eq!(res[1].purpose.as_deref(), Some("HS_SERVICE_REND"));
+
+        assert_eq!(parse_circuit_status(""), Some(vec![]));
+        assert_eq!(parse_circuit_status("1 BUILT\r\n2 UNKNOWN"), None);
+    }
+}
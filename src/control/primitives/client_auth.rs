@@ -0,0 +1,116 @@
+use std::str::FromStr;
+
+use crate::onion::OnionAddressV3;
+
+/// ClientAuthEntry describes single client authorization credential registered in tor for onion service v3.
+/// Entries are returned by `ONION_CLIENT_AUTH_VIEW` command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct ClientAuthEntry {
+    /// onion_address is address of onion service which this credential is for
+    pub onion_address: OnionAddressV3,
+
+    /// key_type is type of key. Right now torCP specifies only `x25519`
+    pub key_type: String,
+
+    /// key_blob contains private key encoded as tor returned it
+    pub key_blob: String,
+
+    /// client_name is optional nickname of client
+    pub client_name: Option<String>,
+
+    /// flags contains flags of this credential like `Permanent`
+    pub flags: Vec<String>,
+}
+
+impl FromStr for ClientAuthEntry {
+    type Err = ();
+
+    /// from_str parses single `CLIENT` line of `ONION_CLIENT_AUTH_VIEW` reply like:
+    /// ```text
+    /// CLIENT HSAddress KeyType:PrivateKeyBlob [ClientName=Nickname] [Flags=FLAGS]
+    /// ```
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let mut parts = line.split(' ');
+        if parts.next() != Some("CLIENT") {
+            return Err(());
+        }
+        let onion_address = parts.next()
+            .and_then(|addr| OnionAddressV3::from_str(addr).ok())
+            .ok_or(())?;
+        let (key_type, key_blob) = {
+            let key = parts.next().ok_or(())?;
+            let mut key_parts = key.splitn(2, ':');
+            match (key_parts.next(), key_parts.next()) {
+                (Some(key_type), Some(key_blob)) if !key_type.is_empty() && !key_blob.is_empty() => {
+                    (key_type.to_string(), key_blob.to_string())
+                }
+                _ => return Err(()),
+            }
+        };
+
+        let mut res = ClientAuthEntry {
+            onion_address,
+            key_type,
+            key_blob,
+            client_name: None,
+            flags: Vec::new(),
+        };
+        for part in parts {
+            if let Some(name) = part.strip_prefix("ClientName=") {
+                res.client_name = Some(name.to_string());
+            } else if let Some(flags) = part.strip_prefix("Flags=") {
+                res.flags = flags.split(',').map(|f| f.to_string()).collect();
+            }
+            // ignore unknown parameters, tor may introduce some in future
+        }
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    //noinspection SpellCheckingInspection
+    #[test]
+    fn test_can_parse_client_auth_entry() {
+        let oa = OnionAddressV3::from_str("p53lf57qovyuvwsc6xnrppyply3vtqm7l6pcobkmyqsiofyeznfu5uqd").unwrap();
+        for (i, o) in [
+            (
+                "CLIENT p53lf57qovyuvwsc6xnrppyply3vtqm7l6pcobkmyqsiofyeznfu5uqd x25519:yLcvBzVBd2G2q4xIvKfgaKWYcjaWzwM4iUFxXJ4UUlQ=",
+                Some(ClientAuthEntry {
+                    onion_address: oa,
+                    key_type: "x25519".to_string(),
+                    key_blob: "yLcvBzVBd2G2q4xIvKfgaKWYcjaWzwM4iUFxXJ4UUlQ=".to_string(),
+                    client_name: None,
+                    flags: Vec::new(),
+                })
+            ),
+            (
+                "CLIENT p53lf57qovyuvwsc6xnrppyply3vtqm7l6pcobkmyqsiofyeznfu5uqd x25519:yLcvBzVBd2G2q4xIvKfgaKWYcjaWzwM4iUFxXJ4UUlQ= ClientName=alice Flags=Permanent",
+                Some(ClientAuthEntry {
+                    onion_address: oa,
+                    key_type: "x25519".to_string(),
+                    key_blob: "yLcvBzVBd2G2q4xIvKfgaKWYcjaWzwM4iUFxXJ4UUlQ=".to_string(),
+                    client_name: Some("alice".to_string()),
+                    flags: vec!["Permanent".to_string()],
+                })
+            ),
+            (
+                "CLIENT p53lf57qovyuvwsc6xnrppyply3vtqm7l6pcobkmyqsiofyeznfu5uqd x25519",
+                None
+            ),
+            (
+                "CLIENT invalid x25519:yLcvBzVBd2G2q4xIvKfgaKWYcjaWzwM4iUFxXJ4UUlQ=",
+                None
+            ),
+            (
+                "ONION_CLIENT_AUTH_VIEW",
+                None
+            ),
+        ].iter().cloned() {
+            assert_eq!(ClientAuthEntry::from_str(i).ok(), o);
+        }
+    }
+}
@@ -0,0 +1,208 @@
+use std::borrow::Cow;
+use std::convert::TryFrom;
+use std::str::FromStr;
+
+use crate::control::primitives::{AsyncEvent, AsyncEventKind};
+use crate::utils::{parse_single_key_value, unquote_string};
+
+/// ConfValue is value of tor's configuration option used with `SETCONF`.
+///
+/// It lets one pass bools and numbers directly, so there is no need to remember that tor wants `1` rather than `true`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfValue<'a> {
+    /// Bool is formatted as `1` or `0`
+    Bool(bool),
+
+    /// Int is formatted as decimal number
+    Int(i64),
+
+    /// Str is passed as-is(it's quoted later like any other value)
+    Str(&'a str),
+
+    /// None resets option to its default value
+    None,
+}
+
+impl<'a> ConfValue<'a> {
+    /// to_conf_string formats value the way tor expects it in `SETCONF` call(before quoting).
+    /// `None` is returned when option should be reset to default.
+    pub fn to_conf_string(&self) -> Option<Cow<'a, str>> {
+        match self {
+            ConfValue::Bool(true) => Some(Cow::Borrowed("1")),
+            ConfValue::Bool(false) => Some(Cow::Borrowed("0")),
+            ConfValue::Int(v) => Some(Cow::Owned(v.to_string())),
+            ConfValue::Str(v) => Some(Cow::Borrowed(*v)),
+            ConfValue::None => None,
+        }
+    }
+}
+
+impl<'a> From<bool> for ConfValue<'a> {
+    fn from(v: bool) -> Self {
+        ConfValue::Bool(v)
+    }
+}
+
+impl<'a> From<i64> for ConfValue<'a> {
+    fn from(v: i64) -> Self {
+        ConfValue::Int(v)
+    }
+}
+
+impl<'a> From<i32> for ConfValue<'a> {
+    fn from(v: i32) -> Self {
+        ConfValue::Int(v as i64)
+    }
+}
+
+impl<'a> From<u32> for ConfValue<'a> {
+    fn from(v: u32) -> Self {
+        ConfValue::Int(v as i64)
+    }
+}
+
+impl<'a> From<u16> for ConfValue<'a> {
+    fn from(v: u16) -> Self {
+        ConfValue::Int(v as i64)
+    }
+}
+
+impl<'a> From<&'a str> for ConfValue<'a> {
+    fn from(v: &'a str) -> Self {
+        ConfValue::Str(v)
+    }
+}
+
+impl<'a, T> From<Option<T>> for ConfValue<'a>
+    where T: Into<ConfValue<'a>>
+{
+    fn from(v: Option<T>) -> Self {
+        match v {
+            Some(v) => v.into(),
+            None => ConfValue::None,
+        }
+    }
+}
+
+/// parse_conf_line parses single line of `GETCONF` reply or `CONF_CHANGED` event, like `SocksPort=9050`.
+///
+/// Line without `=` is option which is set to its default value, so `None` is returned as value then.
+/// Quoted values are unquoted.
+pub(crate) fn parse_conf_line(line: &str) -> Option<(&str, Option<String>)> {
+    if !line.contains('=') {
+        return Some((line, None));
+    }
+    let (k, v) = parse_single_key_value(line).ok()?;
+    // TODO(teawithsand): Apply some restrictions on what is key?
+    //  ensure unique keys?
+    /*
+        According to torCP docs:
+        ```
+        Value may be a raw value or a quoted string.  Tor will try to use unquoted
+        values except when the value could be misinterpreted through not being
+        quoted. (Right now, Tor supports no such misinterpretable values for
+        configuration options.)
+        ```
+    */
+    let v = match unquote_string(v) {
+        (Some(offset), Ok(unquoted)) if offset == v.len() - 1 => unquoted.into_owned(),
+        (None, Ok(unquoted)) => unquoted.into_owned(),
+        _ => return None,
+    };
+    Some((k, Some(v)))
+}
+
+/// ConfChangedEvent is parsed `CONF_CHANGED` asynchronous event, which is sent when configuration of tor changes:
+/// ```text
+/// 650-CONF_CHANGED
+/// 650-SocksPort=9050
+/// 650-ExitPolicy
+/// 650 OK
+/// ```
+///
+/// # TorCP docs
+/// Ctrl+F `4.1.18. Configuration changed`
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct ConfChangedEvent {
+    /// changes contains changed options and their new values in order sent by tor.
+    /// Value is `None` when option was reset to its default value.
+    pub changes: Vec<(String, Option<String>)>,
+}
+
+impl<'a> TryFrom<&AsyncEvent<'a>> for ConfChangedEvent {
+    type Error = ();
+
+    fn try_from(event: &AsyncEvent<'a>) -> Result<Self, ()> {
+        match event.lines.first().map(|l| AsyncEventKind::from_str(l)) {
+            Some(Ok(AsyncEventKind::ConfigurationChanged)) => {}
+            _ => return Err(()),
+        }
+        let mut lines = &event.lines[1..];
+        if let Some((last, rest)) = lines.split_last() {
+            if last == "OK" {
+                lines = rest;
+            }
+        }
+        let changes = lines.iter()
+            .map(|l| parse_conf_line(l).map(|(k, v)| (k.to_string(), v)))
+            .collect::<Option<_>>()
+            .ok_or(())?;
+        Ok(Self {
+            changes,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_can_parse_conf_changed_event() {
+        for (i, o) in [
+            (
+                &["CONF_CHANGED", "SocksPort=9050", "ExitPolicy", "Nickname=\"quoted\"", "OK"][..],
+                Some(vec![
+                    ("SocksPort", Some("9050")),
+                    ("ExitPolicy", None),
+                    ("Nickname", Some("quoted")),
+                ])
+            ),
+            (&["CONF_CHANGED", "SocksPort=9050", "SocksPort=9150", "OK"][..], Some(vec![
+                ("SocksPort", Some("9050")),
+                ("SocksPort", Some("9150")),
+            ])),
+            (&["CONF_CHANGED", "OK"][..], Some(vec![])),
+            (&["CONF_CHANGED", "Nickname=\"quoted\"trailing", "OK"][..], None),
+            (&["CIRC 1 BUILT"][..], None),
+            (&[][..], None),
+        ].iter() {
+            let event = AsyncEvent {
+                code: 650,
+                lines: i.iter().map(|l| Cow::Borrowed(*l)).collect(),
+            };
+            let expected = o.as_ref().map(|changes| ConfChangedEvent {
+                changes: changes.iter()
+                    .map(|(k, v)| (k.to_string(), v.map(|v| v.to_string())))
+                    .collect(),
+            });
+            assert_eq!(ConfChangedEvent::try_from(&event).ok(), expected, "{:?}", i);
+        }
+    }
+
+    #[test]
+    fn test_can_format_conf_value() {
+        for (i, o) in [
+            (ConfValue::from(true), Some("1")),
+            (ConfValue::from(false), Some("0")),
+            (ConfValue::from(9050u16), Some("9050")),
+            (ConfValue::from(-1), Some("-1")),
+            (ConfValue::from("auto"), Some("auto")),
+            (ConfValue::from(None::<bool>), None),
+            (ConfValue::from(Some(true)), Some("1")),
+        ].iter() {
+            assert_eq!(i.to_conf_string().as_deref(), *o);
+        }
+    }
+}
@@ -0,0 +1,65 @@
+#[cfg(feature = "v3")]
+use crate::control::primitives::{AddOnionOptions, OnionListener};
+#[cfg(feature = "v3")]
+use crate::onion::TorSecretKeyV3;
+
+/// ConnState is snapshot of state of `AuthenticatedConn`: events it's subscribed to and onion services it has added.
+///
+/// It's created with `AuthenticatedConn::to_state` and applied to new connection with `AuthenticatedConn::restore_state`,
+/// so daemon may persist it(with `serialize` feature) and resume after restart of tor or itself.
+///
+/// # Security note
+/// It contains secret keys of onion services. Store it the way you would store these keys.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct ConnState {
+    /// events_extended is `extended` flag used with last `SETEVENTS`
+    pub events_extended: bool,
+
+    /// events contains names of events, like `CIRC` or `HS_DESC`
+    pub events: Vec<String>,
+
+    /// onion_services contains onion services added by connection, which were not removed with `del_onion`.
+    /// It stays empty unless tracking is enabled with `AuthenticatedConn::set_track_onion_services`.
+    #[cfg(feature = "v3")]
+    pub onion_services: Vec<OnionServiceState>,
+}
+
+/// OnionServiceState is everything required to add onion service v3 once again
+#[cfg(feature = "v3")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct OnionServiceState {
+    pub key: TorSecretKeyV3,
+    pub options: AddOnionOptions,
+    pub listeners: Vec<OnionListener>,
+}
+
+#[cfg(all(test, feature = "serialize", feature = "v3"))]
+mod test {
+    use std::net::SocketAddr;
+
+    use super::*;
+
+    #[test]
+    fn test_can_serialize_and_deserialize_conn_state() {
+        let state = ConnState {
+            events_extended: false,
+            events: vec!["CIRC".to_string(), "HS_DESC".to_string()],
+            onion_services: vec![OnionServiceState {
+                key: TorSecretKeyV3::generate(),
+                options: AddOnionOptions {
+                    detach: true,
+                    ..Default::default()
+                },
+                listeners: vec![
+                    OnionListener::from(80),
+                    OnionListener::from((443, "127.0.0.1:8443".parse::<SocketAddr>().unwrap())),
+                ],
+            }],
+        };
+        let text = serde_json::to_string(&state).unwrap();
+        let restored: ConnState = serde_json::from_str(&text).unwrap();
+        assert_eq!(restored, state);
+    }
+}
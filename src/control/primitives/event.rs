@@ -1,7 +1,7 @@
 use std::borrow::Cow;
 use std::str::FromStr;
 
-// note: torut DOES NOT IMPLEMENTS event parsing right now.
+// note: torut implements parsing for some events only(like `StatusEvent`).
 //  take a look at AsyncEventKind there are so many of them!
 
 /// AsyncEvent is able to contain all info about async event which has been received from
@@ -0,0 +1,166 @@
+use std::convert::TryFrom;
+use std::str::FromStr;
+
+use crate::control::primitives::{AsyncEvent, AsyncEventKind};
+use crate::utils::{is_valid_fingerprint, parse_server_spec};
+
+/// GuardStatus is status of entry guard reported in `GUARD` event
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum GuardStatus {
+    /// New is set when guard was added to list of guards
+    New,
+    /// Up is set when connection to guard has succeeded
+    Up,
+    /// Down is set when connection to guard has failed
+    Down,
+    /// Bad is set when guard became unusable, for instance it's no longer listed in consensus
+    Bad,
+    /// Good is set when guard which was bad became usable again
+    Good,
+    /// Dropped is set when guard was removed from list of guards
+    Dropped,
+}
+
+impl FromStr for GuardStatus {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, ()> {
+        match s {
+            "NEW" => Ok(Self::New),
+            "UP" => Ok(Self::Up),
+            "DOWN" => Ok(Self::Down),
+            "BAD" => Ok(Self::Bad),
+            "GOOD" => Ok(Self::Good),
+            "DROPPED" => Ok(Self::Dropped),
+            _ => Err(()),
+        }
+    }
+}
+
+/// GuardEvent is parsed `GUARD` asynchronous event like:
+/// ```text
+/// 650 GUARD ENTRY $7EA6EAD6FD83083C538F44038BBFA077587DD755~moria1 UP
+/// ```
+///
+/// # TorCP docs
+/// Ctrl+F `4.1.13. Our set of guard nodes has changed`
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct GuardEvent {
+    /// guard_type is type of guard. Tor sends only `ENTRY` for now.
+    pub guard_type: String,
+
+    /// name is name of relay like `$fingerprint~nickname`, `$fingerprint=nickname` or just `$fingerprint`
+    pub name: String,
+
+    pub status: GuardStatus,
+}
+
+impl GuardEvent {
+    /// fingerprint returns fingerprint part of name without leading `$`
+    pub fn fingerprint(&self) -> &str {
+        parse_server_spec(&self.name).0.unwrap_or("")
+    }
+
+    /// nickname returns nickname part of name if there is one
+    pub fn nickname(&self) -> Option<&str> {
+        parse_server_spec(&self.name).1
+    }
+}
+
+impl<'a> TryFrom<&AsyncEvent<'a>> for GuardEvent {
+    type Error = ();
+
+    fn try_from(event: &AsyncEvent<'a>) -> Result<Self, ()> {
+        let line = event.lines.first().ok_or(())?;
+
+        let mut parts = line.split(' ');
+        match parts.next().map(AsyncEventKind::from_str) {
+            Some(Ok(AsyncEventKind::OurSetOfGuardNodesHasChanged)) => {}
+            _ => return Err(()),
+        }
+        let guard_type = parts.next().filter(|t| !t.is_empty()).ok_or(())?;
+        let name = parts.next().ok_or(())?;
+        let status = parts.next().ok_or(())?.parse()?;
+        // tor may add more fields in future, so anything after status is ignored
+
+        match parse_server_spec(name) {
+            (Some(fingerprint), _) if is_valid_fingerprint(fingerprint) => {}
+            _ => return Err(()),
+        }
+
+        Ok(Self {
+            guard_type: guard_type.to_string(),
+            name: name.to_string(),
+            status,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::borrow::Cow;
+
+    use super::*;
+
+    #[test]
+    fn test_can_parse_guard_event() {
+        for (i, o) in [
+            (
+                "GUARD ENTRY $7EA6EAD6FD83083C538F44038BBFA077587DD755~moria1 UP",
+                Some(GuardEvent {
+                    guard_type: "ENTRY".to_string(),
+                    name: "$7EA6EAD6FD83083C538F44038BBFA077587DD755~moria1".to_string(),
+                    status: GuardStatus::Up,
+                })
+            ),
+            (
+                "GUARD ENTRY $7EA6EAD6FD83083C538F44038BBFA077587DD755=moria1 NEW",
+                Some(GuardEvent {
+                    guard_type: "ENTRY".to_string(),
+                    name: "$7EA6EAD6FD83083C538F44038BBFA077587DD755=moria1".to_string(),
+                    status: GuardStatus::New,
+                })
+            ),
+            (
+                "GUARD ENTRY $7EA6EAD6FD83083C538F44038BBFA077587DD755 DROPPED",
+                Some(GuardEvent {
+                    guard_type: "ENTRY".to_string(),
+                    name: "$7EA6EAD6FD83083C538F44038BBFA077587DD755".to_string(),
+                    status: GuardStatus::Dropped,
+                })
+            ),
+            ("GUARD ENTRY $7EA6EAD6FD83083C538F44038BBFA077587DD755~moria1 UNKNOWN", None),
+            ("GUARD ENTRY moria1 UP", None),
+            ("GUARD ENTRY $7EA6EAD6~moria1 UP", None),
+            ("GUARD ENTRY $7EA6EAD6FD83083C538F44038BBFA077587DD755~moria1", None),
+            ("GUARD  $7EA6EAD6FD83083C538F44038BBFA077587DD755~moria1 UP", None),
+            ("CIRC 1 BUILT", None),
+            ("", None),
+        ].iter() {
+            let event = AsyncEvent {
+                code: 650,
+                lines: vec![Cow::Borrowed(*i)],
+            };
+            assert_eq!(GuardEvent::try_from(&event).ok(), *o, "{:?}", i);
+        }
+    }
+
+    #[test]
+    fn test_can_split_guard_name() {
+        for (name, fingerprint, nickname) in [
+            ("$7EA6EAD6FD83083C538F44038BBFA077587DD755~moria1", "7EA6EAD6FD83083C538F44038BBFA077587DD755", Some("moria1")),
+            ("$7EA6EAD6FD83083C538F44038BBFA077587DD755=moria1", "7EA6EAD6FD83083C538F44038BBFA077587DD755", Some("moria1")),
+            ("$7EA6EAD6FD83083C538F44038BBFA077587DD755", "7EA6EAD6FD83083C538F44038BBFA077587DD755", None),
+        ].iter() {
+            let event = GuardEvent {
+                guard_type: "ENTRY".to_string(),
+                name: name.to_string(),
+                status: GuardStatus::Up,
+            };
+            assert_eq!(event.fingerprint(), *fingerprint);
+            assert_eq!(event.nickname(), *nickname);
+        }
+    }
+}
@@ -0,0 +1,172 @@
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::str::FromStr;
+
+use crate::control::primitives::{AsyncEvent, AsyncEventKind};
+use crate::utils::parse_key_value_line;
+
+/// HsDescAction is action reported in `HS_DESC` event
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum HsDescAction {
+    /// Requested is set when descriptor was requested from hidden service directory
+    Requested,
+    /// Upload is set when descriptor upload to hidden service directory was started
+    Upload,
+    /// Received is set when descriptor was fetched successfully
+    Received,
+    /// Uploaded is set when descriptor was uploaded successfully
+    Uploaded,
+    /// Ignore is set when fetched descriptor was ignored
+    Ignore,
+    /// Failed is set when fetching or uploading descriptor has failed. `REASON` argument says why.
+    Failed,
+    /// Created is set when new descriptor of onion service was created
+    Created,
+}
+
+impl FromStr for HsDescAction {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, ()> {
+        match s {
+            "REQUESTED" => Ok(Self::Requested),
+            "UPLOAD" => Ok(Self::Upload),
+            "RECEIVED" => Ok(Self::Received),
+            "UPLOADED" => Ok(Self::Uploaded),
+            "IGNORE" => Ok(Self::Ignore),
+            "FAILED" => Ok(Self::Failed),
+            "CREATED" => Ok(Self::Created),
+            _ => Err(()),
+        }
+    }
+}
+
+/// HsDescEvent is parsed `HS_DESC` asynchronous event like:
+/// ```text
+/// 650 HS_DESC UPLOADED p53lf57qovyuvwsc6xnrppyply3vtqm7l6pcobkmyqsiofyeznfu5uqd UNKNOWN $7EA6EAD6FD83083C538F44038BBFA077587DD755~moria1
+/// ```
+///
+/// # TorCP docs
+/// Ctrl+F `4.1.25. HiddenService descriptors`
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct HsDescEvent {
+    pub action: HsDescAction,
+
+    /// address is onion address without `.onion` suffix. It's `None` when tor sent `UNKNOWN`.
+    pub address: Option<String>,
+
+    /// auth_type is client authorization type like `NO_AUTH` or `UNKNOWN`
+    pub auth_type: String,
+
+    /// hs_dir is hidden service directory like `$fingerprint~nickname`. It's `None` when tor sent `UNKNOWN`.
+    pub hs_dir: Option<String>,
+
+    /// descriptor_id is id of descriptor if tor sent one
+    pub descriptor_id: Option<String>,
+
+    /// args contains keyword arguments like `REASON` or `HSDIR_INDEX`. Quoted values are unquoted.
+    pub args: HashMap<String, String>,
+}
+
+impl<'a> TryFrom<&AsyncEvent<'a>> for HsDescEvent {
+    type Error = ();
+
+    fn try_from(event: &AsyncEvent<'a>) -> Result<Self, ()> {
+        let line = event.lines.first().ok_or(())?;
+
+        let mut parts = line.splitn(6, ' ');
+        match parts.next().map(AsyncEventKind::from_str) {
+            Some(Ok(AsyncEventKind::HiddenServiceDescriptors)) => {}
+            _ => return Err(()),
+        }
+        let action = parts.next().ok_or(())?.parse()?;
+        let address = parts.next().ok_or(())?;
+        let auth_type = parts.next().ok_or(())?;
+        let hs_dir = parts.next().ok_or(())?;
+        let mut rest = parts.next().unwrap_or("");
+
+        // descriptor id is the only positional argument which can follow directory and it never contains `=`
+        let mut descriptor_id = None;
+        let first = rest.split(' ').next().unwrap_or("");
+        if !first.is_empty() && !first.contains('=') {
+            descriptor_id = Some(first.to_string());
+            rest = rest[first.len()..].trim_start_matches(' ');
+        }
+
+        let args = parse_key_value_line(rest).ok_or(())?
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.into_owned()))
+            .collect();
+        let known = |v: &str| if v == "UNKNOWN" { None } else { Some(v.to_string()) };
+        Ok(Self {
+            action,
+            address: known(address),
+            auth_type: auth_type.to_string(),
+            hs_dir: known(hs_dir),
+            descriptor_id,
+            args,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::borrow::Cow;
+
+    use super::*;
+
+    //noinspection SpellCheckingInspection
+    #[test]
+    fn test_can_parse_hs_desc_event() {
+        for (i, o) in [
+            (
+                "HS_DESC UPLOADED p53lf57qovyuvwsc6xnrppyply3vtqm7l6pcobkmyqsiofyeznfu5uqd UNKNOWN $7EA6EAD6FD83083C538F44038BBFA077587DD755~moria1",
+                Some(HsDescEvent {
+                    action: HsDescAction::Uploaded,
+                    address: Some("p53lf57qovyuvwsc6xnrppyply3vtqm7l6pcobkmyqsiofyeznfu5uqd".to_string()),
+                    auth_type: "UNKNOWN".to_string(),
+                    hs_dir: Some("$7EA6EAD6FD83083C538F44038BBFA077587DD755~moria1".to_string()),
+                    descriptor_id: None,
+                    args: HashMap::new(),
+                })
+            ),
+            (
+                "HS_DESC FAILED p53lf57qovyuvwsc6xnrppyply3vtqm7l6pcobkmyqsiofyeznfu5uqd NO_AUTH $7EA6EAD6FD83083C538F44038BBFA077587DD755 b3oeducbhjmbqmgw2i3jtz4fekkrinwj REASON=UPLOAD_REJECTED HSDIR_INDEX=\"abcd\"",
+                Some(HsDescEvent {
+                    action: HsDescAction::Failed,
+                    address: Some("p53lf57qovyuvwsc6xnrppyply3vtqm7l6pcobkmyqsiofyeznfu5uqd".to_string()),
+                    auth_type: "NO_AUTH".to_string(),
+                    hs_dir: Some("$7EA6EAD6FD83083C538F44038BBFA077587DD755".to_string()),
+                    descriptor_id: Some("b3oeducbhjmbqmgw2i3jtz4fekkrinwj".to_string()),
+                    args: [("REASON", "UPLOAD_REJECTED"), ("HSDIR_INDEX", "abcd")].iter()
+                        .map(|(k, v)| (k.to_string(), v.to_string()))
+                        .collect(),
+                })
+            ),
+            (
+                "HS_DESC CREATED UNKNOWN UNKNOWN UNKNOWN",
+                Some(HsDescEvent {
+                    action: HsDescAction::Created,
+                    address: None,
+                    auth_type: "UNKNOWN".to_string(),
+                    hs_dir: None,
+                    descriptor_id: None,
+                    args: HashMap::new(),
+                })
+            ),
+            ("HS_DESC UNKNOWN_ACTION p53lf57qovyuvwsc6xnrppyply3vtqm7l6pcobkmyqsiofyeznfu5uqd NO_AUTH UNKNOWN", None),
+            ("HS_DESC UPLOADED p53lf57qovyuvwsc6xnrppyply3vtqm7l6pcobkmyqsiofyeznfu5uqd NO_AUTH", None),
+            ("HS_DESC FAILED UNKNOWN NO_AUTH UNKNOWN b3oeducbhjmbqmgw2i3jtz4fekkrinwj REASON", None),
+            ("CIRC 5 BUILT", None),
+            ("", None),
+        ].iter() {
+            let event = AsyncEvent {
+                code: 650,
+                lines: vec![Cow::Borrowed(*i)],
+            };
+            assert_eq!(HsDescEvent::try_from(&event).ok(), *o, "{:?}", i);
+        }
+    }
+}
@@ -0,0 +1,110 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+// hacky macro which generates id types, so circuit and stream ids share implementation
+// but can't be mixed with each other
+macro_rules! generate_id {
+    {
+        $(#[$meta:meta])*
+        $typename:ident
+    } => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        #[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+        pub enum $typename {
+            /// Numeric is id which is decimal number. Tor uses these ones right now.
+            Numeric(u64),
+
+            /// Other is id which is not decimal number but it's still valid according to torCP.
+            Other(String),
+        }
+
+        impl From<u64> for $typename {
+            fn from(id: u64) -> Self {
+                Self::Numeric(id)
+            }
+        }
+
+        impl Display for $typename {
+            fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+                match self {
+                    Self::Numeric(id) => write!(f, "{}", id),
+                    Self::Other(id) => write!(f, "{}", id),
+                }
+            }
+        }
+
+        impl FromStr for $typename {
+            type Err = ();
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                if !is_valid_id(s) {
+                    return Err(());
+                }
+                // leading zeros would be lost when formatting number, so keep these as text
+                if s == "0" || !s.starts_with('0') {
+                    if let Ok(id) = s.parse() {
+                        return Ok(Self::Numeric(id));
+                    }
+                }
+                Ok(Self::Other(s.to_string()))
+            }
+        }
+    }
+}
+
+/// is_valid_id checks if given text is valid `CircuitID` or `StreamID` token.
+///
+/// # TorCP docs
+/// ```text
+/// CircuitID = 1*16 IDChar
+/// IDChar = ALPHA / DIGIT
+/// ```
+fn is_valid_id(id: &str) -> bool {
+    !id.is_empty() && id.len() <= 16 && id.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+generate_id! {
+    /// CircuitId identifies circuit in commands and events which refer to circuits.
+    ///
+    /// It's separate type from `StreamId` so one can't be passed where other one is expected.
+    CircuitId
+}
+
+generate_id! {
+    /// StreamId identifies stream in commands and events which refer to streams.
+    ///
+    /// It's separate type from `CircuitId` so one can't be passed where other one is expected.
+    StreamId
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_can_parse_and_format_ids() {
+        for (i, o) in [
+            ("0", Some(CircuitId::Numeric(0))),
+            ("42", Some(CircuitId::Numeric(42))),
+            ("18446744073709551615", None),
+            ("1844674407370955", Some(CircuitId::Numeric(1844674407370955))),
+            ("007", Some(CircuitId::Other("007".to_string()))),
+            ("abc12", Some(CircuitId::Other("abc12".to_string()))),
+            ("", None),
+            ("12 13", None),
+            ("12\r\n", None),
+            ("-1", None),
+            ("aaaaaaaaaaaaaaaaa", None),
+        ].iter().cloned() {
+            let res = CircuitId::from_str(i).ok();
+            assert_eq!(res, o, "{:?}", i);
+            if let Some(res) = res {
+                assert_eq!(res.to_string(), i);
+            }
+        }
+
+        assert_eq!(StreamId::from_str("12"), Ok(StreamId::from(12)));
+        assert_eq!(StreamId::from(12).to_string(), "12");
+    }
+}
@@ -0,0 +1,118 @@
+use std::fmt::{self, Display, Formatter};
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use crate::utils::unquote_string;
+
+/// ListenerAddr is address tor is listening on, as reported by `net/listeners/*` info keys
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum ListenerAddr {
+    /// Tcp listener like `127.0.0.1:9050` or `[::1]:9050`
+    Tcp(SocketAddr),
+
+    /// Unix socket listener like `unix:/run/tor/socks`
+    Unix(PathBuf),
+}
+
+impl FromStr for ListenerAddr {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, ()> {
+        if let Some(path) = s.strip_prefix("unix:") {
+            if path.is_empty() {
+                return Err(());
+            }
+            return Ok(Self::Unix(PathBuf::from(path)));
+        }
+        s.parse().map(Self::Tcp).map_err(|_| ())
+    }
+}
+
+/// Formats address the way tor reports it, so unix socket gets `unix:` prefix.
+impl Display for ListenerAddr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Tcp(addr) => write!(f, "{}", addr),
+            Self::Unix(path) => write!(f, "unix:{}", path.display()),
+        }
+    }
+}
+
+/// parse_listeners parses raw value of `net/listeners/*` info key.
+///
+/// Tor quotes each listener and separates them with spaces like `"127.0.0.1:9050" "unix:/run/tor/socks"`.
+/// Addresses separated with spaces inside single quoted string are accepted as well.
+/// Empty value means that there are no listeners of given kind.
+///
+/// # Returns
+/// `None` is returned if any listener can't be unquoted or parsed.
+pub fn parse_listeners(value: &str) -> Option<Vec<ListenerAddr>> {
+    let mut res = Vec::new();
+    let mut rest = value.trim_start();
+    while !rest.is_empty() {
+        let token = if rest.starts_with('"') {
+            let (offset, text) = unquote_string(rest);
+            let offset = offset?;
+            let text = text.ok()?;
+            rest = &rest[offset + 1..];
+            text
+        } else {
+            let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            let text = &rest[..end];
+            rest = &rest[end..];
+            text.into()
+        };
+        for addr in token.split_whitespace() {
+            res.push(addr.parse().ok()?);
+        }
+        rest = rest.trim_start();
+    }
+    Some(res)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_can_parse_listeners() {
+        for (i, o) in [
+            ("", Some(vec![])),
+            ("\"127.0.0.1:9050\"", Some(vec![ListenerAddr::Tcp("127.0.0.1:9050".parse().unwrap())])),
+            ("127.0.0.1:9050", Some(vec![ListenerAddr::Tcp("127.0.0.1:9050".parse().unwrap())])),
+            (
+                "\"127.0.0.1:9050\" \"[::1]:9050\"",
+                Some(vec![
+                    ListenerAddr::Tcp("127.0.0.1:9050".parse().unwrap()),
+                    ListenerAddr::Tcp("[::1]:9050".parse().unwrap()),
+                ])
+            ),
+            (
+                "\"127.0.0.1:9050 unix:/run/tor/socks\"",
+                Some(vec![
+                    ListenerAddr::Tcp("127.0.0.1:9050".parse().unwrap()),
+                    ListenerAddr::Unix(PathBuf::from("/run/tor/socks")),
+                ])
+            ),
+            (
+                "\"unix:/run/tor/control\"",
+                Some(vec![ListenerAddr::Unix(PathBuf::from("/run/tor/control"))])
+            ),
+            ("\"unix:\"", None),
+            ("\"localhost:9050\"", None),
+            ("\"127.0.0.1:9050", None),
+            ("\"127.0.0.1\"", None),
+        ].iter() {
+            assert_eq!(parse_listeners(i), *o, "{:?}", i);
+        }
+    }
+
+    #[test]
+    fn test_can_display_listener_addr() {
+        for i in ["127.0.0.1:9050", "[::1]:9050", "unix:/run/tor/socks"].iter() {
+            assert_eq!(i.parse::<ListenerAddr>().unwrap().to_string(), *i);
+        }
+    }
+}
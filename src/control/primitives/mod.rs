@@ -1,10 +1,40 @@
+pub use add_onion::*;
 pub use auth::*;
+pub use circuit_status::*;
+#[cfg(feature = "v3")]
+pub use client_auth::*;
+pub use conf::*;
+pub use conn_state::*;
 pub use error::*;
 pub use event::*;
+pub use guard_event::*;
+pub use hs_desc_event::*;
+pub use ids::*;
+pub use listener_addr::*;
+pub use onion_target::*;
+pub use resolve::*;
+pub use router_status::*;
 pub use signal::*;
+pub use status_event::*;
+pub use version_status::*;
 
+mod add_onion;
 mod auth;
+mod circuit_status;
+#[cfg(feature = "v3")]
+mod client_auth;
+mod conf;
+mod conn_state;
 mod error;
 mod signal;
+mod status_event;
 mod event;
+mod guard_event;
+mod hs_desc_event;
+mod ids;
+mod listener_addr;
+mod onion_target;
+mod resolve;
+mod router_status;
+mod version_status;
 
@@ -0,0 +1,116 @@
+use std::fmt::{self, Display, Formatter};
+use std::net::SocketAddr;
+use std::path::PathBuf;
+
+use crate::utils::quote_string;
+
+/// OnionTarget describes where connections to onion service's virtual port are forwarded
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum OnionTarget {
+    /// Tcp forwards connections to given tcp address, like `127.0.0.1:8080` or `[::1]:8080`.
+    /// Tor does not understand ipv6 scope ids(`[fe80::1%2]:8080`), so such addresses are rejected by `ADD_ONION` functions.
+    Tcp(SocketAddr),
+
+    /// Unix forwards connections to unix socket at given path, like `/run/web.sock`
+    Unix(PathBuf),
+}
+
+impl From<SocketAddr> for OnionTarget {
+    fn from(addr: SocketAddr) -> Self {
+        Self::Tcp(addr)
+    }
+}
+
+impl From<PathBuf> for OnionTarget {
+    fn from(path: PathBuf) -> Self {
+        Self::Unix(path)
+    }
+}
+
+/// Formats target in the way `ADD_ONION`'s `Port=` argument expects it.
+///
+/// Unix socket path is always quoted, so it never contains spaces or other chars which could break the command.
+impl Display for OnionTarget {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Tcp(addr) => write!(f, "{}", addr),
+            #[cfg(unix)]
+            Self::Unix(path) => {
+                use std::os::unix::ffi::OsStrExt;
+                write!(f, "unix:{}", quote_string(path.as_os_str().as_bytes()))
+            }
+            #[cfg(not(unix))]
+            Self::Unix(path) => write!(f, "unix:{}", quote_string(path.to_string_lossy().as_bytes())),
+        }
+    }
+}
+
+/// OnionListener is single `Port=` mapping of onion service: virtual port and target it's forwarded to.
+///
+/// It can be created from `(u16, SocketAddr)` tuple, so the old form of listeners still works.
+///
+/// When target is `None` only virtual port is sent(`Port=80`) and tor uses its default target,
+/// which is `127.0.0.1` with the same port as virtual one.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct OnionListener {
+    pub port: u16,
+    pub target: Option<OnionTarget>,
+}
+
+impl From<u16> for OnionListener {
+    fn from(port: u16) -> Self {
+        Self {
+            port,
+            target: None,
+        }
+    }
+}
+
+impl From<(u16, SocketAddr)> for OnionListener {
+    fn from((port, addr): (u16, SocketAddr)) -> Self {
+        Self {
+            port,
+            target: Some(OnionTarget::Tcp(addr)),
+        }
+    }
+}
+
+impl From<(u16, OnionTarget)> for OnionListener {
+    fn from((port, target): (u16, OnionTarget)) -> Self {
+        Self {
+            port,
+            target: Some(target),
+        }
+    }
+}
+
+impl From<(u16, Option<OnionTarget>)> for OnionListener {
+    fn from((port, target): (u16, Option<OnionTarget>)) -> Self {
+        Self {
+            port,
+            target,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::net::Ipv4Addr;
+
+    use super::*;
+
+    #[test]
+    fn test_can_format_onion_target() {
+        for (i, o) in [
+            (OnionTarget::Tcp(SocketAddr::new(Ipv4Addr::new(127, 0, 0, 1).into(), 8080)), "127.0.0.1:8080"),
+            (OnionTarget::Tcp("[::1]:8080".parse().unwrap()), "[::1]:8080"),
+            (OnionTarget::Unix(PathBuf::from("/run/web.sock")), "unix:\"/run/web.sock\""),
+            (OnionTarget::Unix(PathBuf::from("/run/my web\".sock")), "unix:\"/run/my\\40web\\\".sock\""),
+            (OnionTarget::Unix(PathBuf::from("/run/web\r\n.sock")), "unix:\"/run/web\\r\\n.sock\""),
+        ].iter() {
+            assert_eq!(i.to_string(), *o);
+        }
+    }
+}
@@ -0,0 +1,57 @@
+use std::fmt::{self, Display, Formatter};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+/// ResolveTarget is thing which can be resolved with `RESOLVE` command.
+///
+/// Plain `&str` converts into hostname, so `resolve("example.com")` works.
+/// Ip addresses are kept typed, so ipv6 literal is formatted without ambiguity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ResolveTarget<'a> {
+    /// Hostname like `example.com`. It's validated before it's sent to tor.
+    Hostname(&'a str),
+
+    /// Ip address literal
+    Ip(IpAddr),
+}
+
+impl<'a> From<&'a str> for ResolveTarget<'a> {
+    fn from(hostname: &'a str) -> Self {
+        Self::Hostname(hostname)
+    }
+}
+
+impl<'a> From<&'a String> for ResolveTarget<'a> {
+    fn from(hostname: &'a String) -> Self {
+        Self::Hostname(hostname)
+    }
+}
+
+impl From<IpAddr> for ResolveTarget<'_> {
+    fn from(ip: IpAddr) -> Self {
+        Self::Ip(ip)
+    }
+}
+
+impl From<Ipv4Addr> for ResolveTarget<'_> {
+    fn from(ip: Ipv4Addr) -> Self {
+        Self::Ip(IpAddr::V4(ip))
+    }
+}
+
+impl From<Ipv6Addr> for ResolveTarget<'_> {
+    fn from(ip: Ipv6Addr) -> Self {
+        Self::Ip(IpAddr::V6(ip))
+    }
+}
+
+/// Formats target the way it's sent in `RESOLVE` command.
+/// Ipv6 address is put in square brackets like `[::1]`.
+impl Display for ResolveTarget<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Hostname(hostname) => write!(f, "{}", hostname),
+            Self::Ip(IpAddr::V4(ip)) => write!(f, "{}", ip),
+            Self::Ip(IpAddr::V6(ip)) => write!(f, "[{}]", ip),
+        }
+    }
+}
@@ -0,0 +1,291 @@
+use std::convert::TryFrom;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::str::FromStr;
+
+use crate::control::primitives::{AsyncEvent, AsyncEventKind};
+
+/// RouterStatus is single router status entry, as returned by `GETINFO ns/id/<fingerprint>` like:
+/// ```text
+/// r moria1 lpXfw1/+uGEym58asExGOXAgzjE IpcU7dolas8+Q+oAzwgvZIWx7PA 2024-01-01 12:00:00 128.31.0.34 9101 9131
+/// a [2001:db8::1]:9101
+/// s Authority Fast Running Stable V2Dir Valid
+/// w Bandwidth=20
+/// ```
+///
+/// Lines other than `r`, `a`, `s` and `w` are ignored.
+///
+/// # TorCP docs
+/// Ctrl+F `ns/id/<OR identity>`. Format of entry itself is described in dir-spec.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct RouterStatus {
+    pub nickname: String,
+
+    /// identity is base64 encoded(without padding) identity digest of router, as it's in `r` line
+    pub identity: String,
+
+    /// digest is base64 encoded digest of router's descriptor.
+    /// It's `None` when entry comes from microdescriptor consensus, which does not contain it.
+    pub digest: Option<String>,
+
+    /// publication is publication time of router's descriptor in `YYYY-MM-DD HH:MM:SS` format
+    pub publication: String,
+
+    pub address: Ipv4Addr,
+    pub or_port: u16,
+
+    /// dir_port is zero when router has no directory port
+    pub dir_port: u16,
+
+    /// or_addresses contains additional OR addresses from `a` lines, usually ipv6 ones
+    pub or_addresses: Vec<SocketAddr>,
+
+    /// flags like `Running`, `Stable` or `Guard` from `s` line
+    pub flags: Vec<String>,
+
+    /// bandwidth is `Bandwidth` value from `w` line in kilobytes per second
+    pub bandwidth: Option<u64>,
+}
+
+impl RouterStatus {
+    /// has_flag returns true if router has given flag, like `Running` or `Exit`
+    pub fn has_flag(&self, flag: &str) -> bool {
+        self.flags.iter().any(|f| f == flag)
+    }
+}
+
+impl FromStr for RouterStatus {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, ()> {
+        let mut lines = s.lines()
+            .map(|l| l.trim())
+            .filter(|l| !l.is_empty());
+
+        let r_line = lines.next().ok_or(())?;
+        let parts = r_line.split(' ').collect::<Vec<_>>();
+        // r nickname identity [digest] date time address or_port dir_port
+        let (nickname, identity, digest, rest) = match parts.as_slice() {
+            ["r", nickname, identity, digest, rest @ ..] if rest.len() == 5 => (nickname, identity, Some(digest), rest),
+            ["r", nickname, identity, rest @ ..] if rest.len() == 5 => (nickname, identity, None, rest),
+            _ => return Err(()),
+        };
+        if nickname.is_empty() || identity.is_empty() || digest.is_some_and(|d| d.is_empty()) {
+            return Err(());
+        }
+
+        let mut res = RouterStatus {
+            nickname: nickname.to_string(),
+            identity: identity.to_string(),
+            digest: digest.map(|d| d.to_string()),
+            publication: format!("{} {}", rest[0], rest[1]),
+            address: rest[2].parse().map_err(|_| ())?,
+            or_port: rest[3].parse().map_err(|_| ())?,
+            dir_port: rest[4].parse().map_err(|_| ())?,
+            or_addresses: Vec::new(),
+            flags: Vec::new(),
+            bandwidth: None,
+        };
+
+        for line in lines {
+            let mut parts = line.splitn(2, ' ');
+            let kind = parts.next().unwrap_or("");
+            let value = parts.next().unwrap_or("");
+            match kind {
+                // second `r` line means there is more than one entry
+                "r" => return Err(()),
+                "a" => res.or_addresses.push(value.parse().map_err(|_| ())?),
+                "s" => res.flags = value.split(' ')
+                    .filter(|f| !f.is_empty())
+                    .map(|f| f.to_string())
+                    .collect(),
+                "w" => {
+                    for arg in value.split(' ') {
+                        if let Some(bw) = arg.strip_prefix("Bandwidth=") {
+                            res.bandwidth = Some(bw.parse().map_err(|_| ())?);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(res)
+    }
+}
+
+/// parse_router_status_entries parses text containing many router status entries one after another,
+/// like value of `ns/all` info key or body of `NS` event.
+/// Each entry starts with `r` line and it's parsed with `RouterStatus::from_str`.
+///
+/// `None` is returned if any entry is not valid.
+pub fn parse_router_status_entries(text: &str) -> Option<Vec<RouterStatus>> {
+    let mut res = Vec::new();
+    let mut entry = String::new();
+    for line in text.lines().map(|l| l.trim()).filter(|l| !l.is_empty()) {
+        if line.starts_with("r ") && !entry.is_empty() {
+            res.push(entry.parse().ok()?);
+            entry.clear();
+        }
+        entry.push_str(line);
+        entry.push('\n');
+    }
+    if !entry.is_empty() {
+        res.push(entry.parse().ok()?);
+    }
+    Some(res)
+}
+
+/// NetworkStatusEvent is parsed `NS` or `NEWCONSENSUS` asynchronous event like:
+/// ```text
+/// 650+NS
+/// r moria1 lpXfw1/+uGEym58asExGOXAgzjE IpcU7dolas8+Q+oAzwgvZIWx7PA 2024-01-01 12:00:00 128.31.0.34 9101 9131
+/// s Authority Fast Running Stable V2Dir Valid
+/// .
+/// 650 OK
+/// ```
+///
+/// # TorCP docs
+/// Ctrl+F `4.1.12. Network status changed` and `4.1.17. New consensus networkstatus has arrived`
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct NetworkStatusEvent {
+    /// kind is either `NetworkStatusHasChanged` or `NewConsensusNetworkStatusHasArrived`
+    pub kind: AsyncEventKind,
+
+    /// entries contains router status entries in order they were sent
+    pub entries: Vec<RouterStatus>,
+}
+
+impl<'a> TryFrom<&AsyncEvent<'a>> for NetworkStatusEvent {
+    type Error = ();
+
+    fn try_from(event: &AsyncEvent<'a>) -> Result<Self, ()> {
+        // whole multiline body is put into first line, right after event name
+        let line = event.lines.first().ok_or(())?;
+        let (name, body) = match line.find(['\r', '\n']) {
+            Some(idx) => (&line[..idx], &line[idx..]),
+            None => (line.as_ref(), ""),
+        };
+        let kind = match AsyncEventKind::from_str(name) {
+            Ok(kind @ AsyncEventKind::NetworkStatusHasChanged)
+            | Ok(kind @ AsyncEventKind::NewConsensusNetworkStatusHasArrived) => kind,
+            _ => return Err(()),
+        };
+        Ok(Self {
+            kind,
+            entries: parse_router_status_entries(body).ok_or(())?,
+        })
+    }
+}
+
+/// DescSelector selects relay which descriptor should be returned by `get_descriptor`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum DescSelector {
+    /// ByFingerprint selects relay by its fingerprint, optionally prefixed with `$`(`desc/id/<fingerprint>` info key)
+    ByFingerprint(String),
+
+    /// ByNickname selects relay by its nickname(`desc/name/<nickname>` info key)
+    ByNickname(String),
+}
+
+#[cfg(test)]
+mod test {
+    use std::borrow::Cow;
+
+    use super::*;
+
+    //noinspection SpellCheckingInspection
+    #[test]
+    fn test_can_parse_router_status() {
+        for (i, o) in [
+            (
+                concat!(
+                    "r moria1 lpXfw1/+uGEym58asExGOXAgzjE IpcU7dolas8+Q+oAzwgvZIWx7PA 2024-01-01 12:00:00 128.31.0.34 9101 9131\r\n",
+                    "a [2001:db8::1]:9101\r\n",
+                    "s Authority Fast Running Stable V2Dir Valid\r\n",
+                    "w Bandwidth=20 Unmeasured=1\r\n",
+                    "p reject 1-65535",
+                ),
+                Some(RouterStatus {
+                    nickname: "moria1".to_string(),
+                    identity: "lpXfw1/+uGEym58asExGOXAgzjE".to_string(),
+                    digest: Some("IpcU7dolas8+Q+oAzwgvZIWx7PA".to_string()),
+                    publication: "2024-01-01 12:00:00".to_string(),
+                    address: Ipv4Addr::new(128, 31, 0, 34),
+                    or_port: 9101,
+                    dir_port: 9131,
+                    or_addresses: vec!["[2001:db8::1]:9101".parse().unwrap()],
+                    flags: ["Authority", "Fast", "Running", "Stable", "V2Dir", "Valid"].iter().map(|f| f.to_string()).collect(),
+                    bandwidth: Some(20),
+                })
+            ),
+            (
+                "\r\nr moria1 lpXfw1/+uGEym58asExGOXAgzjE 2024-01-01 12:00:00 128.31.0.34 9101 0\r\ns Running\r\nm abc",
+                Some(RouterStatus {
+                    nickname: "moria1".to_string(),
+                    identity: "lpXfw1/+uGEym58asExGOXAgzjE".to_string(),
+                    digest: None,
+                    publication: "2024-01-01 12:00:00".to_string(),
+                    address: Ipv4Addr::new(128, 31, 0, 34),
+                    or_port: 9101,
+                    dir_port: 0,
+                    or_addresses: vec![],
+                    flags: vec!["Running".to_string()],
+                    bandwidth: None,
+                })
+            ),
+            ("", None),
+            ("s Running", None),
+            ("r moria1 lpXfw1/+uGEym58asExGOXAgzjE 2024-01-01 12:00:00 128.31.0.34 9101", None),
+            ("r moria1 lpXfw1/+uGEym58asExGOXAgzjE 2024-01-01 12:00:00 128.31.0.340 9101 0", None),
+            ("r moria1 lpXfw1/+uGEym58asExGOXAgzjE 2024-01-01 12:00:00 128.31.0.34 91010 0", None),
+            ("r moria1 lpXfw1/+uGEym58asExGOXAgzjE 2024-01-01 12:00:00 128.31.0.34 9101 0\na 2001:db8::1", None),
+            ("r moria1 lpXfw1/+uGEym58asExGOXAgzjE 2024-01-01 12:00:00 128.31.0.34 9101 0\nw Bandwidth=x", None),
+            (
+                "r moria1 lpXfw1/+uGEym58asExGOXAgzjE 2024-01-01 12:00:00 128.31.0.34 9101 0\nr moria2 lpXfw1/+uGEym58asExGOXAgzjE 2024-01-01 12:00:00 128.31.0.34 9101 0",
+                None
+            ),
+        ].iter() {
+            assert_eq!(RouterStatus::from_str(i).ok(), *o, "{:?}", i);
+        }
+    }
+
+    //noinspection SpellCheckingInspection
+    #[test]
+    fn test_can_parse_network_status_event() {
+        let moria = "r moria1 lpXfw1/+uGEym58asExGOXAgzjE IpcU7dolas8+Q+oAzwgvZIWx7PA 2024-01-01 12:00:00 128.31.0.34 9101 9131";
+        let other = "r other lpXfw1/+uGEym58asExGOXAgzjE 2024-01-01 12:00:00 1.2.3.4 443 0";
+        for (i, o) in [
+            (
+                format!("NS\r\n{}\r\ns Running Valid\r\nw Bandwidth=20\r\n{}\r\ns Running", moria, other),
+                Some((AsyncEventKind::NetworkStatusHasChanged, 2)),
+            ),
+            (
+                format!("NEWCONSENSUS\r\n{}\r\n", moria),
+                Some((AsyncEventKind::NewConsensusNetworkStatusHasArrived, 1)),
+            ),
+            ("NS".to_string(), Some((AsyncEventKind::NetworkStatusHasChanged, 0))),
+            (format!("NS\r\ns Running\r\n{}", moria), None),
+            (format!("NS\r\n{}\r\nw Bandwidth=x", moria), None),
+            (format!("CIRC\r\n{}", moria), None),
+            ("".to_string(), None),
+        ].iter() {
+            let event = AsyncEvent {
+                code: 650,
+                lines: vec![Cow::Borrowed(i.as_str()), Cow::Borrowed("OK")],
+            };
+            let res = NetworkStatusEvent::try_from(&event).ok();
+            assert_eq!(res.as_ref().map(|e| (e.kind, e.entries.len())), *o, "{:?}", i);
+        }
+
+        let event = AsyncEvent {
+            code: 650,
+            lines: vec![Cow::Owned(format!("NS\r\n{}\r\ns Running Valid\r\n{}\r\ns Running", moria, other))],
+        };
+        let event = NetworkStatusEvent::try_from(&event).unwrap();
+        assert_eq!(event.entries[0], RouterStatus::from_str(&format!("{}\ns Running Valid", moria)).unwrap());
+        assert_eq!(event.entries[1].nickname, "other");
+        assert_eq!(event.entries[1].flags, ["Running"]);
+    }
+}
@@ -0,0 +1,270 @@
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::str::FromStr;
+
+use crate::control::primitives::{AsyncEvent, AsyncEventKind};
+use crate::utils::parse_key_value_line;
+
+/// StatusSeverity is severity of status event
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum StatusSeverity {
+    Notice,
+    Warn,
+    Err,
+}
+
+impl FromStr for StatusSeverity {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, ()> {
+        match s {
+            "NOTICE" => Ok(Self::Notice),
+            "WARN" => Ok(Self::Warn),
+            "ERR" => Ok(Self::Err),
+            _ => Err(()),
+        }
+    }
+}
+
+/// StatusEvent is parsed `STATUS_GENERAL`, `STATUS_CLIENT` or `STATUS_SERVER` asynchronous event like:
+/// ```text
+/// 650 STATUS_CLIENT NOTICE BOOTSTRAP PROGRESS=100 TAG=done SUMMARY="Done"
+/// ```
+///
+/// # TorCP docs
+/// Ctrl+F `4.1.10. Status events`
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct StatusEvent {
+    /// kind is one of `StatusGeneral`, `StatusClient` or `StatusServer`
+    pub kind: AsyncEventKind,
+
+    pub severity: StatusSeverity,
+
+    /// action is what happened, like `CIRCUIT_ESTABLISHED`, `BOOTSTRAP` or `DANGEROUS_PORT`
+    pub action: String,
+
+    /// args contains arguments of action. Quoted values are unquoted.
+    pub args: HashMap<String, String>,
+}
+
+impl<'a> TryFrom<&AsyncEvent<'a>> for StatusEvent {
+    type Error = ();
+
+    fn try_from(event: &AsyncEvent<'a>) -> Result<Self, ()> {
+        let line = event.lines.first().ok_or(())?;
+
+        let mut parts = line.splitn(4, ' ');
+        let kind = match parts.next().map(AsyncEventKind::from_str) {
+            Some(Ok(kind @ AsyncEventKind::StatusGeneral))
+            | Some(Ok(kind @ AsyncEventKind::StatusClient))
+            | Some(Ok(kind @ AsyncEventKind::StatusServer)) => kind,
+            _ => return Err(()),
+        };
+        let severity = parts.next().ok_or(())?.parse()?;
+        let action = parts.next().ok_or(())?;
+        if action.is_empty() {
+            return Err(());
+        }
+        let args = parse_key_value_line(parts.next().unwrap_or("")).ok_or(())?
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.into_owned()))
+            .collect();
+
+        Ok(Self {
+            kind,
+            severity,
+            action: action.to_string(),
+            args,
+        })
+    }
+}
+
+/// BootstrapPhase describes how far tor is with bootstrapping.
+///
+/// It's returned by `GETINFO status/bootstrap-phase` and it's carried by `BOOTSTRAP` status event as well:
+/// ```text
+/// NOTICE BOOTSTRAP PROGRESS=75 TAG=enough_dirinfo SUMMARY="Loaded enough directory info to build circuits"
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct BootstrapPhase {
+    /// progress is percent of bootstrapping done, from 0 to 100
+    pub progress: u8,
+
+    /// tag is short machine readable name of phase, like `enough_dirinfo` or `done`
+    pub tag: String,
+
+    /// summary is human readable description of phase
+    pub summary: String,
+}
+
+impl BootstrapPhase {
+    /// is_done returns true if tor has finished bootstrapping
+    pub fn is_done(&self) -> bool {
+        self.progress >= 100
+    }
+}
+
+impl<'a> TryFrom<&'a StatusEvent> for BootstrapPhase {
+    type Error = ();
+
+    fn try_from(event: &'a StatusEvent) -> Result<Self, ()> {
+        if event.action != "BOOTSTRAP" {
+            return Err(());
+        }
+        let progress = event.args.get("PROGRESS")
+            .and_then(|p| p.parse().ok())
+            .filter(|p| *p <= 100)
+            .ok_or(())?;
+        Ok(Self {
+            progress,
+            tag: event.args.get("TAG").ok_or(())?.clone(),
+            summary: event.args.get("SUMMARY").ok_or(())?.clone(),
+        })
+    }
+}
+
+impl FromStr for BootstrapPhase {
+    type Err = ();
+
+    /// from_str parses value of `status/bootstrap-phase` info key, which is `BOOTSTRAP` status event without event name
+    fn from_str(s: &str) -> Result<Self, ()> {
+        let mut parts = s.splitn(3, ' ');
+        let severity = parts.next().ok_or(())?.parse()?;
+        let action = parts.next().ok_or(())?;
+        let args = parse_key_value_line(parts.next().unwrap_or("")).ok_or(())?
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.into_owned()))
+            .collect();
+        Self::try_from(&StatusEvent {
+            kind: AsyncEventKind::StatusClient,
+            severity,
+            action: action.to_string(),
+            args,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::borrow::Cow;
+
+    use super::*;
+
+    #[test]
+    fn test_can_parse_status_event() {
+        for (i, o) in [
+            (
+                "STATUS_CLIENT NOTICE CIRCUIT_ESTABLISHED",
+                Some(StatusEvent {
+                    kind: AsyncEventKind::StatusClient,
+                    severity: StatusSeverity::Notice,
+                    action: "CIRCUIT_ESTABLISHED".to_string(),
+                    args: HashMap::new(),
+                })
+            ),
+            (
+                "STATUS_CLIENT NOTICE BOOTSTRAP PROGRESS=100 TAG=done SUMMARY=\"Done\"",
+                Some(StatusEvent {
+                    kind: AsyncEventKind::StatusClient,
+                    severity: StatusSeverity::Notice,
+                    action: "BOOTSTRAP".to_string(),
+                    args: [("PROGRESS", "100"), ("TAG", "done"), ("SUMMARY", "Done")].iter()
+                        .map(|(k, v)| (k.to_string(), v.to_string()))
+                        .collect(),
+                })
+            ),
+            (
+                "STATUS_CLIENT WARN DANGEROUS_PORT PORT=23 RESULT=REJECT",
+                Some(StatusEvent {
+                    kind: AsyncEventKind::StatusClient,
+                    severity: StatusSeverity::Warn,
+                    action: "DANGEROUS_PORT".to_string(),
+                    args: [("PORT", "23"), ("RESULT", "REJECT")].iter()
+                        .map(|(k, v)| (k.to_string(), v.to_string()))
+                        .collect(),
+                })
+            ),
+            (
+                "STATUS_GENERAL ERR CLOCK_JUMPED TIME=120",
+                Some(StatusEvent {
+                    kind: AsyncEventKind::StatusGeneral,
+                    severity: StatusSeverity::Err,
+                    action: "CLOCK_JUMPED".to_string(),
+                    args: [("TIME", "120")].iter()
+                        .map(|(k, v)| (k.to_string(), v.to_string()))
+                        .collect(),
+                })
+            ),
+            (
+                "STATUS_SERVER NOTICE EXTERNAL_ADDRESS ADDRESS=1.2.3.4 METHOD=DIRSERV",
+                Some(StatusEvent {
+                    kind: AsyncEventKind::StatusServer,
+                    severity: StatusSeverity::Notice,
+                    action: "EXTERNAL_ADDRESS".to_string(),
+                    args: [("ADDRESS", "1.2.3.4"), ("METHOD", "DIRSERV")].iter()
+                        .map(|(k, v)| (k.to_string(), v.to_string()))
+                        .collect(),
+                })
+            ),
+            ("STATUS_CLIENT INFO CIRCUIT_ESTABLISHED", None),
+            ("STATUS_CLIENT NOTICE", None),
+            ("STATUS_CLIENT NOTICE BOOTSTRAP PROGRESS", None),
+            ("CIRC 1 BUILT", None),
+            ("", None),
+        ].iter() {
+            let event = AsyncEvent {
+                code: 650,
+                lines: vec![Cow::Borrowed(*i)],
+            };
+            assert_eq!(StatusEvent::try_from(&event).ok(), *o, "{:?}", i);
+        }
+    }
+
+    #[test]
+    fn test_can_parse_bootstrap_phase() {
+        for (i, o) in [
+            (
+                "NOTICE BOOTSTRAP PROGRESS=75 TAG=enough_dirinfo SUMMARY=\"Loaded enough directory info to build circuits\"",
+                Some(BootstrapPhase {
+                    progress: 75,
+                    tag: "enough_dirinfo".to_string(),
+                    summary: "Loaded enough directory info to build circuits".to_string(),
+                })
+            ),
+            (
+                "NOTICE BOOTSTRAP PROGRESS=100 TAG=done SUMMARY=\"Done\"",
+                Some(BootstrapPhase {
+                    progress: 100,
+                    tag: "done".to_string(),
+                    summary: "Done".to_string(),
+                })
+            ),
+            (
+                "WARN BOOTSTRAP PROGRESS=5 TAG=conn SUMMARY=\"Connecting to a relay\" WARNING=\"Connection refused\" REASON=CONNECTREFUSED COUNT=1 RECOMMENDATION=ignore",
+                Some(BootstrapPhase {
+                    progress: 5,
+                    tag: "conn".to_string(),
+                    summary: "Connecting to a relay".to_string(),
+                })
+            ),
+            ("NOTICE BOOTSTRAP PROGRESS=101 TAG=done SUMMARY=\"Done\"", None),
+            ("NOTICE BOOTSTRAP PROGRESS=abc TAG=done SUMMARY=\"Done\"", None),
+            ("NOTICE BOOTSTRAP TAG=done SUMMARY=\"Done\"", None),
+            ("NOTICE CIRCUIT_ESTABLISHED", None),
+            ("BOOTSTRAP PROGRESS=100 TAG=done SUMMARY=\"Done\"", None),
+            ("", None),
+        ].iter() {
+            assert_eq!(BootstrapPhase::from_str(i).ok(), *o, "{:?}", i);
+        }
+
+        let event = AsyncEvent {
+            code: 650,
+            lines: vec![Cow::Borrowed("STATUS_CLIENT NOTICE BOOTSTRAP PROGRESS=100 TAG=done SUMMARY=\"Done\"")],
+        };
+        let phase = BootstrapPhase::try_from(&StatusEvent::try_from(&event).unwrap()).unwrap();
+        assert!(phase.is_done());
+    }
+}
@@ -0,0 +1,68 @@
+/// VersionStatus tells what tor thinks about its own version compared to versions recommended by directory authorities.
+/// It's value of `status/version/current` info key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum VersionStatus {
+    /// New means that version is newer than any recommended one
+    New,
+    /// NewInSeries means that version is newer than recommended ones in its series
+    NewInSeries,
+    /// Old means that version is older than recommended ones
+    Old,
+    /// Obsolete means that version is old and no longer recommended
+    Obsolete,
+    /// Unrecommended means that version is not recommended, even though it's not old
+    Unrecommended,
+    /// Recommended means that version is one of recommended ones
+    Recommended,
+    /// Unknown is returned when tor does not know yet(for instance there is no consensus)
+    /// or when it reports value which is not known to torut
+    Unknown,
+}
+
+/// Parses value of `status/version/current` info key. Values not known to torut become `VersionStatus::Unknown`,
+/// so new values added in future tor versions do not break parsing.
+impl From<&str> for VersionStatus {
+    fn from(s: &str) -> Self {
+        match s {
+            "new" => Self::New,
+            "new in series" => Self::NewInSeries,
+            "old" => Self::Old,
+            "obsolete" => Self::Obsolete,
+            "unrecommended" => Self::Unrecommended,
+            "recommended" => Self::Recommended,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+impl VersionStatus {
+    /// is_outdated returns true if user should be warned that version of tor in use is outdated
+    pub fn is_outdated(&self) -> bool {
+        matches!(self, Self::Old | Self::Obsolete | Self::Unrecommended)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_can_parse_version_status() {
+        for (i, o) in [
+            ("new", VersionStatus::New),
+            ("new in series", VersionStatus::NewInSeries),
+            ("old", VersionStatus::Old),
+            ("obsolete", VersionStatus::Obsolete),
+            ("unrecommended", VersionStatus::Unrecommended),
+            ("recommended", VersionStatus::Recommended),
+            ("unknown", VersionStatus::Unknown),
+            ("something new", VersionStatus::Unknown),
+            ("", VersionStatus::Unknown),
+        ].iter() {
+            assert_eq!(VersionStatus::from(*i), *o, "{:?}", i);
+        }
+        assert!(VersionStatus::Obsolete.is_outdated());
+        assert!(!VersionStatus::Recommended.is_outdated());
+    }
+}
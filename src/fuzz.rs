@@ -1,7 +1,7 @@
 use std::io::Cursor;
 use std::str::FromStr;
 
-use crate::control::conn::{Conn, UnauthenticatedConn};
+use crate::control::{Conn, make_set_conf_call, UnauthenticatedConn};
 
 #[cfg(feature = "v3")]
 use crate::onion::OnionAddressV3;
@@ -35,7 +35,7 @@ pub fn fuzz_conn_parse_response(data: &[u8]) {
         let mut s = Cursor::new(data);
         let mut c = Conn::new(s);
         if let Ok((code, data)) = c.receive_data().await {
-            assert!(code >= 0 && code <= 999);
+            assert!(code >= 200 && code <= 699);
         }
     });
 }
@@ -52,6 +52,31 @@ pub fn fuzz_unauthenticated_conn_parse_protocol_info(data: &[u8]) {
     });
 }
 
+#[cfg(feature = "control")]
+/// fuzz_setconf_line checks that no key or value is able to inject another command into `SETCONF` line.
+/// Data is split into key and value at first zero byte.
+pub fn fuzz_setconf_line(data: &[u8]) {
+    let (key, value) = match data.iter().position(|b| *b == 0) {
+        Some(i) => (&data[..i], Some(&data[i + 1..])),
+        None => (data, None),
+    };
+    let key = match std::str::from_utf8(key) {
+        Ok(key) => key,
+        Err(_) => return,
+    };
+    let value = match value.map(std::str::from_utf8) {
+        Some(Err(_)) => return,
+        Some(Ok(value)) => Some(value),
+        None => None,
+    };
+    if let Ok(Some(line)) = make_set_conf_call(&mut std::iter::once((key, value))) {
+        assert!(line.ends_with("\r\n"));
+        let line = &line[..line.len() - 2];
+        assert!(!line.contains('\r'));
+        assert!(!line.contains('\n'));
+    }
+}
+
 #[cfg(feature = "v3")]
 pub fn fuzz_deserialize_onion_address_v3_from_text(data: &[u8]) {
     if let Ok(data) = std::str::from_utf8(data) {
@@ -12,7 +12,8 @@
 #[macro_use]
 extern crate derive_more;
 #[cfg(feature = "serialize")]
-#[macro_use]
+// serializable types are defined only by code behind these features
+#[cfg_attr(any(feature = "control", feature = "v3"), macro_use)]
 extern crate serde_derive;
 
 pub mod onion;
@@ -0,0 +1,139 @@
+use curve25519_dalek::constants::X25519_BASEPOINT;
+use curve25519_dalek::scalar::Scalar;
+use rand::RngCore;
+use rand::thread_rng;
+use subtle::ConstantTimeEq;
+
+use crate::utils::BASE32_ALPHA;
+
+/// TORV3_CLIENT_AUTH_KEY_LENGTH is length of x25519 keys used for client authorization, which is 32 bytes
+pub const TORV3_CLIENT_AUTH_KEY_LENGTH: usize = 32;
+
+/// TorClientAuthKey is x25519 private key used for client authorization of onion services v3.
+///
+/// Its public half is given to onion service(`ClientAuthV3=` argument of `ADD_ONION`)
+/// and private one is given to tor which connects to the service(`ONION_CLIENT_AUTH_ADD`).
+///
+/// Bytes of key are clamped the way x25519 requires it, so keys created from bytes which differ only
+/// in clamped bits are equal.
+#[derive(Clone)]
+pub struct TorClientAuthKey([u8; TORV3_CLIENT_AUTH_KEY_LENGTH]);
+
+impl Eq for TorClientAuthKey {}
+
+/// Comparison runs in constant time, the same way as for `TorSecretKeyV3`.
+impl PartialEq for TorClientAuthKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.0[..].ct_eq(&other.0[..]).into()
+    }
+}
+
+impl TorClientAuthKey {
+    /// generate generates new random `TorClientAuthKey`
+    pub fn generate() -> Self {
+        let mut bytes = [0u8; TORV3_CLIENT_AUTH_KEY_LENGTH];
+        thread_rng().fill_bytes(&mut bytes[..]);
+        Self::from_bytes(bytes)
+    }
+
+    /// from_bytes creates `TorClientAuthKey` from raw x25519 private key. Bytes are clamped.
+    pub fn from_bytes(mut bytes: [u8; TORV3_CLIENT_AUTH_KEY_LENGTH]) -> Self {
+        bytes[0] &= 248;
+        bytes[31] &= 127;
+        bytes[31] |= 64;
+        Self(bytes)
+    }
+
+    pub fn as_bytes(&self) -> [u8; TORV3_CLIENT_AUTH_KEY_LENGTH] {
+        self.0
+    }
+
+    /// public_key returns raw x25519 public key of this private key
+    pub fn public_key(&self) -> [u8; TORV3_CLIENT_AUTH_KEY_LENGTH] {
+        (X25519_BASEPOINT * Scalar::from_bits(self.0)).to_bytes()
+    }
+
+    /// public_key_base32 returns public key encoded in base32(without padding), which is the form
+    /// `ClientAuthV3=` argument of `ADD_ONION` expects.
+    pub fn public_key_base32(&self) -> String {
+        base32::encode(BASE32_ALPHA, &self.public_key()[..])
+    }
+
+    /// private_key_base64 returns private key encoded in base64, which is the form `ONION_CLIENT_AUTH_ADD`
+    /// expects after `x25519:` prefix.
+    pub fn private_key_base64(&self) -> String {
+        base64::encode(&self.0[..])
+    }
+
+    /// from_private_key_base64 parses key encoded with `private_key_base64`, for instance
+    /// one returned by `ONION_CLIENT_AUTH_VIEW` in `ClientAuthEntry::key_blob`.
+    ///
+    /// `None` is returned if text is not valid base64 or decoded key has invalid length.
+    pub fn from_private_key_base64(text: &str) -> Option<Self> {
+        let raw = base64::decode(text).ok()?;
+        if raw.len() != TORV3_CLIENT_AUTH_KEY_LENGTH {
+            return None;
+        }
+        let mut bytes = [0u8; TORV3_CLIENT_AUTH_KEY_LENGTH];
+        bytes.copy_from_slice(&raw[..]);
+        Some(Self::from_bytes(bytes))
+    }
+
+    /// public_key_from_base32 decodes public key encoded with `public_key_base32`.
+    ///
+    /// `None` is returned if text is not valid base32 or decoded key has invalid length.
+    pub fn public_key_from_base32(text: &str) -> Option<[u8; TORV3_CLIENT_AUTH_KEY_LENGTH]> {
+        let raw = base32::decode(BASE32_ALPHA, text)?;
+        if raw.len() != TORV3_CLIENT_AUTH_KEY_LENGTH {
+            return None;
+        }
+        let mut bytes = [0u8; TORV3_CLIENT_AUTH_KEY_LENGTH];
+        bytes.copy_from_slice(&raw[..]);
+        Some(bytes)
+    }
+}
+
+impl std::fmt::Debug for TorClientAuthKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        write!(f, "TorClientAuthKey(****)")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_can_encode_and_decode_client_auth_key() {
+        for _ in 0..8 {
+            let key = TorClientAuthKey::generate();
+
+            let private = key.private_key_base64();
+            assert_eq!(private.len(), 44);
+            assert_eq!(TorClientAuthKey::from_private_key_base64(&private), Some(key.clone()));
+
+            let public = key.public_key_base32();
+            assert_eq!(public.len(), 52);
+            assert_eq!(TorClientAuthKey::public_key_from_base32(&public), Some(key.public_key()));
+            assert_eq!(TorClientAuthKey::public_key_from_base32(&public.to_ascii_lowercase()), Some(key.public_key()));
+        }
+
+        assert_eq!(TorClientAuthKey::from_private_key_base64("not base64"), None);
+        assert_eq!(TorClientAuthKey::from_private_key_base64("AAAA"), None);
+        assert_eq!(TorClientAuthKey::public_key_from_base32("1"), None);
+        assert_eq!(TorClientAuthKey::public_key_from_base32("AAAA"), None);
+    }
+
+    #[test]
+    fn test_can_derive_client_auth_public_key() {
+        // test vector from RFC 7748, section 6.1(alice's keys)
+        let key = TorClientAuthKey::from_bytes([
+            0x77, 0x07, 0x6d, 0x0a, 0x73, 0x18, 0xa5, 0x7d, 0x3c, 0x16, 0xc1, 0x72, 0x51, 0xb2, 0x66, 0x45,
+            0xdf, 0x4c, 0x2f, 0x87, 0xeb, 0xc0, 0x99, 0x2a, 0xb1, 0x77, 0xfb, 0xa5, 0x1d, 0xb9, 0x2c, 0x2a,
+        ]);
+        assert_eq!(key.public_key(), [
+            0x85, 0x20, 0xf0, 0x09, 0x89, 0x30, 0xa7, 0x54, 0x74, 0x8b, 0x7d, 0xdc, 0xb4, 0x3e, 0xf7, 0x5a,
+            0x0d, 0xbf, 0x3a, 0x0d, 0x26, 0x38, 0x1a, 0xf4, 0xeb, 0xa4, 0xa9, 0x8e, 0xaa, 0x9b, 0x4e, 0x6a,
+        ]);
+    }
+}
@@ -1,5 +1,7 @@
+use curve25519_dalek::edwards::CompressedEdwardsY;
 use ed25519_dalek::{ExpandedSecretKey, PublicKey, SecretKey, SignatureError};
 use rand::thread_rng;
+use subtle::ConstantTimeEq;
 
 use crate::onion::OnionAddressV3;
 use crate::utils::BASE32_ALPHA;
@@ -12,6 +14,10 @@ pub const TORV3_PUBLIC_KEY_LENGTH: usize = ed25519_dalek::PUBLIC_KEY_LENGTH;
 /// (equal to Ed25519 extended secret key length)
 pub const TORV3_SECRET_KEY_LENGTH: usize = ed25519_dalek::EXPANDED_SECRET_KEY_LENGTH;
 
+/// Header of `hs_ed25519_secret_key` file written by tor into `HiddenServiceDir`.
+/// It's followed by 64 bytes of expanded secret key.
+pub const TORV3_SECRET_KEY_FILE_HEADER: &[u8; 32] = b"== ed25519v1-secret: type0 ==\0\0\0";
+
 /// TorPublicKeyV3 describes onion service's public key(use to connect to onion service)
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
 #[repr(transparent)]
@@ -75,6 +81,17 @@ impl TorPublicKeyV3 {
     pub fn get_onion_address(&self) -> OnionAddressV3 {
         OnionAddressV3::from(self)
     }
+
+    /// to_x25519 converts this ed25519 public key into x25519(curve25519) public key using birational map
+    /// from Edwards to Montgomery form, the same way as tor and libsodium(`crypto_sign_ed25519_pk_to_curve25519`) do it.
+    ///
+    /// # Returns
+    /// `None` if bytes of this key are not valid ed25519 point.
+    /// It may happen only for keys taken from addresses created with `OnionAddressV3::new_unchecked`.
+    pub fn to_x25519(&self) -> Option<[u8; 32]> {
+        CompressedEdwardsY(self.0).decompress()
+            .map(|p| p.to_montgomery().to_bytes())
+    }
 }
 
 impl std::fmt::Debug for TorPublicKeyV3 {
@@ -83,9 +100,51 @@ impl std::fmt::Debug for TorPublicKeyV3 {
     }
 }
 
+/// TorPublicKeyV3 is displayed as standard base64(with padding) of its 32 bytes, so it's the same text as used
+/// by serde and it may be parsed back with `FromStr`.
 impl std::fmt::Display for TorPublicKeyV3 {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
-        write!(f, "TorPublicKey({})", base32::encode(BASE32_ALPHA, &self.0))
+        write!(f, "{}", base64::encode(&self.0[..]))
+    }
+}
+
+/// TorPublicKeyV3ParseError is returned when `TorPublicKeyV3` can't be parsed from string
+#[derive(Debug)]
+pub enum TorPublicKeyV3ParseError {
+    /// Base64Error is returned when text is not valid base64
+    Base64Error,
+
+    /// InvalidLength is returned when decoded key is not 32 bytes long
+    InvalidLength,
+
+    /// InvalidKey is returned when decoded bytes are not valid ed25519 public key
+    InvalidKey,
+}
+
+impl std::fmt::Display for TorPublicKeyV3ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TorPublicKeyV3ParseError::Base64Error => write!(f, "Filed to parse TorPublicKeyV3: invalid base64"),
+            TorPublicKeyV3ParseError::InvalidLength => write!(f, "Filed to parse TorPublicKeyV3: invalid length"),
+            TorPublicKeyV3ParseError::InvalidKey => write!(f, "Filed to parse TorPublicKeyV3: invalid ed25519 key"),
+        }
+    }
+}
+
+impl std::error::Error for TorPublicKeyV3ParseError {}
+
+impl std::str::FromStr for TorPublicKeyV3 {
+    type Err = TorPublicKeyV3ParseError;
+
+    /// from_str parses key encoded the way `Display` does it, which is base64 of 32 key bytes.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let raw = base64::decode(s).map_err(|_| TorPublicKeyV3ParseError::Base64Error)?;
+        if raw.len() != TORV3_PUBLIC_KEY_LENGTH {
+            return Err(TorPublicKeyV3ParseError::InvalidLength);
+        }
+        let mut buf = [0u8; TORV3_PUBLIC_KEY_LENGTH];
+        buf.copy_from_slice(&raw[..]);
+        Self::from_bytes(&buf).map_err(|_| TorPublicKeyV3ParseError::InvalidKey)
     }
 }
 
@@ -94,6 +153,11 @@ impl std::fmt::Display for TorPublicKeyV3 {
 /// In fact it can be treated as keypair because public key may be derived from secret one quite easily.
 ///
 /// It uses expanded secret key in order to support importing existing keys from tor.
+///
+/// # Note
+/// `From<[u8; 64]>` does not check if given bytes are valid expanded secret key, so using key created from
+/// arbitrary bytes may yield garbage or panic(for instance in `public`).
+/// Use `from_bytes_checked` when bytes come from untrusted source.
 #[derive(Clone)]
 #[repr(transparent)]
 #[derive(From, Into)]
@@ -101,31 +165,138 @@ pub struct TorSecretKeyV3([u8; TORV3_SECRET_KEY_LENGTH]);
 
 impl Eq for TorSecretKeyV3 {}
 
+/// Comparison runs in constant time, so it does not leak how many leading bytes of keys are equal.
+/// Keys are rarely compared, so it's rather defense-in-depth, but it's cheap to do it right.
 impl PartialEq for TorSecretKeyV3 {
-    // is non constant time eq fine here?
     fn eq(&self, other: &Self) -> bool {
-        self.0.iter().zip(other.0.iter()).all(|(b1, b2)| *b1 == *b2)
+        self.0[..].ct_eq(&other.0[..]).into()
     }
 }
 
 impl TorSecretKeyV3 {
-    pub(crate) fn as_tor_proto_encoded(&self) -> String {
-        base64::encode(&self.0[..])
+    /// from_bytes_checked creates `TorSecretKeyV3` from bytes of ed25519 expanded secret key
+    /// checking their validity first.
+    ///
+    /// Scalar part(first 32 bytes) of expanded secret key has to be clamped, the same way as ed25519 and tor do it.
+    /// Otherwise error is returned.
+    ///
+    /// # Example
+    /// ```
+    /// use torut::onion::TorSecretKeyV3;
+    ///
+    /// let sk = TorSecretKeyV3::generate();
+    /// assert_eq!(TorSecretKeyV3::from_bytes_checked(sk.as_bytes()).unwrap(), sk);
+    /// assert!(TorSecretKeyV3::from_bytes_checked([0u8; 64]).is_err());
+    /// ```
+    pub fn from_bytes_checked(bytes: [u8; TORV3_SECRET_KEY_LENGTH]) -> Result<Self, SignatureError> {
+        ExpandedSecretKey::from_bytes(&bytes[..])?;
+        let is_clamped = bytes[0] & 0b0000_0111 == 0
+            && bytes[31] & 0b1000_0000 == 0
+            && bytes[31] & 0b0100_0000 != 0;
+        if !is_clamped {
+            return Err(SignatureError::new());
+        }
+        Ok(TorSecretKeyV3(bytes))
     }
 
     /// generate generates new `TorSecretKeyV3`
     pub fn generate() -> Self {
         let sk: SecretKey = SecretKey::generate(&mut thread_rng());
-        let esk = ExpandedSecretKey::from(&sk);
+        Self::from_secret_key(&sk)
+    }
+
+    /// from_expanded_secret_key creates `TorSecretKeyV3` from ed25519-dalek's expanded secret key.
+    ///
+    /// Tor uses expanded keys internally, so no conversion is done.
+    pub fn from_expanded_secret_key(esk: &ExpandedSecretKey) -> Self {
         TorSecretKeyV3(esk.to_bytes())
     }
 
+    /// from_secret_key creates `TorSecretKeyV3` from ed25519-dalek's secret key(seed), for instance
+    /// one which is part of `ed25519_dalek::Keypair`.
+    ///
+    /// Key is expanded the same way `generate` does it, so public key of created key is equal to
+    /// `ed25519_dalek::PublicKey::from(sk)`.
+    ///
+    /// # Example
+    /// ```
+    /// use ed25519_dalek::{PublicKey, SecretKey};
+    /// use torut::onion::TorSecretKeyV3;
+    ///
+    /// let sk = SecretKey::from_bytes(&[7u8; 32]).unwrap();
+    /// let tsk = TorSecretKeyV3::from_secret_key(&sk);
+    /// assert_eq!(tsk.public().to_bytes(), PublicKey::from(&sk).to_bytes());
+    /// ```
+    pub fn from_secret_key(sk: &SecretKey) -> Self {
+        Self::from_expanded_secret_key(&ExpandedSecretKey::from(sk))
+    }
+
     /// creates `TorPublicKeyV3` from this secret key
     pub fn public(&self) -> TorPublicKeyV3 {
         let esk = ExpandedSecretKey::from_bytes(&self.0).expect("Invalid secret key contained");
         TorPublicKeyV3(PublicKey::from(&esk).to_bytes())
     }
 
+    /// get_public_key is alias for `public`
+    pub fn get_public_key(&self) -> TorPublicKeyV3 {
+        self.public()
+    }
+
+    /// get_onion_address creates onion address of onion service which uses this key.
+    ///
+    /// It's shortcut for `key.public().get_onion_address()`.
+    pub fn get_onion_address(&self) -> OnionAddressV3 {
+        self.public().get_onion_address()
+    }
+
+    /// from_tor_key_file creates `TorSecretKeyV3` from contents of `hs_ed25519_secret_key` file,
+    /// which tor stores in `HiddenServiceDir`.
+    ///
+    /// # Returns
+    /// `None` if data does not start with `TORV3_SECRET_KEY_FILE_HEADER`, has invalid length or key is not valid.
+    ///
+    /// # Example
+    /// ```
+    /// use torut::onion::TorSecretKeyV3;
+    ///
+    /// let sk = TorSecretKeyV3::generate();
+    /// assert_eq!(TorSecretKeyV3::from_tor_key_file(&sk.to_tor_key_file()).unwrap(), sk);
+    /// ```
+    pub fn from_tor_key_file(data: &[u8]) -> Option<Self> {
+        let header_len = TORV3_SECRET_KEY_FILE_HEADER.len();
+        if data.len() != header_len + TORV3_SECRET_KEY_LENGTH || data[..header_len] != TORV3_SECRET_KEY_FILE_HEADER[..] {
+            return None;
+        }
+        let mut buf = [0u8; TORV3_SECRET_KEY_LENGTH];
+        buf.copy_from_slice(&data[header_len..]);
+        Self::from_bytes_checked(buf).ok()
+    }
+
+    /// to_tor_key_file creates contents of `hs_ed25519_secret_key` file, so key may be used with tor's
+    /// `HiddenServiceDir` directly.
+    pub fn to_tor_key_file(&self) -> Vec<u8> {
+        let mut res = Vec::with_capacity(TORV3_SECRET_KEY_FILE_HEADER.len() + TORV3_SECRET_KEY_LENGTH);
+        res.extend_from_slice(&TORV3_SECRET_KEY_FILE_HEADER[..]);
+        res.extend_from_slice(&self.0[..]);
+        res
+    }
+
+    /// to_base64 returns standard base64(with padding) of expanded secret key bytes.
+    /// It's the same text as used by serde, so it may be deserialized back.
+    ///
+    /// # Note
+    /// `Display` and `Debug` never print the key, so it does not leak into logs by accident.
+    /// This function is the way to get it out intentionally, for instance when exporting onion service.
+    pub fn to_base64(&self) -> String {
+        base64::encode(&self.0[..])
+    }
+
+    /// as_tor_service_id returns service id of onion service which uses this key,
+    /// which is its onion address without `.onion` suffix, like tor's `ServiceID` is.
+    pub fn as_tor_service_id(&self) -> String {
+        self.get_onion_address().get_address_without_dot_onion()
+    }
+
     pub fn as_bytes(&self) -> [u8; 64] {
         self.0.clone()
     }
@@ -153,4 +324,100 @@ impl Drop for TorSecretKeyV3 {
         zero_memory(&mut self.0[..]);
     }
 }
-*/
\ No newline at end of file
+*/
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[test]
+    fn test_can_get_onion_address_from_secret_key() {
+        let sk = TorSecretKeyV3::generate();
+        assert_eq!(sk.get_public_key(), sk.public());
+        assert_eq!(sk.get_onion_address(), sk.public().get_onion_address());
+    }
+
+    #[test]
+    fn test_can_create_secret_key_from_dalek_keys() {
+        let kp = ed25519_dalek::Keypair::generate(&mut thread_rng());
+
+        let tsk = TorSecretKeyV3::from_secret_key(&kp.secret);
+        assert_eq!(tsk.public().to_bytes(), kp.public.to_bytes());
+        assert_eq!(tsk.public().to_bytes(), PublicKey::from(&kp.secret).to_bytes());
+
+        let esk = ExpandedSecretKey::from(&kp.secret);
+        let tesk = TorSecretKeyV3::from_expanded_secret_key(&esk);
+        assert_eq!(tesk, tsk);
+        assert_eq!(tesk.public().to_bytes(), PublicKey::from(&esk).to_bytes());
+
+        // keys created this way are valid
+        assert_eq!(TorSecretKeyV3::from_bytes_checked(tsk.as_bytes()).unwrap(), tsk);
+    }
+
+    #[test]
+    fn test_can_convert_public_key_to_x25519() {
+        // test vector from libsodium's ed25519_convert test
+        let seed = [
+            0x42, 0x11, 0x51, 0xa4, 0x59, 0xfa, 0xea, 0xde, 0x3d, 0x24, 0x71, 0x15, 0xf9, 0x4a, 0xed, 0xae,
+            0x42, 0x31, 0x81, 0x24, 0x09, 0x5a, 0xfa, 0xbe, 0x4d, 0x14, 0x51, 0xa5, 0x59, 0xfa, 0xed, 0xee,
+        ];
+        let sk = TorSecretKeyV3::from_secret_key(&SecretKey::from_bytes(&seed).unwrap());
+        assert_eq!(sk.public().to_x25519().unwrap(), [
+            0xf1, 0x81, 0x4f, 0x0e, 0x8f, 0xf1, 0x04, 0x3d, 0x8a, 0x44, 0xd2, 0x5b, 0xab, 0xff, 0x3c, 0xed,
+            0xca, 0xe6, 0xc2, 0x2c, 0x3e, 0xda, 0xa4, 0x8f, 0x85, 0x7a, 0xe7, 0x0d, 0xe2, 0xba, 0xae, 0x50,
+        ]);
+
+        // x25519 public key derived from scalar of expanded secret key has to be the same
+        for _ in 0..8 {
+            let sk = TorSecretKeyV3::generate();
+            let mut scalar = [0u8; 32];
+            scalar.copy_from_slice(&sk.as_bytes()[..32]);
+            let expected = curve25519_dalek::constants::X25519_BASEPOINT * curve25519_dalek::scalar::Scalar::from_bits(scalar);
+            assert_eq!(sk.public().to_x25519().unwrap(), expected.to_bytes());
+        }
+    }
+
+    #[test]
+    fn test_can_compare_secret_keys() {
+        let sk = TorSecretKeyV3::generate();
+        assert_eq!(sk, sk.clone());
+        assert_ne!(sk, TorSecretKeyV3::generate());
+
+        for idx in [0, TORV3_SECRET_KEY_LENGTH - 1].iter().copied() {
+            let mut bytes = sk.as_bytes();
+            bytes[idx] ^= 1;
+            assert_ne!(sk, TorSecretKeyV3::from(bytes));
+        }
+    }
+
+    #[test]
+    fn test_can_export_secret_key() {
+        let sk = TorSecretKeyV3::generate();
+        assert_eq!(sk.to_base64(), base64::encode(&sk.as_bytes()[..]));
+        assert_eq!(sk.to_string(), "TorSecretKey(****)");
+        assert_eq!(format!("{:?}", sk), "TorSecretKey(****)");
+
+        let service_id = sk.as_tor_service_id();
+        assert!(!service_id.ends_with(".onion"));
+        assert_eq!(format!("{}.onion", service_id), sk.get_onion_address().to_string());
+    }
+
+    #[test]
+    fn test_can_display_and_parse_public_key() {
+        let pk = TorSecretKeyV3::generate().public();
+        let text = pk.to_string();
+        assert_eq!(text, base64::encode(pk.as_bytes()));
+        assert_eq!(TorPublicKeyV3::from_str(&text).unwrap(), pk);
+        assert!(format!("{:?}", pk).starts_with("TorPublicKey("));
+
+        for invalid in [
+            "",
+            "not base64!",
+            "AAAA",
+        ].iter() {
+            assert!(TorPublicKeyV3::from_str(invalid).is_err(), "{:?}", invalid);
+        }
+    }
+}
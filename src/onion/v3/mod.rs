@@ -1,6 +1,8 @@
+pub use client_auth_key::*;
 pub use key::*;
 pub use onion::*;
 
+mod client_auth_key;
 mod key;
 mod onion;
 
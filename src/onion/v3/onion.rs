@@ -17,10 +17,19 @@ use crate::utils::BASE32_ALPHA;
 /// This variable denotes byte length of OnionAddressV3.
 pub const TORV3_ONION_ADDRESS_LENGTH_BYTES: usize = 34;
 
+/// 34 bytes of `TORV3_ONION_ADDRESS_LENGTH_BYTES` + 1 byte of version eq to 3 = 35
+///
+/// This variable denotes byte length of raw representation of OnionAddressV3, which is base32 encoded
+/// in order to create text form of address.
+pub const TORV3_ONION_ADDRESS_RAW_LENGTH_BYTES: usize = TORV3_ONION_ADDRESS_LENGTH_BYTES + 1;
+
 /// OnionAddressV3 contains public part of Tor's onion service address version 3.,
-/// It can't contain invalid onion address
+/// It can't contain invalid onion address(unless created with `new_unchecked`)
+///
+/// It stores entire raw representation(public key, checksum and version byte) so checksum is computed only once,
+/// when address is created, and formatting does not have to rebuild raw bytes.
 #[derive(Clone, Copy)]
-pub struct OnionAddressV3([u8; TORV3_ONION_ADDRESS_LENGTH_BYTES]);
+pub struct OnionAddressV3([u8; TORV3_ONION_ADDRESS_RAW_LENGTH_BYTES]);
 
 impl PartialEq for OnionAddressV3 {
     #[inline]
@@ -31,9 +40,30 @@ impl PartialEq for OnionAddressV3 {
 
 impl Eq for OnionAddressV3 {}
 
+impl std::hash::Hash for OnionAddressV3 {
+    #[inline]
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.0[..].hash(state)
+    }
+}
+
+impl PartialOrd for OnionAddressV3 {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for OnionAddressV3 {
+    #[inline]
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.0[..].cmp(&other.0[..])
+    }
+}
+
 impl From<&TorPublicKeyV3> for OnionAddressV3 {
     fn from(tpk: &TorPublicKeyV3) -> Self {
-        let mut buf = [0u8; 34];
+        let mut buf = [0u8; TORV3_ONION_ADDRESS_RAW_LENGTH_BYTES];
         tpk.0.iter().copied().enumerate().for_each(|(i, b)| {
             buf[i] = b;
         });
@@ -46,6 +76,7 @@ impl From<&TorPublicKeyV3> for OnionAddressV3 {
         let res_vec = h.finalize().to_vec();
         buf[32] = res_vec[0];
         buf[33] = res_vec[1];
+        buf[34] = 3;
         Self(buf)
     }
 }
@@ -55,7 +86,7 @@ impl std::fmt::Debug for OnionAddressV3 {
         write!(
             f,
             "OnionAddress({})",
-            base32::encode(BASE32_ALPHA, &(self.get_raw_bytes())[..]).to_ascii_lowercase(),
+            base32::encode(BASE32_ALPHA, &self.0[..]).to_ascii_lowercase(),
         )
     }
 }
@@ -65,23 +96,37 @@ impl std::fmt::Display for OnionAddressV3 {
         write!(
             f,
             "{}.onion",
-            base32::encode(BASE32_ALPHA, &(self.get_raw_bytes())[..]).to_ascii_lowercase()
+            base32::encode(BASE32_ALPHA, &self.0[..]).to_ascii_lowercase()
         )
     }
 }
 
 impl OnionAddressV3 {
+    /// new_unchecked creates onion address from its raw representation without validating it.
+    /// No checksum is computed, so it's cheaper than `from_str` or `From<&TorPublicKeyV3>`.
+    ///
+    /// Raw representation is the one returned from `get_raw_bytes`.
+    ///
+    /// # Contract
+    /// Caller must guarantee that `bytes` is valid raw onion address:
+    /// 32 bytes of ed25519 public key followed by two first bytes of
+    /// `sha3_256(".onion checksum" || pubkey || version)` and version byte eq to 3.
+    ///
+    /// Violating this contract does not cause undefined behaviour, but such address is not valid tor onion address
+    /// so it won't be accepted by tor nor by `from_str` and it's not equal to address created from same public key.
+    #[inline]
+    pub fn new_unchecked(bytes: [u8; TORV3_ONION_ADDRESS_RAW_LENGTH_BYTES]) -> Self {
+        Self(bytes)
+    }
+
     #[inline]
     pub fn get_address_without_dot_onion(&self) -> String {
-        base32::encode(BASE32_ALPHA, &(self.get_raw_bytes())[..]).to_ascii_lowercase()
+        base32::encode(BASE32_ALPHA, &self.0[..]).to_ascii_lowercase()
     }
 
     #[inline]
-    pub fn get_raw_bytes(&self) -> [u8; 35] {
-        let mut buf = [0u8; 35];
-        buf[..34].clone_from_slice(&self.0);
-        buf[34] = 3;
-        buf
+    pub fn get_raw_bytes(&self) -> [u8; TORV3_ONION_ADDRESS_RAW_LENGTH_BYTES] {
+        self.0
     }
 
     #[inline]
@@ -163,12 +208,8 @@ impl FromStr for OnionAddressV3 {
             return Err(OnionAddressParseError::InvalidChecksum);
         }
 
-        let mut buf = [0u8; 34];
-        for i in 0..32 {
-            buf[i] = res[i];
-        }
-        buf[32] = res_vec[0];
-        buf[33] = res_vec[1];
+        let mut buf = [0u8; TORV3_ONION_ADDRESS_RAW_LENGTH_BYTES];
+        buf.clone_from_slice(&res[..]);
 
         Ok(Self(buf))
     }
@@ -178,6 +219,27 @@ impl FromStr for OnionAddressV3 {
 mod test {
     use super::*;
 
+    #[test]
+    fn test_can_use_onion_address_as_map_key() {
+        let a1 = crate::onion::TorSecretKeyV3::generate().public().get_onion_address();
+        let a2 = crate::onion::TorSecretKeyV3::generate().public().get_onion_address();
+
+        let mut hash_set = std::collections::HashSet::new();
+        hash_set.insert(a1);
+        hash_set.insert(a2);
+        hash_set.insert(a1);
+        assert_eq!(hash_set.len(), 2);
+
+        let mut tree_set = std::collections::BTreeSet::new();
+        tree_set.insert(a1);
+        tree_set.insert(a2);
+        tree_set.insert(a1);
+        assert_eq!(tree_set.len(), 2);
+
+        assert_eq!(a1.cmp(&a2), a1.get_raw_bytes().cmp(&a2.get_raw_bytes()));
+        assert_eq!(a1.cmp(&a1), std::cmp::Ordering::Equal);
+    }
+
     //noinspection SpellCheckingInspection
     #[test]
     fn test_can_parse_onion_address() {
@@ -196,4 +258,26 @@ mod test {
         let oa2 = pk.get_onion_address();
         assert_eq!(oa, oa2);
     }
+
+    // benchmark-style loop: addresses built once with checksum computation and ones built from cached raw bytes
+    // must format the same. Time is not measured, since timing assertions in unit tests are flaky.
+    #[test]
+    fn test_new_unchecked_formats_like_address_from_key_in_loop() {
+        let keys = (0..64)
+            .map(|_| crate::onion::TorSecretKeyV3::generate().public())
+            .collect::<Vec<_>>();
+        let cached = keys.iter()
+            .map(|k| OnionAddressV3::from(k).get_raw_bytes())
+            .collect::<Vec<_>>();
+        for (key, raw) in keys.iter().zip(cached.iter()) {
+            assert_eq!(raw[34], 3);
+
+            let from_key = OnionAddressV3::from(key);
+            let unchecked = OnionAddressV3::new_unchecked(*raw);
+            assert_eq!(from_key, unchecked);
+            assert_eq!(from_key.to_string(), unchecked.to_string());
+            assert_eq!(from_key.get_address_without_dot_onion(), unchecked.get_address_without_dot_onion());
+            assert_eq!(OnionAddressV3::from_str(&unchecked.get_address_without_dot_onion()).unwrap(), from_key);
+        }
+    }
 }
@@ -6,7 +6,7 @@ use crate::onion::v3::{TorPublicKeyV3, TorSecretKeyV3};
 impl Serialize for TorSecretKeyV3 {
     fn serialize<S>(&self, serializer: S) -> Result<<S as Serializer>::Ok, <S as Serializer>::Error> where
         S: Serializer {
-        serializer.serialize_str(&base64::encode(&self.as_bytes()[..]))
+        serializer.serialize_str(&self.to_base64())
     }
 }
 
@@ -27,7 +27,7 @@ impl<'de> Deserialize<'de> for TorSecretKeyV3 {
         }
         let mut buf = [0u8; 64];
         buf.clone_from_slice(&raw[..]);
-        Ok(Self::from(buf))
+        Self::from_bytes_checked(buf).map_err(serde::de::Error::custom)
     }
 }
 
@@ -59,6 +59,16 @@ mod test {
         let rsk: TorSecretKeyV3 = serde_json::from_slice(&data).unwrap();
 
         assert_eq!(sk, rsk);
+        assert_eq!(serde_json::to_string(&sk).unwrap(), format!("\"{}\"", sk.to_base64()));
+    }
+
+    #[test]
+    fn test_can_not_deserialize_invalid_secret_key() {
+        let data = serde_json::to_vec(&base64::encode(&[0u8; 64][..])).unwrap();
+        assert!(serde_json::from_slice::<TorSecretKeyV3>(&data).is_err());
+
+        let data = serde_json::to_vec(&base64::encode(&[0u8; 32][..])).unwrap();
+        assert!(serde_json::from_slice::<TorSecretKeyV3>(&data).is_err());
     }
 
     #[test]
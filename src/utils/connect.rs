@@ -1 +0,0 @@
-// TODO(teawithsand): Connecting through tor utils here(de facto socks utils)
\ No newline at end of file
@@ -0,0 +1,91 @@
+use std::io;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+
+/// ControlPortAddr describes endpoint which tor's control port listens on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ControlPortAddr {
+    /// Tcp is control port listening on tcp socket, like `127.0.0.1:9051`
+    Tcp(SocketAddr),
+
+    /// Unix is control socket listening on unix socket, like `/run/tor/control`
+    Unix(PathBuf),
+}
+
+/// parse_control_port_file parses contents of file written by tor when `ControlPortWriteToFile` is set.
+///
+/// File contains one line per listener, like:
+/// ```text
+/// PORT=127.0.0.1:9051
+/// UNIX_PORT=/run/tor/control
+/// ```
+/// `PORT=unix:/path` form is accepted as well.
+///
+/// # Return value
+/// First listener found in file is returned.
+///
+/// # Example
+/// ```
+/// use torut::utils::{parse_control_port_file, ControlPortAddr};
+/// assert_eq!(
+///     parse_control_port_file("PORT=127.0.0.1:41235\n").unwrap(),
+///     ControlPortAddr::Tcp("127.0.0.1:41235".parse().unwrap())
+/// );
+/// ```
+pub fn parse_control_port_file(contents: &str) -> Result<ControlPortAddr, io::Error> {
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let res = if let Some(path) = line.strip_prefix("UNIX_PORT=") {
+            ControlPortAddr::Unix(PathBuf::from(path))
+        } else if let Some(addr) = line.strip_prefix("PORT=") {
+            if let Some(path) = addr.strip_prefix("unix:") {
+                ControlPortAddr::Unix(PathBuf::from(path))
+            } else {
+                let addr = addr.parse()
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+                ControlPortAddr::Tcp(addr)
+            }
+        } else {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "Invalid control port file line"));
+        };
+        return Ok(res);
+    }
+    Err(io::Error::new(io::ErrorKind::InvalidData, "No control port found in file"))
+}
+
+/// read_control_port_file reads and parses file written by tor when `ControlPortWriteToFile` is set.
+///
+/// It's useful when tor is started with `ControlPort auto`, so random port is chosen.
+/// Take a look at `parse_control_port_file` for format details.
+pub fn read_control_port_file(path: impl AsRef<Path>) -> Result<ControlPortAddr, io::Error> {
+    let contents = std::fs::read_to_string(path)?;
+    parse_control_port_file(&contents)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_can_parse_control_port_file() {
+        for (i, o) in [
+            ("PORT=127.0.0.1:9051\n", Some(ControlPortAddr::Tcp("127.0.0.1:9051".parse().unwrap()))),
+            ("PORT=[::1]:9051\r\n", Some(ControlPortAddr::Tcp("[::1]:9051".parse().unwrap()))),
+            ("PORT=unix:/run/tor/control\n", Some(ControlPortAddr::Unix(PathBuf::from("/run/tor/control")))),
+            ("UNIX_PORT=/run/tor/control\n", Some(ControlPortAddr::Unix(PathBuf::from("/run/tor/control")))),
+            (
+                "PORT=127.0.0.1:9051\nUNIX_PORT=/run/tor/control\n",
+                Some(ControlPortAddr::Tcp("127.0.0.1:9051".parse().unwrap()))
+            ),
+            ("\nPORT=127.0.0.1:9051", Some(ControlPortAddr::Tcp("127.0.0.1:9051".parse().unwrap()))),
+            ("", None),
+            ("PORT=localhost\n", None),
+            ("SOCKS=127.0.0.1:9050\n", None),
+        ].iter().cloned() {
+            assert_eq!(parse_control_port_file(i).ok(), o, "{:?}", i);
+        }
+    }
+}
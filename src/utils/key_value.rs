@@ -1,3 +1,7 @@
+use std::borrow::Cow;
+
+use crate::utils::unquote_string;
+
 /// parse_single_key_value parses response in following format:
 /// ```text
 /// KEYWORD=VALUE
@@ -43,10 +47,96 @@ pub fn parse_single_key_value(text: &str) -> Result<(&str, &str), ()>
     Ok((key, value))
 }
 
+/// parse_key_value_line parses space separated list of key value pairs, like the ones found in asynchronous events:
+/// ```text
+/// PROGRESS=75 TAG=enough_dirinfo SUMMARY="Loaded enough directory info to build circuits"
+/// ```
+/// Values which are quoted strings are unquoted, so they may contain spaces.
+/// Pairs are returned in order they occur in text.
+///
+/// # Return value
+/// `None` is returned:
+/// - if any space separated part is not `KEY=VALUE` pair, or key is empty or contains chars not allowed by `parse_single_key_value`
+/// - if quoted value is not valid quoted string or it's not followed by space or end of text
+///
+/// # Example
+/// ```
+/// use torut::utils::parse_key_value_line;
+/// let res = parse_key_value_line("PROGRESS=75 SUMMARY=\"Done\\\"\" TAG=done").unwrap();
+/// assert_eq!(res.len(), 3);
+/// assert_eq!((res[0].0, &res[0].1 as &str), ("PROGRESS", "75"));
+/// assert_eq!((res[1].0, &res[1].1 as &str), ("SUMMARY", "Done\""));
+/// assert_eq!((res[2].0, &res[2].1 as &str), ("TAG", "done"));
+/// assert!(parse_key_value_line("NOT_A_PAIR").is_none());
+/// ```
+pub fn parse_key_value_line(text: &str) -> Option<Vec<(&str, Cow<'_, str>)>> {
+    let mut res = Vec::new();
+    let mut rest = text;
+    loop {
+        rest = rest.trim_start_matches(' ');
+        if rest.is_empty() {
+            break;
+        }
+        let eq_offset = rest.find('=')?;
+        let key = &rest[..eq_offset];
+        if key.is_empty() || key.contains(' ') {
+            return None;
+        }
+        // key charset is same as in single key value
+        parse_single_key_value(&rest[..eq_offset + 1]).ok()?;
+
+        let value_and_rest = &rest[eq_offset + 1..];
+        if value_and_rest.starts_with('"') {
+            match unquote_string(value_and_rest) {
+                (Some(offset), Ok(value)) => {
+                    rest = &value_and_rest[offset + 1..];
+                    if !rest.is_empty() && !rest.starts_with(' ') {
+                        return None;
+                    }
+                    res.push((key, value));
+                }
+                _ => return None,
+            }
+        } else {
+            let value_end = value_and_rest.find(' ').unwrap_or(value_and_rest.len());
+            res.push((key, Cow::Borrowed(&value_and_rest[..value_end])));
+            rest = &value_and_rest[value_end..];
+        }
+    }
+    Some(res)
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
 
+    #[test]
+    fn test_can_parse_key_value_line() {
+        for (i, o) in [
+            ("", Some(vec![])),
+            ("A=B", Some(vec![("A", "B")])),
+            ("A=B C=D", Some(vec![("A", "B"), ("C", "D")])),
+            ("A=  C=D", Some(vec![("A", ""), ("C", "D")])),
+            ("A=\"B C\" C=\"\"", Some(vec![("A", "B C"), ("C", "")])),
+            ("A=\"B\\\"C\"", Some(vec![("A", "B\"C")])),
+            ("A=B=C", Some(vec![("A", "B=C")])),
+            ("A=\"B\"C", None),
+            ("A=\"B", None),
+            ("A", None),
+            ("=B", None),
+            ("A=B C", None),
+            ("A B=C", None),
+        ].iter() {
+            let res = parse_key_value_line(i).map(|res| {
+                res.into_iter().map(|(k, v)| (k.to_string(), v.into_owned())).collect::<Vec<_>>()
+            });
+            let o = o.as_ref().map(|o| {
+                o.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect::<Vec<_>>()
+            });
+            assert_eq!(res, o, "{:?}", i);
+        }
+    }
+
     #[test]
     fn test_can_parse_single_key_value() {
         for (i, o) in [
@@ -3,17 +3,27 @@ use std::future::Future;
 
 mod quoted;
 mod key_value;
+mod multiline;
+mod validation;
+mod server_spec;
 mod run;
-mod connect;
+mod control_port;
+#[cfg(feature = "v3")]
+mod onion_dir;
 
 #[cfg(testtor)]
 mod testing;
 
 
 pub use key_value::*;
+pub use multiline::*;
 pub use quoted::*;
 pub use run::*;
-pub use connect::*;
+pub use validation::*;
+pub use server_spec::*;
+pub use control_port::*;
+#[cfg(feature = "v3")]
+pub use onion_dir::*;
 
 #[cfg(testtor)]
 pub use testing::*;
@@ -30,6 +40,53 @@ pub(crate) fn block_on<F, O>(f: F) -> O
     rt.block_on(f)
 }
 
+/// TestStream is `AsyncRead + AsyncWrite` stream used for testing.
+/// It reads data from given input and stores all the data written to it in `output`.
+#[cfg(test)]
+pub(crate) struct TestStream {
+    input: std::io::Cursor<Vec<u8>>,
+    pub output: Vec<u8>,
+}
+
+#[cfg(test)]
+impl TestStream {
+    pub fn new(input: &[u8]) -> Self {
+        Self {
+            input: std::io::Cursor::new(Vec::from(input)),
+            output: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+impl tokio::io::AsyncRead for TestStream {
+    fn poll_read(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        buf: &mut tokio::io::ReadBuf<'_>,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        std::pin::Pin::new(&mut self.input).poll_read(cx, buf)
+    }
+}
+
+#[cfg(test)]
+impl tokio::io::AsyncWrite for TestStream {
+    fn poll_write(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        buf: &[u8],
+    ) -> std::task::Poll<std::io::Result<usize>> {
+        std::pin::Pin::new(&mut self.output).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: std::pin::Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> std::task::Poll<std::io::Result<()>> {
+        std::pin::Pin::new(&mut self.output).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: std::pin::Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> std::task::Poll<std::io::Result<()>> {
+        std::pin::Pin::new(&mut self.output).poll_shutdown(cx)
+    }
+}
 
 #[allow(dead_code)]
 #[cfg(any(test, fuzzing))]
@@ -44,6 +101,7 @@ pub(crate) fn block_on_with_env<F, O>(f: F) -> O
 }
 
 /// is_valid_keyword checks if given text is valid tor keyword for functions like `GETCONF` or `SETCONF`
+/// Underscores are allowed, since some options like `__OwningControllerProcess` contain them.
 ///
 /// Note: this function was not tested against torCP but it's simple and robust and should work.
 pub(crate) fn is_valid_keyword(config_option: &str) -> bool {
@@ -51,64 +109,15 @@ pub(crate) fn is_valid_keyword(config_option: &str) -> bool {
         return false;
     }
     for c in config_option.chars() {
-        if !c.is_ascii_alphanumeric() {
-            return false;
-        }
-    }
-    true
-}
-
-/// is_valid_event returns true if name is valid event name or false if it should not be used in context of
-/// `SETEVENTS` call
-pub(crate) fn is_valid_event(event_name: &str) -> bool {
-    if event_name.is_empty() {
-        return false;
-    }
-    for c in event_name.chars() {
-        if !c.is_ascii_uppercase() {
-            return false;
-        }
-    }
-    true
-}
-
-/// is_valid_hostname checks if given text is valid hostname which can be resolved with tor
-pub(crate) fn is_valid_hostname(config_option: &str) -> bool {
-    if config_option.is_empty() {
-        return false;
-    }
-    for c in config_option.chars() {
-        if !c.is_ascii_alphanumeric() && c != '.' && c != '-' {
+        if !c.is_ascii_alphanumeric() && c != '_' {
             return false;
         }
     }
     true
 }
 
-/// is_valid_keyword checks if given text is valid tor info keyword for `GETINFO` call
-///
-/// Note: this function was not tested against torCP but it's simple and robust and should work.
-pub(crate) fn is_valid_option(config_option: &str) -> bool {
-    if config_option.is_empty() {
-        return false;
-    }
-    for c in config_option.chars() {
-        if !c.is_ascii() || c == '\r' || c == '\n' {
-            return false;
-        }
-    }
-    if !config_option.chars().nth(0).unwrap().is_ascii_alphanumeric() {
-        return false;
-    }
-    if !config_option.chars().rev().nth(0).unwrap().is_ascii_alphanumeric() {
-        return false;
-    }
-    true
-}
-
-
 /// BASE32_ALPHA to use when encoding base32 stuff
-#[allow(dead_code)] // not used when onion service v2 enabled
+#[cfg(feature = "v3")]
 pub(crate) const BASE32_ALPHA: base32::Alphabet = base32::Alphabet::RFC4648 {
     padding: false,
 };
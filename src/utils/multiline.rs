@@ -0,0 +1,95 @@
+use std::borrow::Cow;
+
+/// encode_multiline_data creates `CmdData` token from given text.
+/// It's used by commands which send multiline data to tor, like `+POSTDESCRIPTOR` or `+LOADCONF`.
+///
+/// According to torCP docs:
+/// ```text
+/// If the line begins with ".", it's prefixed with another.
+/// ```
+/// So each line starting with dot gets one more dot(dot-stuffing), otherwise line which is single dot would
+/// terminate data block.
+///
+/// All line endings(both `\n` and `\r\n`) are normalized to `\r\n`.
+/// Result is terminated with `.\r\n` line so it may be sent right after the command line.
+///
+/// It's reverse function to `decode_multiline_data`.
+///
+/// # Example
+/// ```
+/// use torut::utils::encode_multiline_data;
+/// assert_eq!(encode_multiline_data("a\n.\nb"), "a\r\n..\r\nb\r\n.\r\n");
+/// assert_eq!(encode_multiline_data(""), ".\r\n");
+/// ```
+pub fn encode_multiline_data(body: &str) -> String {
+    // each line gets at least two more bytes
+    let mut res = String::with_capacity(body.len() + 3);
+    for line in body.lines() {
+        if line.starts_with('.') {
+            res.push('.');
+        }
+        res.push_str(line);
+        res.push_str("\r\n");
+    }
+    res.push_str(".\r\n");
+    res
+}
+
+/// decode_multiline_data reverses dot-stuffing done by tor(or by `encode_multiline_data`) in `CmdData` token.
+///
+/// Data may be given either with terminating `.` line or without it(like `Conn::receive_data` returns it).
+/// Lines in result are separated with `\r\n` and there is no trailing line ending.
+///
+/// # Return value
+/// If there is nothing to unescape borrowed cow is returned.
+///
+/// # Example
+/// ```
+/// use torut::utils::decode_multiline_data;
+/// assert_eq!(decode_multiline_data("a\r\n..\r\nb\r\n.\r\n"), "a\r\n.\r\nb");
+/// assert_eq!(decode_multiline_data("a\r\n..\r\nb"), "a\r\n.\r\nb");
+/// ```
+pub fn decode_multiline_data(data: &str) -> Cow<'_, str> {
+    let data = if data == ".\r\n" {
+        ""
+    } else {
+        data.strip_suffix("\r\n.\r\n").unwrap_or(data)
+    };
+    if !data.starts_with('.') && !data.contains("\r\n.") {
+        return Cow::Borrowed(data);
+    }
+
+    let mut res = String::with_capacity(data.len());
+    for (i, line) in data.split("\r\n").enumerate() {
+        if i != 0 {
+            res.push_str("\r\n");
+        }
+        res.push_str(line.strip_prefix('.').unwrap_or(line));
+    }
+    Cow::Owned(res)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_can_encode_and_decode_multiline_data() {
+        for (input, encoded, decoded) in [
+            ("", ".\r\n", ""),
+            ("single line", "single line\r\n.\r\n", "single line"),
+            (".", "..\r\n.\r\n", "."),
+            ("a\n.\nb", "a\r\n..\r\nb\r\n.\r\n", "a\r\n.\r\nb"),
+            ("a\r\n.\r\nb\r\n", "a\r\n..\r\nb\r\n.\r\n", "a\r\n.\r\nb"),
+            ("..\n.x\n.", "...\r\n..x\r\n..\r\n.\r\n", "..\r\n.x\r\n."),
+            ("a\n\nb", "a\r\n\r\nb\r\n.\r\n", "a\r\n\r\nb"),
+        ].iter().cloned() {
+            let res = encode_multiline_data(input);
+            assert_eq!(res, encoded);
+            assert_eq!(decode_multiline_data(&res), decoded);
+
+            // without terminating line, like `Conn::receive_data` returns it
+            assert_eq!(decode_multiline_data(res[..res.len() - 3].strip_suffix("\r\n").unwrap_or("")), decoded);
+        }
+    }
+}
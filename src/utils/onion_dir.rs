@@ -0,0 +1,92 @@
+use std::io;
+use std::path::Path;
+
+use crate::onion::{OnionAddressV3, TorSecretKeyV3};
+
+/// load_onion_service_from_dir loads onion service v3 stored by tor in single `HiddenServiceDir`.
+///
+/// It reads `hs_ed25519_secret_key` and `hostname` files and checks that they describe the same service.
+///
+/// # Error
+/// Error of kind `io::ErrorKind::InvalidData` is returned when key file is not valid,
+/// hostname is not valid onion address v3 or hostname does not match the key.
+pub fn load_onion_service_from_dir(path: &Path) -> Result<(OnionAddressV3, TorSecretKeyV3), io::Error> {
+    let key_data = std::fs::read(path.join("hs_ed25519_secret_key"))?;
+    let key = TorSecretKeyV3::from_tor_key_file(&key_data)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "Invalid hs_ed25519_secret_key file"))?;
+
+    let hostname = std::fs::read_to_string(path.join("hostname"))?;
+    let hostname = hostname.trim();
+    let address: OnionAddressV3 = hostname.strip_suffix(".onion").unwrap_or(hostname)
+        .parse()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+    if address != key.get_onion_address() {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "Onion service hostname does not match its key"));
+    }
+    Ok((address, key))
+}
+
+/// load_onion_services_from_dir loads all onion services v3 stored in subdirectories of given directory,
+/// like `/var/lib/tor/services/*` when each service has `HiddenServiceDir` there.
+///
+/// Subdirectories without `hs_ed25519_secret_key` file(for instance v2 services) are skipped.
+/// Take a look at `load_onion_service_from_dir` for details of loading single service.
+///
+/// # Return value
+/// Services sorted by name of their directory.
+///
+/// # Error
+/// Error is returned when directory can't be listed or any service found in it can't be loaded.
+pub fn load_onion_services_from_dir(path: &Path) -> Result<Vec<(OnionAddressV3, TorSecretKeyV3)>, io::Error> {
+    let mut dirs = Vec::new();
+    for entry in std::fs::read_dir(path)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() && entry.path().join("hs_ed25519_secret_key").exists() {
+            dirs.push(entry.path());
+        }
+    }
+    dirs.sort();
+
+    dirs.iter()
+        .map(|dir| load_onion_service_from_dir(dir))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_can_load_onion_services_from_dir() {
+        let dir = std::env::temp_dir().join(format!("torut_test_onion_services_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let keys = [TorSecretKeyV3::generate(), TorSecretKeyV3::generate()];
+        for (name, key) in ["a", "b"].iter().zip(keys.iter()) {
+            let service_dir = dir.join(name);
+            std::fs::create_dir_all(&service_dir).unwrap();
+            std::fs::write(service_dir.join("hs_ed25519_secret_key"), key.to_tor_key_file()).unwrap();
+            std::fs::write(service_dir.join("hostname"), format!("{}\n", key.get_onion_address())).unwrap();
+        }
+        std::fs::create_dir_all(dir.join("empty")).unwrap();
+        std::fs::write(dir.join("not_a_service"), b"").unwrap();
+
+        let services = load_onion_services_from_dir(&dir).unwrap();
+        assert_eq!(services.len(), 2);
+        for ((address, key), expected_key) in services.iter().zip(keys.iter()) {
+            assert_eq!(key, expected_key);
+            assert_eq!(*address, expected_key.get_onion_address());
+        }
+
+        // hostname of other service
+        std::fs::write(dir.join("a").join("hostname"), format!("{}\n", keys[1].get_onion_address())).unwrap();
+        assert_eq!(load_onion_services_from_dir(&dir).unwrap_err().kind(), io::ErrorKind::InvalidData);
+
+        std::fs::write(dir.join("a").join("hostname"), format!("{}\n", keys[0].get_onion_address())).unwrap();
+        std::fs::write(dir.join("a").join("hs_ed25519_secret_key"), &keys[0].as_bytes()[..]).unwrap();
+        assert_eq!(load_onion_services_from_dir(&dir).unwrap_err().kind(), io::ErrorKind::InvalidData);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
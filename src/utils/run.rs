@@ -1,4 +1,5 @@
 use std::io::{BufRead, BufReader};
+use std::net::SocketAddr;
 use std::ops::{Deref, DerefMut};
 use std::process::{Child, Command, Stdio};
 
@@ -85,8 +86,26 @@ pub fn run_tor<A, T, P>(path: P, args: A) -> Result<Child, std::io::Error>
         A: AsRef<[T]>,
         T: AsRef<str>,
         P: AsRef<str>,
+{
+    run_tor_with_control_addr(path, args).map(|(c, _)| c)
+}
+
+/// run_tor_with_control_addr works just like `run_tor` but it also returns address of control port listener
+/// found in tor's "Opened Control listener" log line.
+///
+/// It makes `ControlPort auto` usable, since tor picks port and prints it.
+///
+/// # Return value
+/// Address is `None` when it was not possible to parse it from log line, for instance when control listener
+/// is unix socket.
+pub fn run_tor_with_control_addr<A, T, P>(path: P, args: A) -> Result<(Child, Option<SocketAddr>), std::io::Error>
+    where
+        A: AsRef<[T]>,
+        T: AsRef<str>,
+        P: AsRef<str>,
 {
     let path = path.as_ref();
+    let control_addr;
     let mut c = Command::new(path)
         .args(args.as_ref().iter().map(|t| t.as_ref()))
         // .env_clear()
@@ -118,6 +137,7 @@ pub fn run_tor<A, T, P>(path: P, args: A) -> Result<Child, std::io::Error>
                 };
 
                 if l.contains("Opened Control listener") {
+                    control_addr = parse_control_listener_addr(&l);
                     break;
                 }
             }
@@ -126,9 +146,50 @@ pub fn run_tor<A, T, P>(path: P, args: A) -> Result<Child, std::io::Error>
             // It may cause partial data loss but it's better than dropping child.
         }
     }
-    Ok(c)
+    Ok((c, control_addr))
+}
+
+/// parse_control_listener_addr parses address from tor's log line like:
+/// ```text
+/// Oct 16 12:00:00.000 [notice] Opened Control listener connection (ready) on 127.0.0.1:9051
+/// ```
+fn parse_control_listener_addr(line: &str) -> Option<SocketAddr> {
+    let (_, addr) = line.trim_end().rsplit_once(" on ")?;
+    addr.parse().ok()
 }
 
 // TODO(teawithsand): async run_tor
 
-// tests for these are in testing.rs
\ No newline at end of file
+// tests running tor are in testing.rs
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_can_parse_control_listener_addr() {
+        for (i, o) in [
+            (
+                "Oct 16 12:00:00.000 [notice] Opened Control listener connection (ready) on 127.0.0.1:9051\n",
+                Some("127.0.0.1:9051"),
+            ),
+            (
+                "Oct 16 12:00:00.000 [notice] Opened Control listener on 127.0.0.1:41235\n",
+                Some("127.0.0.1:41235"),
+            ),
+            (
+                "Oct 16 12:00:00.000 [notice] Opened Control listener connection (ready) on [::1]:9051\r\n",
+                Some("[::1]:9051"),
+            ),
+            (
+                "Oct 16 12:00:00.000 [notice] Opened Control listener connection (ready) on /run/tor/control\n",
+                None,
+            ),
+            (
+                "Oct 16 12:00:00.000 [notice] Opened Control listener",
+                None,
+            ),
+        ].iter() {
+            assert_eq!(parse_control_listener_addr(i), o.map(|o| o.parse().unwrap()), "{:?}", i);
+        }
+    }
+}
\ No newline at end of file
@@ -0,0 +1,61 @@
+/// parse_server_spec splits single relay name used by torCP(for instance element of circuit path with `VERBOSE_NAMES`)
+/// into fingerprint(without leading `$`) and nickname.
+///
+/// Following forms are handled:
+/// - `$fingerprint~nickname` - nickname is verified
+/// - `$fingerprint=nickname` - nickname is not verified
+/// - `$fingerprint`
+/// - `nickname`
+///
+/// It only splits text, so fingerprint should be validated with `is_valid_fingerprint` if it matters.
+/// Empty components are returned as `None`.
+///
+/// # Example
+/// ```
+/// use torut::utils::parse_server_spec;
+/// assert_eq!(
+///     parse_server_spec("$7EA6EAD6FD83083C538F44038BBFA077587DD755~moria1"),
+///     (Some("7EA6EAD6FD83083C538F44038BBFA077587DD755"), Some("moria1")),
+/// );
+/// assert_eq!(parse_server_spec("moria1"), (None, Some("moria1")));
+/// ```
+pub fn parse_server_spec(spec: &str) -> (Option<&str>, Option<&str>) {
+    let (fingerprint, nickname) = match spec.strip_prefix('$') {
+        Some(rest) => match rest.find(['~', '=']) {
+            Some(idx) => (Some(&rest[..idx]), Some(&rest[idx + 1..])),
+            None => (Some(rest), None),
+        },
+        None => (None, Some(spec)),
+    };
+    (fingerprint.filter(|f| !f.is_empty()), nickname.filter(|n| !n.is_empty()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_can_parse_server_spec() {
+        for (i, o) in [
+            (
+                "$7EA6EAD6FD83083C538F44038BBFA077587DD755~moria1",
+                (Some("7EA6EAD6FD83083C538F44038BBFA077587DD755"), Some("moria1")),
+            ),
+            (
+                "$7EA6EAD6FD83083C538F44038BBFA077587DD755=moria1",
+                (Some("7EA6EAD6FD83083C538F44038BBFA077587DD755"), Some("moria1")),
+            ),
+            (
+                "$7EA6EAD6FD83083C538F44038BBFA077587DD755",
+                (Some("7EA6EAD6FD83083C538F44038BBFA077587DD755"), None),
+            ),
+            ("moria1", (None, Some("moria1"))),
+            ("$7EA6EAD6FD83083C538F44038BBFA077587DD755~", (Some("7EA6EAD6FD83083C538F44038BBFA077587DD755"), None)),
+            ("$~moria1", (None, Some("moria1"))),
+            ("$", (None, None)),
+            ("", (None, None)),
+        ].iter() {
+            assert_eq!(parse_server_spec(i), *o, "{:?}", i);
+        }
+    }
+}
@@ -0,0 +1,273 @@
+/// MAX_HOSTNAME_LENGTH is max length of hostname in text form
+const MAX_HOSTNAME_LENGTH: usize = 253;
+
+/// MAX_HOSTNAME_LABEL_LENGTH is max length of single dot-separated part of hostname
+const MAX_HOSTNAME_LABEL_LENGTH: usize = 63;
+
+/// is_valid_hostname checks if given text is valid hostname which can be resolved with tor
+///
+/// Hostname is valid when:
+/// - it's not empty and it's not longer than 253 chars
+/// - it consists of dot-separated labels which are 1 to 63 chars long
+/// - labels contain only ascii letters, digits and hyphens(so no whitespace nor control chars which could be used
+///   to inject another command)
+///
+/// # Example
+/// ```
+/// use torut::utils::is_valid_hostname;
+/// assert!(is_valid_hostname("torproject.org"));
+/// assert!(!is_valid_hostname("torproject.org\r\nSIGNAL SHUTDOWN"));
+/// assert!(!is_valid_hostname("torproject..org"));
+/// ```
+pub fn is_valid_hostname(hostname: &str) -> bool {
+    if hostname.is_empty() || hostname.len() > MAX_HOSTNAME_LENGTH {
+        return false;
+    }
+    for label in hostname.split('.') {
+        if label.is_empty() || label.len() > MAX_HOSTNAME_LABEL_LENGTH {
+            return false;
+        }
+        for c in label.chars() {
+            if !c.is_ascii_alphanumeric() && c != '-' {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+/// is_valid_event returns true if name is valid event name or false if it should not be used in context of
+/// `SETEVENTS` call
+///
+/// Event name is valid when it's not empty and consists of uppercase ascii letters, digits and underscores,
+/// like `CIRC`, `HS_DESC` or `CONF_CHANGED`.
+///
+/// # Example
+/// ```
+/// use torut::utils::is_valid_event;
+/// assert!(is_valid_event("HS_DESC"));
+/// assert!(!is_valid_event("hs_desc"));
+/// assert!(!is_valid_event("CIRC STREAM"));
+/// ```
+pub fn is_valid_event(event_name: &str) -> bool {
+    if event_name.is_empty() {
+        return false;
+    }
+    for c in event_name.chars() {
+        if !c.is_ascii_uppercase() && !c.is_ascii_digit() && c != '_' {
+            return false;
+        }
+    }
+    true
+}
+
+/// is_valid_option checks if given text is valid tor info keyword for `GETINFO` call
+///
+/// Keyword is valid when:
+/// - it's not empty
+/// - it consists of printable ascii chars only(so no whitespace nor control chars), which allows keys
+///   like `net/listeners/socks`, `ip-to-country/1.2.3.4` or `ns/id/$fingerprint`
+/// - it starts and ends with ascii letter or digit
+///
+/// # Example
+/// ```
+/// use torut::utils::is_valid_option;
+/// assert!(is_valid_option("net/listeners/socks"));
+/// assert!(!is_valid_option("version config-file"));
+/// assert!(!is_valid_option("version\r\nSIGNAL SHUTDOWN"));
+/// ```
+pub fn is_valid_option(option: &str) -> bool {
+    if option.is_empty() {
+        return false;
+    }
+    for c in option.chars() {
+        if !c.is_ascii_graphic() {
+            return false;
+        }
+    }
+    if !option.chars().next().unwrap().is_ascii_alphanumeric() {
+        return false;
+    }
+    if !option.chars().next_back().unwrap().is_ascii_alphanumeric() {
+        return false;
+    }
+    true
+}
+
+/// is_valid_fingerprint checks if given text is valid relay fingerprint, which is hex encoded identity digest
+/// optionally prefixed with `$`, like `$7EA6EAD6FD83083C538F44038BBFA077587DD755`.
+///
+/// # Example
+/// ```
+/// use torut::utils::is_valid_fingerprint;
+/// assert!(is_valid_fingerprint("7EA6EAD6FD83083C538F44038BBFA077587DD755"));
+/// assert!(is_valid_fingerprint("$7EA6EAD6FD83083C538F44038BBFA077587DD755"));
+/// assert!(!is_valid_fingerprint("moria1"));
+/// ```
+pub fn is_valid_fingerprint(fingerprint: &str) -> bool {
+    let fingerprint = fingerprint.strip_prefix('$').unwrap_or(fingerprint);
+    fingerprint.len() == 40 && fingerprint.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// is_valid_nickname checks if given text is valid relay nickname, which is 1 to 19 ascii alphanumeric characters,
+/// like `moria1`.
+///
+/// # Example
+/// ```
+/// use torut::utils::is_valid_nickname;
+/// assert!(is_valid_nickname("moria1"));
+/// assert!(!is_valid_nickname("$7EA6EAD6FD83083C538F44038BBFA077587DD755"));
+/// ```
+pub fn is_valid_nickname(nickname: &str) -> bool {
+    !nickname.is_empty() && nickname.len() <= 19 && nickname.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+/// is_valid_feature returns true if name is valid feature name which can be used in context of `USEFEATURE` call
+/// for instance `EXTENDED_EVENTS` or `VERBOSE_NAMES`
+///
+/// Feature names use same charset as event names, so it's checked with `is_valid_event`.
+pub(crate) fn is_valid_feature(feature_name: &str) -> bool {
+    is_valid_event(feature_name)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_is_valid_hostname() {
+        for (i, o) in [
+            ("torproject.org", true),
+            ("www.torproject.org", true),
+            ("p53lf57qovyuvwsc6xnrppyply3vtqm7l6pcobkmyqsiofyeznfu5uqd.onion", true),
+            ("localhost", true),
+            ("127.0.0.1", true),
+            ("my-host.example", true),
+            ("", false),
+            (".", false),
+            (".torproject.org", false),
+            ("torproject.org.", false),
+            ("torproject..org", false),
+            ("tor project.org", false),
+            ("torproject.org\r\nSIGNAL SHUTDOWN", false),
+            ("torproject.org\n", false),
+            ("tor\0project.org", false),
+            ("tor\tproject.org", false),
+            ("torprojęct.org", false),
+            (&"a".repeat(63), true),
+            (&"a".repeat(64), false),
+            (&["a"; 127].join("."), true),
+            (&["a"; 128].join("."), false),
+        ].iter() {
+            assert_eq!(is_valid_hostname(i), *o, "{:?}", i);
+        }
+    }
+
+    #[test]
+    fn test_is_valid_event() {
+        for name in [
+            "CIRC", "STREAM", "ORCONN", "BW",
+            "DEBUG", "INFO", "NOTICE", "WARN", "ERR",
+            "NEWDESC", "ADDRMAP", "AUTHDIR_NEWDESCS", "DESCCHANGED",
+            "STATUS_GENERAL", "STATUS_CLIENT", "STATUS_SERVER",
+            "GUARD", "NS", "STREAM_BW", "CLIENTS_SEEN", "NEWCONSENSUS", "BUILDTIMEOUT_SET", "SIGNAL",
+            "CONF_CHANGED", "CIRC_MINOR", "TRANSPORT_LAUNCHED", "CONN_BW", "CIRC_BW", "CELL_STATS", "TB_EMPTY",
+            "HS_DESC", "HS_DESC_CONTENT", "NETWORK_LIVENESS", "PT_LOG", "PT_STATUS",
+        ].iter() {
+            assert!(is_valid_event(name), "{:?}", name);
+        }
+        for name in [
+            "",
+            "circ",
+            "Circ",
+            "CIRC STREAM",
+            "CIRC\r\nSIGNAL",
+            "HS-DESC",
+            "HS/DESC",
+        ].iter() {
+            assert!(!is_valid_event(name), "{:?}", name);
+        }
+    }
+
+    #[test]
+    fn test_is_valid_option() {
+        for option in [
+            "version",
+            "sr/previous",
+            "sr/current",
+            "net/listeners/socks",
+            "net/listeners/control",
+            "status/bootstrap-phase",
+            "onions/detached",
+            "config-file",
+            "ip-to-country/1.2.3.4",
+            "ns/id/$7EA6EAD6FD83083C538F44038BBFA077587DD755",
+            "md/name/moria1",
+        ].iter() {
+            assert!(is_valid_option(option), "{:?}", option);
+        }
+        for option in [
+            "",
+            "version config-file",
+            "version\r\nSIGNAL SHUTDOWN",
+            "version\n",
+            "\tversion",
+            "/net/listeners",
+            "net/listeners/",
+            "onions/current\0",
+            "wersjä",
+        ].iter() {
+            assert!(!is_valid_option(option), "{:?}", option);
+        }
+    }
+
+    #[test]
+    fn test_is_valid_fingerprint() {
+        for (i, o) in [
+            ("7EA6EAD6FD83083C538F44038BBFA077587DD755", true),
+            ("$7EA6EAD6FD83083C538F44038BBFA077587DD755", true),
+            ("7ea6ead6fd83083c538f44038bbfa077587dd755", true),
+            ("", false),
+            ("$", false),
+            ("$$7EA6EAD6FD83083C538F44038BBFA077587DD755", false),
+            ("7EA6EAD6FD83083C538F44038BBFA077587DD75", false),
+            ("7EA6EAD6FD83083C538F44038BBFA077587DD7555", false),
+            ("7EA6EAD6FD83083C538F44038BBFA077587DD75G", false),
+            ("7EA6EAD6FD83083C538F44038BBFA077587DD755\r\n", false),
+            ("$7EA6EAD6FD83083C538F44038BBFA077587DD755~moria1", false),
+        ].iter() {
+            assert_eq!(is_valid_fingerprint(i), *o, "{:?}", i);
+        }
+    }
+
+    #[test]
+    fn test_is_valid_nickname() {
+        for (i, o) in [
+            ("moria1", true),
+            ("a", true),
+            ("abcdefghij123456789", true),
+            ("", false),
+            ("abcdefghij1234567890", false),
+            ("my-relay", false),
+            ("moria1\r\n", false),
+            ("$7EA6EAD6FD83083C538F44038BBFA077587DD755", false),
+        ].iter() {
+            assert_eq!(is_valid_nickname(i), *o, "{:?}", i);
+        }
+    }
+
+    #[test]
+    fn test_is_valid_feature() {
+        for (i, o) in [
+            ("EXTENDED_EVENTS", true),
+            ("VERBOSE_NAMES", true),
+            ("FEATURE2", true),
+            ("", false),
+            ("extended_events", false),
+            ("EXTENDED EVENTS", false),
+            ("EXTENDED_EVENTS\r\nSIGNAL SHUTDOWN", false),
+        ].iter() {
+            assert_eq!(is_valid_feature(i), *o, "{:?}", i);
+        }
+    }
+}
@@ -0,0 +1,9 @@
+#!/bin/sh
+# checks that crate builds and tests pass with each subset of features, not only default ones
+# warnings are denied, since unused code in some subset usually means that cfg gate is missing
+set -e
+export RUSTFLAGS="-Dwarnings"
+for features in "" "control" "v3" "serialize" "v3,control" "serialize,v3" "serialize,control" "control,tracing"; do
+    echo "Testing with features: \"$features\""
+    cargo test --no-default-features --features "$features"
+done